@@ -0,0 +1,292 @@
+//! Everything the server knows about a single document, recomputed from scratch on each change.
+//!
+//! Nothing in here speaks LSP: positions are byte offsets into the document, leaving the
+//! protocol's line/column encoding to [`crate::convert`].
+
+use elysium::{
+    ast::{self, validation},
+    eval::Overflow,
+    hir::{self, Database, HirExpr},
+    trimmed_range, Diagnostic, SyntaxKind, SyntaxNode, SyntaxToken,
+};
+use rowan::{TextRange, TextSize};
+
+#[derive(Debug)]
+pub struct Analysis {
+    root: SyntaxNode,
+    db: Database,
+    diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Variable,
+    Function,
+}
+
+/// A definition to list in the document outline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The whole definition.
+    pub range: TextRange,
+    pub name_range: TextRange,
+    /// Definitions nested in a function's body.
+    pub children: Vec<Self>,
+}
+
+impl Analysis {
+    /// Parses, validates, lowers, resolves, type checks and folds `text`, collecting
+    /// diagnostics from each stage.
+    pub fn new(text: &str) -> Self {
+        let parse = elysium::parse(text);
+        let root = parse.syntax();
+
+        let mut diagnostics = parse.diagnostics();
+        diagnostics.extend(
+            validation::validate(&root)
+                .into_iter()
+                .map(Diagnostic::from),
+        );
+
+        let (mut db, stmts) = ast::Root::cast(root.clone())
+            .map(|ast| hir::lower(&ast))
+            .unwrap_or_default();
+        diagnostics.extend(
+            hir::resolve(&mut db, &stmts)
+                .into_iter()
+                .map(Diagnostic::from),
+        );
+        diagnostics.extend(
+            hir::infer(&db, &stmts)
+                .errors()
+                .iter()
+                .cloned()
+                .map(Diagnostic::from),
+        );
+        diagnostics.extend(fold_warnings(&root));
+
+        Self {
+            root,
+            db,
+            diagnostics,
+        }
+    }
+
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Markdown describing what the name or literal at `offset` is, and the range it covers.
+    pub fn hover(&self, offset: TextSize) -> Option<(TextRange, String)> {
+        let source_map = &self.db.source_map;
+        let idx = source_map.expr_at_offset(&self.root, offset)?;
+        let range = source_map.expr_range(idx)?;
+
+        let description = match &self.db.exprs[idx] {
+            HirExpr::LocalRef(local) => {
+                let name = &self.db.locals[*local].name;
+                let kind = source_map.local_syntax(*local)?.kind();
+                if kind == SyntaxKind::Param {
+                    format!("{name} (parameter)")
+                } else {
+                    format!("let {name}")
+                }
+            }
+            HirExpr::FnRef(function) => {
+                let function = &self.db.fns[*function];
+                let params: Vec<_> = function
+                    .params
+                    .iter()
+                    .map(|param| self.db.locals[*param].name.as_str())
+                    .collect();
+                format!("fn {}({})", function.name, params.join(", "))
+            }
+            HirExpr::Literal(literal) => literal.to_string(),
+            _ => return None,
+        };
+
+        Some((range, format!("```elysium\n{description}\n```")))
+    }
+
+    /// The name of the definition that the reference at `offset` resolves to.
+    pub fn definition(&self, offset: TextSize) -> Option<TextRange> {
+        let source_map = &self.db.source_map;
+        let idx = source_map.expr_at_offset(&self.root, offset)?;
+
+        match self.db.exprs[idx] {
+            HirExpr::LocalRef(local) => source_map.local_range(local),
+            HirExpr::FnRef(function) => source_map.fn_range(function),
+            _ => None,
+        }
+    }
+
+    /// Top-level definitions, with the definitions inside each function nested under it.
+    pub fn symbols(&self) -> Vec<Symbol> {
+        ast::Root::cast(self.root.clone())
+            .map(|root| symbols(root.stmts()))
+            .unwrap_or_default()
+    }
+}
+
+/// Warnings from constant folding, for evaluation with [`Overflow::Checked`]. Folding rewrites
+/// expressions in place, so it gets HIR lowered just for it, leaving the analysis's own matching
+/// the source.
+fn fold_warnings(root: &SyntaxNode) -> Vec<Diagnostic> {
+    let Some(ast) = ast::Root::cast(root.clone()) else {
+        return Vec::new();
+    };
+    let (mut db, stmts) = hir::lower(&ast);
+    hir::resolve(&mut db, &stmts);
+
+    hir::fold(&mut db, &stmts, Overflow::Checked)
+        .into_iter()
+        .map(Diagnostic::from)
+        .collect()
+}
+
+fn symbols(stmts: impl Iterator<Item = ast::Stmt>) -> Vec<Symbol> {
+    stmts
+        .filter_map(|stmt| match stmt {
+            ast::Stmt::VariableDef(var_def) => {
+                let name = var_def.name()?;
+                Some(symbol(
+                    SymbolKind::Variable,
+                    var_def.syntax(),
+                    &name,
+                    Vec::new(),
+                ))
+            }
+            ast::Stmt::FnDef(fn_def) => {
+                let children = fn_def
+                    .body()
+                    .map(|body| symbols(body.stmts()))
+                    .unwrap_or_default();
+                let name = fn_def.name()?;
+                Some(symbol(
+                    SymbolKind::Function,
+                    fn_def.syntax(),
+                    &name,
+                    children,
+                ))
+            }
+            ast::Stmt::Expr(_) => None,
+        })
+        .collect()
+}
+
+fn symbol(
+    kind: SymbolKind,
+    node: &SyntaxNode,
+    name: &SyntaxToken,
+    children: Vec<Symbol>,
+) -> Symbol {
+    Symbol {
+        name: name.text().to_owned(),
+        kind,
+        range: trimmed_range(node),
+        name_range: name.text_range(),
+        children,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: u32, end: u32) -> TextRange {
+        TextRange::new(start.into(), end.into())
+    }
+
+    #[test]
+    fn collects_diagnostics_from_every_stage() {
+        let analysis = Analysis::new("let a = 99999999999999999999\nlet b = c\nlet d =");
+        let codes: Vec<_> = analysis.diagnostics().iter().map(|d| d.code).collect();
+
+        assert_eq!(codes, ["E0001", "E0002", "E0003"]);
+    }
+
+    #[test]
+    fn folding_warns_without_changing_hover() {
+        let analysis = Analysis::new("let a = 1\na * 1 + 1 / 0");
+        let codes: Vec<_> = analysis.diagnostics().iter().map(|d| d.code).collect();
+
+        assert_eq!(codes, ["W0001"]);
+        assert_eq!(
+            analysis.hover(10.into()),
+            Some((range(10, 11), "```elysium\nlet a\n```".into()))
+        );
+    }
+
+    #[test]
+    fn hover_local() {
+        let analysis = Analysis::new("let a = 1\na");
+
+        assert_eq!(
+            analysis.hover(10.into()),
+            Some((range(10, 11), "```elysium\nlet a\n```".into()))
+        );
+    }
+
+    #[test]
+    fn hover_param_and_fn() {
+        let analysis = Analysis::new("fn add(x, y) { x + y }\nadd(1, 2)");
+
+        assert_eq!(
+            analysis.hover(15.into()).map(|(_, text)| text),
+            Some("```elysium\nx (parameter)\n```".into())
+        );
+        assert_eq!(
+            analysis.hover(24.into()).map(|(_, text)| text),
+            Some("```elysium\nfn add(x, y)\n```".into())
+        );
+    }
+
+    #[test]
+    fn no_hover_on_unresolved_name() {
+        let analysis = Analysis::new("a");
+
+        assert_eq!(analysis.hover(0.into()), None);
+    }
+
+    #[test]
+    fn definition_of_local_and_fn() {
+        let analysis = Analysis::new("let a = 1\nfn f() { a }\nf()");
+
+        assert_eq!(analysis.definition(19.into()), Some(range(4, 5)));
+        assert_eq!(analysis.definition(23.into()), Some(range(13, 14)));
+        assert_eq!(analysis.definition(8.into()), None);
+    }
+
+    #[test]
+    fn nested_symbols() {
+        let analysis = Analysis::new("let a = 1\nfn f(x) {\n  let b = x\n  b\n}\na");
+
+        assert_eq!(
+            analysis.symbols(),
+            [
+                Symbol {
+                    name: "a".into(),
+                    kind: SymbolKind::Variable,
+                    range: range(0, 9),
+                    name_range: range(4, 5),
+                    children: Vec::new(),
+                },
+                Symbol {
+                    name: "f".into(),
+                    kind: SymbolKind::Function,
+                    range: range(10, 37),
+                    name_range: range(13, 14),
+                    children: vec![Symbol {
+                        name: "b".into(),
+                        kind: SymbolKind::Variable,
+                        range: range(22, 31),
+                        name_range: range(26, 27),
+                        children: Vec::new(),
+                    }],
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,109 @@
+//! Conversions between elysium's byte offsets and the protocol's UTF-16 positions.
+
+use crate::analysis::{Symbol, SymbolKind};
+use elysium::{Diagnostic, LineIndex, Severity, WideLineCol};
+use lsp_types::{
+    DiagnosticRelatedInformation, DiagnosticSeverity, DocumentSymbol, Location, NumberOrString,
+    Position, Range, Url,
+};
+use rowan::{TextRange, TextSize};
+
+pub fn offset(line_index: &LineIndex, position: Position) -> Option<TextSize> {
+    let line_col = line_index.to_utf8(WideLineCol {
+        line: position.line,
+        col: position.character,
+    });
+    line_index.offset(line_col)
+}
+
+pub fn position(line_index: &LineIndex, offset: TextSize) -> Position {
+    let WideLineCol { line, col } = line_index.to_wide(line_index.line_col(offset));
+    Position::new(line, col)
+}
+
+pub fn range(line_index: &LineIndex, range: TextRange) -> Range {
+    Range::new(
+        position(line_index, range.start()),
+        position(line_index, range.end()),
+    )
+}
+
+pub fn diagnostic(
+    line_index: &LineIndex,
+    uri: &Url,
+    diagnostic: &Diagnostic,
+) -> lsp_types::Diagnostic {
+    let related_information = diagnostic
+        .labels
+        .iter()
+        .map(|label| DiagnosticRelatedInformation {
+            location: Location::new(uri.clone(), range(line_index, label.range)),
+            message: label.message.clone(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut message = diagnostic.message.clone();
+    for note in &diagnostic.notes {
+        message.push_str("\nnote: ");
+        message.push_str(note);
+    }
+
+    lsp_types::Diagnostic {
+        range: range(line_index, diagnostic.range),
+        severity: Some(match diagnostic.severity {
+            Severity::Warning => DiagnosticSeverity::WARNING,
+            Severity::Error => DiagnosticSeverity::ERROR,
+        }),
+        code: Some(NumberOrString::String(diagnostic.code.to_owned())),
+        source: Some("elysium".to_owned()),
+        message,
+        related_information: (!related_information.is_empty()).then_some(related_information),
+        ..lsp_types::Diagnostic::default()
+    }
+}
+
+#[allow(deprecated)] // `DocumentSymbol::deprecated` has to be given even though it is unused.
+pub fn document_symbol(line_index: &LineIndex, symbol: Symbol) -> DocumentSymbol {
+    let children = symbol
+        .children
+        .into_iter()
+        .map(|child| document_symbol(line_index, child))
+        .collect::<Vec<_>>();
+
+    DocumentSymbol {
+        name: symbol.name,
+        detail: None,
+        kind: match symbol.kind {
+            SymbolKind::Variable => lsp_types::SymbolKind::VARIABLE,
+            SymbolKind::Function => lsp_types::SymbolKind::FUNCTION,
+        },
+        tags: None,
+        deprecated: None,
+        range: range(line_index, symbol.range),
+        selection_range: range(line_index, symbol.name_range),
+        children: (!children.is_empty()).then_some(children),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use elysium::LineCol;
+
+    #[test]
+    fn positions_count_utf16_units() {
+        let line_index = LineIndex::new("let 𝕏 = 1\nlet é = 𝕏");
+
+        assert_eq!(position(&line_index, 8.into()), Position::new(0, 6));
+        assert_eq!(position(&line_index, 22.into()), Position::new(1, 8));
+        assert_eq!(offset(&line_index, Position::new(1, 8)), Some(22.into()));
+        assert_eq!(line_index.line_col(22.into()), LineCol { line: 1, col: 9 });
+    }
+
+    #[test]
+    fn offset_of_missing_line() {
+        let line_index = LineIndex::new("a");
+
+        assert_eq!(offset(&line_index, Position::new(3, 0)), None);
+    }
+}
@@ -0,0 +1,520 @@
+//! Language server for elysium, speaking LSP over any [`Connection`].
+//!
+//! Documents are re-analysed from scratch whenever they change, and diagnostics are pushed to
+//! the client after every open and change.
+
+mod analysis;
+mod convert;
+
+use analysis::Analysis;
+use elysium::LineIndex;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
+use lsp_types::{
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as LspNotification, PublishDiagnostics,
+    },
+    request::{DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as LspRequest},
+    DocumentSymbolResponse, GotoDefinitionResponse, Hover, HoverContents, HoverProviderCapability,
+    Location, MarkupContent, MarkupKind, OneOf, PublishDiagnosticsParams, ServerCapabilities,
+    TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind, Url,
+};
+use std::{collections::HashMap, error::Error};
+
+pub type Result<T, E = Box<dyn Error + Send + Sync>> = std::result::Result<T, E>;
+
+pub fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        ..ServerCapabilities::default()
+    }
+}
+
+/// Performs the initialize handshake, then serves requests until the client shuts down.
+///
+/// # Errors
+///
+/// Returns an error if the client breaks the protocol or disconnects.
+pub fn run(connection: &Connection) -> Result<()> {
+    connection.initialize(serde_json::to_value(capabilities())?)?;
+    Server::default().main_loop(connection)
+}
+
+struct Document {
+    line_index: LineIndex,
+    analysis: Analysis,
+}
+
+impl Document {
+    fn new(text: &str) -> Self {
+        Self {
+            line_index: LineIndex::new(text),
+            analysis: Analysis::new(text),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Url, Document>,
+}
+
+impl Server {
+    fn main_loop(&mut self, connection: &Connection) -> Result<()> {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    let response = self.handle_request(request);
+                    connection.sender.send(response.into())?;
+                }
+                Message::Notification(notification) => {
+                    let method = notification.method.clone();
+                    match self.handle_notification(notification) {
+                        Ok(Some(uri)) => connection
+                            .sender
+                            .send(self.publish_diagnostics(uri).into())?,
+                        Ok(None) => {}
+                        // There is no response to carry the error back, and one bad notification
+                        // is no reason to stop serving the rest.
+                        Err(error) => {
+                            eprintln!("ignoring malformed `{method}` notification: {error}");
+                        }
+                    }
+                }
+                Message::Response(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+        let result = match request.method.as_str() {
+            HoverRequest::METHOD => handle::<HoverRequest>(request, |params| {
+                self.hover(&params.text_document_position_params)
+            }),
+            GotoDefinition::METHOD => handle::<GotoDefinition>(request, |params| {
+                self.definition(&params.text_document_position_params)
+            }),
+            DocumentSymbolRequest::METHOD => handle::<DocumentSymbolRequest>(request, |params| {
+                self.symbols(&params.text_document.uri)
+            }),
+            method => {
+                return Response::new_err(
+                    id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("unhandled method `{method}`"),
+                )
+            }
+        };
+
+        result.unwrap_or_else(|error| {
+            Response::new_err(id, ErrorCode::InvalidParams as i32, error.to_string())
+        })
+    }
+
+    /// Applies a document notification, returning the document whose diagnostics changed.
+    fn handle_notification(&mut self, notification: Notification) -> Result<Option<Url>> {
+        let uri = match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params = notification
+                    .extract::<<DidOpenTextDocument as LspNotification>::Params>(
+                        DidOpenTextDocument::METHOD,
+                    )?;
+                let document = params.text_document;
+                self.documents
+                    .insert(document.uri.clone(), Document::new(&document.text));
+                document.uri
+            }
+            DidChangeTextDocument::METHOD => {
+                let params = notification
+                    .extract::<<DidChangeTextDocument as LspNotification>::Params>(
+                        DidChangeTextDocument::METHOD,
+                    )?;
+                // We only ask for full syncs, so the last change holds the whole text.
+                let Some(change) = params.content_changes.last() else {
+                    return Ok(None);
+                };
+                let uri = params.text_document.uri;
+                self.documents
+                    .insert(uri.clone(), Document::new(&change.text));
+                uri
+            }
+            DidCloseTextDocument::METHOD => {
+                let params = notification
+                    .extract::<<DidCloseTextDocument as LspNotification>::Params>(
+                        DidCloseTextDocument::METHOD,
+                    )?;
+                let uri = params.text_document.uri;
+                self.documents.remove(&uri);
+                uri
+            }
+            _ => return Ok(None),
+        };
+
+        Ok(Some(uri))
+    }
+
+    /// Diagnostics for `uri`, which are cleared once the document is closed.
+    fn publish_diagnostics(&self, uri: Url) -> Notification {
+        let diagnostics = self
+            .documents
+            .get(&uri)
+            .map(|document| {
+                document
+                    .analysis
+                    .diagnostics()
+                    .iter()
+                    .map(|diagnostic| convert::diagnostic(&document.line_index, &uri, diagnostic))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Notification::new(
+            PublishDiagnostics::METHOD.to_owned(),
+            PublishDiagnosticsParams::new(uri, diagnostics, None),
+        )
+    }
+
+    fn hover(&self, params: &TextDocumentPositionParams) -> Option<Hover> {
+        let (document, offset) = self.locate(params)?;
+        let (range, markdown) = document.analysis.hover(offset)?;
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: markdown,
+            }),
+            range: Some(convert::range(&document.line_index, range)),
+        })
+    }
+
+    fn definition(&self, params: &TextDocumentPositionParams) -> Option<GotoDefinitionResponse> {
+        let (document, offset) = self.locate(params)?;
+        let range = document.analysis.definition(offset)?;
+
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            params.text_document.uri.clone(),
+            convert::range(&document.line_index, range),
+        )))
+    }
+
+    fn symbols(&self, uri: &Url) -> Option<DocumentSymbolResponse> {
+        let document = self.documents.get(uri)?;
+        let symbols = document
+            .analysis
+            .symbols()
+            .into_iter()
+            .map(|symbol| convert::document_symbol(&document.line_index, symbol))
+            .collect();
+
+        Some(DocumentSymbolResponse::Nested(symbols))
+    }
+
+    fn locate(&self, params: &TextDocumentPositionParams) -> Option<(&Document, rowan::TextSize)> {
+        let document = self.documents.get(&params.text_document.uri)?;
+        let offset = convert::offset(&document.line_index, params.position)?;
+        Some((document, offset))
+    }
+}
+
+/// Decodes the params of `request`, runs `handler` and encodes its result as a response.
+fn handle<R>(request: Request, handler: impl FnOnce(R::Params) -> R::Result) -> Result<Response>
+where
+    R: LspRequest,
+{
+    let (id, params): (RequestId, R::Params) = request.extract(R::METHOD)?;
+    Ok(Response::new_ok(id, handler(params)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lsp_types::{
+        notification::{Exit, Initialized},
+        request::{Initialize, Shutdown},
+        DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+        DocumentSymbolParams, GotoDefinitionParams, HoverParams, InitializeParams,
+        InitializedParams, NumberOrString, PartialResultParams, Position, Range,
+        TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+        VersionedTextDocumentIdentifier, WorkDoneProgressParams,
+    };
+    use std::{
+        thread::{self, JoinHandle},
+        time::Duration,
+    };
+
+    /// Drives a server running on another thread through an in-memory connection.
+    struct Client {
+        connection: Connection,
+        server: Option<JoinHandle<Result<()>>>,
+        next_id: i32,
+    }
+
+    impl Client {
+        fn start() -> Self {
+            let (server, connection) = Connection::memory();
+            let server = thread::spawn(move || run(&server));
+
+            let mut client = Self {
+                connection,
+                server: Some(server),
+                next_id: 0,
+            };
+            client.request::<Initialize>(InitializeParams::default());
+            client.notify::<Initialized>(InitializedParams {});
+            client
+        }
+
+        fn send_request(&mut self, method: &str, params: serde_json::Value) -> Response {
+            self.next_id += 1;
+            let id = RequestId::from(self.next_id);
+            self.send(Request::new(id.clone(), method.to_owned(), params).into());
+
+            match self.recv() {
+                Message::Response(response) if response.id == id => response,
+                message => panic!("expected a response to {id}, got {message:?}"),
+            }
+        }
+
+        fn request<R: LspRequest>(&mut self, params: R::Params) -> R::Result {
+            let response = self.send_request(R::METHOD, serde_json::to_value(params).unwrap());
+            assert!(response.error.is_none(), "{:?}", response.error);
+            serde_json::from_value(response.result.unwrap_or_default()).unwrap()
+        }
+
+        fn notify<N: LspNotification>(&self, params: N::Params) {
+            self.send(Notification::new(N::METHOD.to_owned(), params).into());
+        }
+
+        fn notification<N: LspNotification>(&self) -> N::Params {
+            match self.recv() {
+                Message::Notification(notification) => notification.extract(N::METHOD).unwrap(),
+                message => panic!("expected {}, got {message:?}", N::METHOD),
+            }
+        }
+
+        fn send(&self, message: Message) {
+            self.connection.sender.send(message).unwrap();
+        }
+
+        fn recv(&self) -> Message {
+            self.connection
+                .receiver
+                .recv_timeout(Duration::from_secs(5))
+                .unwrap()
+        }
+
+        fn open(&self, text: &str) -> PublishDiagnosticsParams {
+            self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+                text_document: TextDocumentItem::new(uri(), "elysium".into(), 0, text.into()),
+            });
+            self.notification::<PublishDiagnostics>()
+        }
+    }
+
+    impl Drop for Client {
+        fn drop(&mut self) {
+            if thread::panicking() {
+                return;
+            }
+            self.request::<Shutdown>(());
+            self.notify::<Exit>(());
+            self.server.take().unwrap().join().unwrap().unwrap();
+        }
+    }
+
+    fn uri() -> Url {
+        Url::parse("file:///test.ely").unwrap()
+    }
+
+    fn position(line: u32, character: u32) -> TextDocumentPositionParams {
+        TextDocumentPositionParams::new(
+            TextDocumentIdentifier::new(uri()),
+            Position::new(line, character),
+        )
+    }
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+    }
+
+    #[test]
+    fn open_publishes_diagnostics() {
+        let client = Client::start();
+        let published = client.open("let a = 1\nlet b = c");
+
+        assert_eq!(published.uri, uri());
+        assert_eq!(published.diagnostics.len(), 1);
+
+        let diagnostic = &published.diagnostics[0];
+        assert_eq!(diagnostic.range, range((1, 8), (1, 9)));
+        assert_eq!(
+            diagnostic.code,
+            Some(NumberOrString::String("E0003".into()))
+        );
+        assert_eq!(diagnostic.message, "cannot find `c` in this scope");
+    }
+
+    #[test]
+    fn labels_become_related_information() {
+        let client = Client::start();
+        let published = client.open("let a = b\nlet b = 1");
+
+        let related = published.diagnostics[0]
+            .related_information
+            .as_ref()
+            .unwrap();
+        assert_eq!(
+            related[0].location,
+            Location::new(uri(), range((1, 4), (1, 5)))
+        );
+        assert_eq!(related[0].message, "defined here");
+    }
+
+    #[test]
+    fn malformed_notification_is_skipped() {
+        let client = Client::start();
+        client.send(
+            Notification::new(
+                DidOpenTextDocument::METHOD.to_owned(),
+                serde_json::json!({ "textDocument": 1 }),
+            )
+            .into(),
+        );
+
+        assert_eq!(client.open("let a = b").diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn change_republishes_diagnostics() {
+        let client = Client::start();
+        assert_eq!(client.open("let a = (1").diagnostics.len(), 1);
+
+        client.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri(), 1),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: "let a = (1)".into(),
+            }],
+        });
+
+        assert!(client
+            .notification::<PublishDiagnostics>()
+            .diagnostics
+            .is_empty());
+    }
+
+    #[test]
+    fn close_clears_diagnostics() {
+        let client = Client::start();
+        client.open("a");
+
+        client.notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier::new(uri()),
+        });
+
+        assert!(client
+            .notification::<PublishDiagnostics>()
+            .diagnostics
+            .is_empty());
+    }
+
+    #[test]
+    fn hover() {
+        let mut client = Client::start();
+        client.open("let a = 1\na + 2");
+
+        let hover = client
+            .request::<HoverRequest>(HoverParams {
+                text_document_position_params: position(1, 0),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            })
+            .unwrap();
+
+        assert_eq!(hover.range, Some(range((1, 0), (1, 1))));
+        assert_eq!(
+            hover.contents,
+            HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: "```elysium\nlet a\n```".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn goto_definition() {
+        let mut client = Client::start();
+        client.open("let a = 1\nfn f(x) { x + a }");
+
+        let definition = |client: &mut Client, character| {
+            client.request::<GotoDefinition>(GotoDefinitionParams {
+                text_document_position_params: position(1, character),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            })
+        };
+
+        assert_eq!(
+            definition(&mut client, 10),
+            Some(GotoDefinitionResponse::Scalar(Location::new(
+                uri(),
+                range((1, 5), (1, 6))
+            )))
+        );
+        assert_eq!(
+            definition(&mut client, 14),
+            Some(GotoDefinitionResponse::Scalar(Location::new(
+                uri(),
+                range((0, 4), (0, 5))
+            )))
+        );
+        assert_eq!(definition(&mut client, 0), None);
+    }
+
+    #[test]
+    fn document_symbols() {
+        let mut client = Client::start();
+        client.open("let a = 1\nfn f() {\n  let b = a\n}");
+
+        let Some(DocumentSymbolResponse::Nested(symbols)) = client
+            .request::<DocumentSymbolRequest>(DocumentSymbolParams {
+                text_document: TextDocumentIdentifier::new(uri()),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            })
+        else {
+            panic!("expected nested symbols")
+        };
+
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[0].name, "a");
+        assert_eq!(symbols[0].kind, lsp_types::SymbolKind::VARIABLE);
+        assert_eq!(symbols[0].selection_range, range((0, 4), (0, 5)));
+        assert_eq!(symbols[1].name, "f");
+        assert_eq!(symbols[1].range, range((1, 0), (3, 1)));
+
+        let children = symbols[1].children.as_ref().unwrap();
+        assert_eq!(children[0].name, "b");
+        assert_eq!(children[0].range, range((2, 2), (2, 11)));
+    }
+
+    #[test]
+    fn unknown_requests_are_rejected() {
+        let mut client = Client::start();
+        let response = client.send_request("textDocument/completion", serde_json::Value::Null);
+
+        assert_eq!(
+            response.error.map(|error| error.code),
+            Some(ErrorCode::MethodNotFound as i32)
+        );
+    }
+}
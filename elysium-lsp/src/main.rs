@@ -0,0 +1,9 @@
+//! Language server for elysium over stdio.
+
+fn main() -> elysium_lsp::Result<()> {
+    let (connection, io_threads) = lsp_server::Connection::stdio();
+    elysium_lsp::run(&connection)?;
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
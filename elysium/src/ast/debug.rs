@@ -0,0 +1,255 @@
+//! Readable dump of the AST, for `elysium dump --stage ast`.
+
+use super::{BlockExpr, Expr, Root, Stmt, TypeRef};
+use crate::SyntaxToken;
+use std::fmt::{self, Write};
+
+impl Root {
+    /// Prints each statement as a tree with one node per line, showing the names, operators,
+    /// literals and type annotations the AST gives access to. Parts missing because of parse
+    /// errors show as `<missing>`.
+    pub fn debug_tree(&self) -> String {
+        let mut printer = Printer::default();
+        for stmt in self.stmts() {
+            printer.stmt(stmt);
+        }
+        printer.out
+    }
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    depth: usize,
+}
+
+impl Printer {
+    fn line(&mut self, text: fmt::Arguments) {
+        let _ = writeln!(self.out, "{:indent$}{text}", "", indent = self.depth * 2);
+    }
+
+    /// Runs `f` with lines indented one level further.
+    fn nested(&mut self, f: impl FnOnce(&mut Self)) {
+        self.depth += 1;
+        f(self);
+        self.depth -= 1;
+    }
+
+    fn stmt(&mut self, stmt: Stmt) {
+        match stmt {
+            Stmt::VariableDef(def) => {
+                self.line(format_args!(
+                    "VariableDef {}{}",
+                    name(def.name()),
+                    annotation(def.ty())
+                ));
+                self.nested(|p| p.expr(def.value()));
+            }
+            Stmt::FnDef(def) => {
+                let ret = def
+                    .ret_ty()
+                    .map_or_else(String::new, |ty| format!(" -> {}", type_ref(&ty)));
+                self.line(format_args!("FnDef {}{ret}", name(def.name())));
+                self.nested(|p| {
+                    for param in def.param_list().into_iter().flat_map(|list| list.params()) {
+                        p.line(format_args!(
+                            "Param {}{}",
+                            name(param.name()),
+                            annotation(param.ty())
+                        ));
+                    }
+                    p.block(def.body());
+                });
+            }
+            Stmt::Expr(expr) => self.expr(Some(expr)),
+        }
+    }
+
+    fn block(&mut self, block: Option<BlockExpr>) {
+        let Some(block) = block else {
+            self.line(format_args!("<missing>"));
+            return;
+        };
+
+        self.line(format_args!("BlockExpr"));
+        self.nested(|p| {
+            for stmt in block.stmts() {
+                p.stmt(stmt);
+            }
+            if let Some(tail) = block.tail_expr() {
+                p.expr(Some(tail));
+            }
+        });
+    }
+
+    fn expr(&mut self, expr: Option<Expr>) {
+        let Some(expr) = expr else {
+            self.line(format_args!("<missing>"));
+            return;
+        };
+
+        match expr {
+            Expr::AssignExpr(assign) => {
+                self.line(format_args!("AssignExpr {}", name(assign.op())));
+                self.nested(|p| {
+                    p.expr(assign.place());
+                    p.expr(assign.value());
+                });
+            }
+            Expr::BinaryExpr(binary) => {
+                self.line(format_args!("BinaryExpr {}", name(binary.op())));
+                self.nested(|p| {
+                    p.expr(binary.lhs());
+                    p.expr(binary.rhs());
+                });
+            }
+            Expr::BlockExpr(block) => self.block(Some(block)),
+            Expr::BreakExpr(break_expr) => {
+                self.line(format_args!("BreakExpr"));
+                if let Some(value) = break_expr.value() {
+                    self.nested(|p| p.expr(Some(value)));
+                }
+            }
+            Expr::CallExpr(call) => {
+                self.line(format_args!("CallExpr"));
+                self.nested(|p| {
+                    p.expr(call.callee());
+                    for arg in call.arg_list().into_iter().flat_map(|list| list.args()) {
+                        p.expr(Some(arg));
+                    }
+                });
+            }
+            Expr::ContinueExpr(_) => self.line(format_args!("ContinueExpr")),
+            Expr::IfExpr(if_expr) => {
+                self.line(format_args!("IfExpr"));
+                self.nested(|p| {
+                    p.expr(if_expr.condition());
+                    p.block(if_expr.then_branch());
+                    if let Some(else_branch) = if_expr.else_branch() {
+                        p.expr(Some(else_branch));
+                    }
+                });
+            }
+            Expr::Literal(literal) => {
+                self.line(format_args!(
+                    "Literal {}",
+                    name(literal.inner().first_token())
+                ));
+            }
+            Expr::LoopExpr(loop_expr) => {
+                self.line(format_args!("LoopExpr"));
+                self.nested(|p| p.block(loop_expr.body()));
+            }
+            Expr::ParenExpr(paren) => {
+                self.line(format_args!("ParenExpr"));
+                self.nested(|p| p.expr(paren.expr()));
+            }
+            Expr::UnaryExpr(unary) => {
+                self.line(format_args!("UnaryExpr {}", name(unary.op())));
+                self.nested(|p| p.expr(unary.expr()));
+            }
+            Expr::VariableRef(var) => self.line(format_args!("VariableRef {}", name(var.name()))),
+            Expr::WhileExpr(while_expr) => {
+                self.line(format_args!("WhileExpr"));
+                self.nested(|p| {
+                    p.expr(while_expr.condition());
+                    p.block(while_expr.body());
+                });
+            }
+        }
+    }
+}
+
+/// The text of a name, operator or literal token.
+fn name(token: Option<SyntaxToken>) -> String {
+    token.map_or_else(|| "<missing>".to_owned(), |token| token.text().to_owned())
+}
+
+fn annotation(ty: Option<TypeRef>) -> String {
+    ty.map_or_else(String::new, |ty| format!(": {}", type_ref(&ty)))
+}
+
+fn type_ref(ty: &TypeRef) -> String {
+    if ty.is_unit() {
+        "()".to_owned()
+    } else if ty.is_fn() {
+        let params: Vec<_> = ty.params().map(|param| type_ref(&param)).collect();
+        let ret = ty
+            .ret()
+            .map_or_else(|| "<missing>".to_owned(), |ret| type_ref(&ret));
+        format!("fn({}) -> {ret}", params.join(", "))
+    } else {
+        name(ty.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Root, parser::parse};
+    use expect_test::{expect, Expect};
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn check(input: &str, expected: Expect) {
+        let root = Root::cast(parse(input).syntax()).unwrap();
+        expected.assert_eq(&root.debug_tree());
+    }
+
+    #[test]
+    fn debug_definitions() {
+        check(
+            "let a: Int = -1\nfn f(x: fn(Int) -> (), y) -> Bool { let z = y; x(z) }",
+            expect![[r"
+                VariableDef a: Int
+                  UnaryExpr -
+                    Literal 1
+                FnDef f -> Bool
+                  Param x: fn(Int) -> ()
+                  Param y
+                  BlockExpr
+                    VariableDef z
+                      VariableRef y
+                    CallExpr
+                      VariableRef x
+                      VariableRef z
+            "]],
+        );
+    }
+
+    #[test]
+    fn debug_control_flow() {
+        check(
+            "a += if b { 1 } else { (2) }\nloop { while c { break }; continue }",
+            expect![[r"
+                AssignExpr +=
+                  VariableRef a
+                  IfExpr
+                    VariableRef b
+                    BlockExpr
+                      Literal 1
+                    BlockExpr
+                      ParenExpr
+                        Literal 2
+                LoopExpr
+                  BlockExpr
+                    WhileExpr
+                      VariableRef c
+                      BlockExpr
+                        BreakExpr
+                    ContinueExpr
+            "]],
+        );
+    }
+
+    #[test]
+    fn debug_missing_parts() {
+        check(
+            "let = 1 +\nfn",
+            expect![[r"
+            VariableDef <missing>
+              <missing>
+            FnDef <missing>
+              <missing>
+        "]],
+        );
+    }
+}
@@ -1,6 +1,9 @@
+mod debug;
 mod tree;
 pub mod validation;
 
 pub use tree::{
-    BinaryExpr, Expr, Literal, ParenExpr, Root, Stmt, UnaryExpr, VariableDef, VariableRef,
+    ArgList, AssignExpr, BinaryExpr, BlockExpr, BreakExpr, CallExpr, ContinueExpr, Expr, FnDef,
+    IfExpr, Literal, LiteralKind, LoopExpr, Param, ParamList, ParenExpr, Root, Stmt, TypeRef,
+    UnaryExpr, VariableDef, VariableRef, WhileExpr,
 };
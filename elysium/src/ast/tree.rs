@@ -1,14 +1,20 @@
 //! Functionality for the AST built on top of the Rowan CST.
 
 use crate::{
+    lexer::{self, EscapeError},
     syntax::{SyntaxElement, SyntaxKind, SyntaxToken},
     SyntaxNode,
 };
+use rowan::{TextRange, TextSize};
 
 #[derive(Debug)]
 pub struct VariableDef(SyntaxNode);
 
 impl VariableDef {
+    pub const fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+
     pub fn name(&self) -> Option<SyntaxToken> {
         self.0
             .children_with_tokens()
@@ -16,24 +22,190 @@ impl VariableDef {
             .find(|token| token.kind() == SyntaxKind::Ident)
     }
 
+    /// The annotated type, as in `let x: Int = 3`.
+    pub fn ty(&self) -> Option<TypeRef> {
+        self.0.children().find_map(TypeRef::cast)
+    }
+
     pub fn value(&self) -> Option<Expr> {
         self.0.children().find_map(Expr::cast)
     }
 }
 
+#[derive(Debug)]
+pub struct FnDef(SyntaxNode);
+
+impl FnDef {
+    pub const fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .find(|token| token.kind() == SyntaxKind::Ident)
+    }
+
+    pub fn param_list(&self) -> Option<ParamList> {
+        self.0.children().find_map(ParamList::cast)
+    }
+
+    /// The type after `->`.
+    pub fn ret_ty(&self) -> Option<TypeRef> {
+        self.0.children().find_map(TypeRef::cast)
+    }
+
+    pub fn body(&self) -> Option<BlockExpr> {
+        self.0.children().find_map(BlockExpr::cast)
+    }
+}
+
+#[derive(Debug)]
+pub struct ParamList(SyntaxNode);
+
+impl ParamList {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        if node.kind() == SyntaxKind::ParamList {
+            Some(Self(node))
+        } else {
+            None
+        }
+    }
+
+    pub fn params(&self) -> impl Iterator<Item = Param> {
+        self.0.children().filter_map(Param::cast)
+    }
+}
+
+#[derive(Debug)]
+pub struct Param(SyntaxNode);
+
+impl Param {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        if node.kind() == SyntaxKind::Param {
+            Some(Self(node))
+        } else {
+            None
+        }
+    }
+
+    pub const fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+
+    pub fn name(&self) -> Option<SyntaxToken> {
+        self.0.first_token()
+    }
+
+    pub fn ty(&self) -> Option<TypeRef> {
+        self.0.children().find_map(TypeRef::cast)
+    }
+}
+
+/// A type written in the source: a name, `()`, or a function type like `fn(Int) -> Bool`.
+#[derive(Debug)]
+pub struct TypeRef(SyntaxNode);
+
+impl TypeRef {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        if node.kind() == SyntaxKind::TypeRef {
+            Some(Self(node))
+        } else {
+            None
+        }
+    }
+
+    pub const fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+
+    /// The name of a named type such as `Int`.
+    pub fn name(&self) -> Option<SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .find(|token| token.kind() == SyntaxKind::Ident)
+    }
+
+    pub fn is_fn(&self) -> bool {
+        self.first_token_kind() == Some(SyntaxKind::FnKw)
+    }
+
+    pub fn is_unit(&self) -> bool {
+        self.first_token_kind() == Some(SyntaxKind::LParen)
+    }
+
+    /// The parameter types of a function type.
+    pub fn params(&self) -> impl Iterator<Item = Self> {
+        self.0
+            .children_with_tokens()
+            .take_while(|element| element.kind() != SyntaxKind::Arrow)
+            .filter_map(SyntaxElement::into_node)
+            .filter_map(Self::cast)
+    }
+
+    /// The return type of a function type.
+    pub fn ret(&self) -> Option<Self> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|element| element.kind() != SyntaxKind::Arrow)
+            .filter_map(SyntaxElement::into_node)
+            .find_map(Self::cast)
+    }
+
+    fn first_token_kind(&self) -> Option<SyntaxKind> {
+        self.0.first_token().map(|token| token.kind())
+    }
+}
+
 #[derive(Debug)]
 pub enum Expr {
+    AssignExpr(AssignExpr),
     BinaryExpr(BinaryExpr),
+    BlockExpr(BlockExpr),
+    BreakExpr(BreakExpr),
+    CallExpr(CallExpr),
+    ContinueExpr(ContinueExpr),
+    IfExpr(IfExpr),
     Literal(Literal),
+    LoopExpr(LoopExpr),
     ParenExpr(ParenExpr),
     UnaryExpr(UnaryExpr),
     VariableRef(VariableRef),
+    WhileExpr(WhileExpr),
 }
 
 impl Expr {
+    pub const fn syntax(&self) -> &SyntaxNode {
+        match self {
+            Self::AssignExpr(AssignExpr(node))
+            | Self::BinaryExpr(BinaryExpr(node))
+            | Self::BlockExpr(BlockExpr(node))
+            | Self::BreakExpr(BreakExpr(node))
+            | Self::CallExpr(CallExpr(node))
+            | Self::ContinueExpr(ContinueExpr(node))
+            | Self::IfExpr(IfExpr(node))
+            | Self::Literal(Literal(node))
+            | Self::LoopExpr(LoopExpr(node))
+            | Self::ParenExpr(ParenExpr(node))
+            | Self::UnaryExpr(UnaryExpr(node))
+            | Self::VariableRef(VariableRef(node))
+            | Self::WhileExpr(WhileExpr(node)) => node,
+        }
+    }
+
     pub fn cast(node: SyntaxNode) -> Option<Self> {
         let result = match node.kind() {
             SyntaxKind::InfixExpr => Self::BinaryExpr(BinaryExpr(node)),
+            SyntaxKind::AssignExpr => Self::AssignExpr(AssignExpr(node)),
+            SyntaxKind::BlockExpr => Self::BlockExpr(BlockExpr(node)),
+            SyntaxKind::CallExpr => Self::CallExpr(CallExpr(node)),
+            SyntaxKind::IfExpr => Self::IfExpr(IfExpr(node)),
+            SyntaxKind::WhileExpr => Self::WhileExpr(WhileExpr(node)),
+            SyntaxKind::LoopExpr => Self::LoopExpr(LoopExpr(node)),
+            SyntaxKind::BreakExpr => Self::BreakExpr(BreakExpr(node)),
+            SyntaxKind::ContinueExpr => Self::ContinueExpr(ContinueExpr(node)),
             SyntaxKind::Literal => Self::Literal(Literal(node)),
             SyntaxKind::PrefixExpr => Self::UnaryExpr(UnaryExpr(node)),
             SyntaxKind::VariableRef => Self::VariableRef(VariableRef(node)),
@@ -62,12 +234,249 @@ impl BinaryExpr {
             .find(|token| {
                 matches!(
                     token.kind(),
-                    SyntaxKind::Plus | SyntaxKind::Minus | SyntaxKind::Star | SyntaxKind::Slash
+                    SyntaxKind::Plus
+                        | SyntaxKind::Minus
+                        | SyntaxKind::Star
+                        | SyntaxKind::Slash
+                        | SyntaxKind::EqEq
+                        | SyntaxKind::BangEq
+                        | SyntaxKind::Less
+                        | SyntaxKind::LessEq
+                        | SyntaxKind::Greater
+                        | SyntaxKind::GreaterEq
+                        | SyntaxKind::AmpAmp
+                        | SyntaxKind::PipePipe
                 )
             })
     }
 }
 
+/// `place = value`, or a compound assignment such as `place += value`.
+#[derive(Debug)]
+pub struct AssignExpr(SyntaxNode);
+
+impl AssignExpr {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        if node.kind() == SyntaxKind::AssignExpr {
+            Some(Self(node))
+        } else {
+            None
+        }
+    }
+
+    /// What is being assigned to, which only [`validation`](super::validation) ensures is a
+    /// variable.
+    pub fn place(&self) -> Option<Expr> {
+        self.0.children().find_map(Expr::cast)
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        self.0.children().filter_map(Expr::cast).nth(1)
+    }
+
+    pub fn op(&self) -> Option<SyntaxToken> {
+        self.0
+            .children_with_tokens()
+            .filter_map(SyntaxElement::into_token)
+            .find(|token| {
+                matches!(
+                    token.kind(),
+                    SyntaxKind::Equals
+                        | SyntaxKind::PlusEq
+                        | SyntaxKind::MinusEq
+                        | SyntaxKind::StarEq
+                        | SyntaxKind::SlashEq
+                )
+            })
+    }
+}
+
+#[derive(Debug)]
+pub struct BlockExpr(SyntaxNode);
+
+impl BlockExpr {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        if node.kind() == SyntaxKind::BlockExpr {
+            Some(Self(node))
+        } else {
+            None
+        }
+    }
+
+    /// Statements in the block, excluding the trailing expression returned by
+    /// [`BlockExpr::tail_expr`].
+    pub fn stmts(&self) -> impl Iterator<Item = Stmt> {
+        let mut stmts: Vec<_> = self.0.children().filter_map(Stmt::cast).collect();
+        if matches!(stmts.last(), Some(Stmt::Expr(_))) {
+            stmts.pop();
+        }
+        stmts.into_iter()
+    }
+
+    /// The final expression of the block, which gives the block its value.
+    pub fn tail_expr(&self) -> Option<Expr> {
+        match self.0.children().filter_map(Stmt::cast).last()? {
+            Stmt::Expr(expr) => Some(expr),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CallExpr(SyntaxNode);
+
+impl CallExpr {
+    pub fn callee(&self) -> Option<Expr> {
+        self.0.children().find_map(Expr::cast)
+    }
+
+    pub fn arg_list(&self) -> Option<ArgList> {
+        self.0.children().find_map(ArgList::cast)
+    }
+}
+
+#[derive(Debug)]
+pub struct ArgList(SyntaxNode);
+
+impl ArgList {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        if node.kind() == SyntaxKind::ArgList {
+            Some(Self(node))
+        } else {
+            None
+        }
+    }
+
+    pub fn args(&self) -> impl Iterator<Item = Expr> {
+        self.0.children().filter_map(Expr::cast)
+    }
+}
+
+#[derive(Debug)]
+pub struct IfExpr(SyntaxNode);
+
+impl IfExpr {
+    /// The condition, which the parser never takes to be a bare block: `if { ... }` has a
+    /// missing condition and a then-branch.
+    pub fn condition(&self) -> Option<Expr> {
+        self.before_else()
+            .find(|expr| !matches!(expr, Expr::BlockExpr(_)))
+    }
+
+    pub fn then_branch(&self) -> Option<BlockExpr> {
+        self.before_else().find_map(|expr| match expr {
+            Expr::BlockExpr(block) => Some(block),
+            _ => None,
+        })
+    }
+
+    /// The block after `else`, or the `IfExpr` of an `else if`.
+    pub fn else_branch(&self) -> Option<Expr> {
+        self.0
+            .children_with_tokens()
+            .skip_while(|element| element.kind() != SyntaxKind::ElseKw)
+            .filter_map(SyntaxElement::into_node)
+            .find_map(Expr::cast)
+    }
+
+    fn before_else(&self) -> impl Iterator<Item = Expr> {
+        self.0
+            .children_with_tokens()
+            .take_while(|element| element.kind() != SyntaxKind::ElseKw)
+            .filter_map(SyntaxElement::into_node)
+            .filter_map(Expr::cast)
+    }
+}
+
+#[derive(Debug)]
+pub struct WhileExpr(SyntaxNode);
+
+impl WhileExpr {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        if node.kind() == SyntaxKind::WhileExpr {
+            Some(Self(node))
+        } else {
+            None
+        }
+    }
+
+    /// The condition, which like that of an [`IfExpr`] is never a bare block.
+    pub fn condition(&self) -> Option<Expr> {
+        self.0
+            .children()
+            .filter_map(Expr::cast)
+            .find(|expr| !matches!(expr, Expr::BlockExpr(_)))
+    }
+
+    pub fn body(&self) -> Option<BlockExpr> {
+        self.0.children().find_map(BlockExpr::cast)
+    }
+}
+
+#[derive(Debug)]
+pub struct LoopExpr(SyntaxNode);
+
+impl LoopExpr {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        if node.kind() == SyntaxKind::LoopExpr {
+            Some(Self(node))
+        } else {
+            None
+        }
+    }
+
+    pub fn body(&self) -> Option<BlockExpr> {
+        self.0.children().find_map(BlockExpr::cast)
+    }
+}
+
+#[derive(Debug)]
+pub struct BreakExpr(SyntaxNode);
+
+impl BreakExpr {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        if node.kind() == SyntaxKind::BreakExpr {
+            Some(Self(node))
+        } else {
+            None
+        }
+    }
+
+    pub const fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+
+    pub fn value(&self) -> Option<Expr> {
+        self.0.children().find_map(Expr::cast)
+    }
+}
+
+#[derive(Debug)]
+pub struct ContinueExpr(SyntaxNode);
+
+impl ContinueExpr {
+    pub fn cast(node: SyntaxNode) -> Option<Self> {
+        if node.kind() == SyntaxKind::ContinueExpr {
+            Some(Self(node))
+        } else {
+            None
+        }
+    }
+
+    pub const fn syntax(&self) -> &SyntaxNode {
+        &self.0
+    }
+}
+
+/// The kinds of value a [`Literal`] can spell out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiteralKind {
+    Number,
+    Float,
+    String,
+    Bool,
+}
+
 #[derive(Debug)]
 pub struct Literal(SyntaxNode);
 
@@ -76,8 +485,84 @@ impl Literal {
         &self.0
     }
 
+    pub fn kind(&self) -> Option<LiteralKind> {
+        match self.0.first_token()?.kind() {
+            SyntaxKind::Number => Some(LiteralKind::Number),
+            SyntaxKind::Float => Some(LiteralKind::Float),
+            SyntaxKind::String => Some(LiteralKind::String),
+            SyntaxKind::TrueKw | SyntaxKind::FalseKw => Some(LiteralKind::Bool),
+            _ => None,
+        }
+    }
+
+    /// The value of an integer literal's digits, or `None` for other kinds and integers too
+    /// large for a `u64`. [`validation`](super::validation) rejects those past `i64::MAX`,
+    /// unless the literal is [negated](Self::is_negated) and goes just one past it.
     pub fn parse(&self) -> Option<u64> {
-        self.0.first_token().unwrap().text().parse().ok()
+        let token = self.0.first_token()?;
+        if token.kind() != SyntaxKind::Number {
+            return None;
+        }
+        lexer::parse_int(token.text()).ok()
+    }
+
+    /// Whether this literal is the operand of a prefix `-`, as in `-1`.
+    pub fn is_negated(&self) -> bool {
+        self.0
+            .parent()
+            .filter(|parent| parent.kind() == SyntaxKind::PrefixExpr)
+            .map(UnaryExpr)
+            .and_then(|unary_expr| unary_expr.op())
+            .is_some_and(|op| op.kind() == SyntaxKind::Minus)
+    }
+
+    /// The value of a float literal, or `None` for other kinds and floats that
+    /// [`validation`](super::validation) rejects.
+    pub fn float(&self) -> Option<f64> {
+        let token = self.0.first_token()?;
+        if token.kind() != SyntaxKind::Float {
+            return None;
+        }
+        lexer::parse_float(token.text()).ok()
+    }
+
+    /// The value of a `true` or `false` literal, or `None` for other kinds.
+    pub fn bool(&self) -> Option<bool> {
+        match self.0.first_token()?.kind() {
+            SyntaxKind::TrueKw => Some(true),
+            SyntaxKind::FalseKw => Some(false),
+            _ => None,
+        }
+    }
+
+    /// The value of a string literal with its escapes decoded, or `None` for other kinds and
+    /// strings that [`validation`](super::validation) rejects.
+    pub fn string(&self) -> Option<String> {
+        let (value, errors) = self.unescape()?;
+        errors.is_empty().then_some(value)
+    }
+
+    /// Decodes a string literal, reporting each problem with its range in the source.
+    pub(crate) fn unescape(&self) -> Option<(String, Vec<(EscapeError, TextRange)>)> {
+        let token = self.0.first_token()?;
+        if token.kind() != SyntaxKind::String {
+            return None;
+        }
+
+        let start = token.text_range().start();
+        let (value, errors) = lexer::unescape(token.text());
+        let errors = errors
+            .into_iter()
+            .map(|(error, range)| {
+                let range = TextRange::new(
+                    start + TextSize::try_from(range.start).unwrap(),
+                    start + TextSize::try_from(range.end).unwrap(),
+                );
+                (error, range)
+            })
+            .collect();
+
+        Some((value, errors))
     }
 
     pub fn cast(node: SyntaxNode) -> Option<Self> {
@@ -110,7 +595,7 @@ impl UnaryExpr {
         self.0
             .children_with_tokens()
             .filter_map(SyntaxElement::into_token)
-            .find(|token| token.kind() == SyntaxKind::Minus)
+            .find(|token| matches!(token.kind(), SyntaxKind::Minus | SyntaxKind::Bang))
     }
 }
 
@@ -126,6 +611,7 @@ impl VariableRef {
 #[derive(Debug)]
 pub enum Stmt {
     VariableDef(VariableDef),
+    FnDef(FnDef),
     Expr(Expr),
 }
 
@@ -133,6 +619,7 @@ impl Stmt {
     pub fn cast(node: SyntaxNode) -> Option<Self> {
         match node.kind() {
             SyntaxKind::VariableDef => Some(Self::VariableDef(VariableDef(node))),
+            SyntaxKind::FnDef => Some(Self::FnDef(FnDef(node))),
             _ => Some(Self::Expr(Expr::cast(node)?)),
         }
     }
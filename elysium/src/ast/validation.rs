@@ -1,31 +1,146 @@
 use core::fmt;
 
-use super::Literal;
-use crate::syntax::SyntaxNode;
+use super::{AssignExpr, BreakExpr, ContinueExpr, Expr, Literal, LiteralKind, TypeRef};
+use crate::{
+    lexer::{self, EscapeError, NumberError},
+    syntax::{self, SyntaxKind, SyntaxNode},
+    Diagnostic,
+};
 use rowan::TextRange;
 
 pub fn validate(node: &SyntaxNode) -> Vec<ValidationError> {
     let mut errors = Vec::new();
 
     for node in node.descendants() {
-        if let Some(literal) = Literal::cast(node) {
-            validate_literal(&mut errors, literal);
+        if let Some(literal) = Literal::cast(node.clone()) {
+            validate_literal(&mut errors, &literal);
+        } else if let Some(break_expr) = BreakExpr::cast(node.clone()) {
+            validate_break(&mut errors, &break_expr);
+        } else if let Some(assign_expr) = AssignExpr::cast(node.clone()) {
+            validate_assign(&mut errors, &assign_expr);
+        } else if let Some(continue_expr) = ContinueExpr::cast(node.clone()) {
+            validate_continue(&mut errors, &continue_expr);
+        } else if let Some(type_ref) = TypeRef::cast(node) {
+            validate_type_ref(&mut errors, &type_ref);
         }
     }
 
     errors
 }
 
-fn validate_literal(errors: &mut Vec<ValidationError>, literal: Literal) {
-    if literal.parse().is_none() {
+fn validate_literal(errors: &mut Vec<ValidationError>, literal: &Literal) {
+    let token = literal.inner().first_token().unwrap();
+
+    let number_error = match literal.kind() {
+        Some(LiteralKind::Number) => match lexer::parse_int(token.text()) {
+            // Only the digits are checked here: the range of `i64` goes one further below zero
+            // than above it.
+            Ok(n) if literal.is_negated() && n > i64::MIN.unsigned_abs() => {
+                Some(NumberError::IntegerTooLarge)
+            }
+            Ok(n) if !literal.is_negated() && n > i64::MAX.unsigned_abs() => {
+                Some(NumberError::IntegerTooLarge)
+            }
+            result => result.err(),
+        },
+        Some(LiteralKind::Float) => lexer::parse_float(token.text()).err(),
+        Some(LiteralKind::String) => {
+            let (_, escape_errors) = literal.unescape().unwrap();
+            errors.extend(escape_errors.into_iter().map(|(error, range)| {
+                let kind = match error {
+                    EscapeError::Unterminated => ValidationErrorKind::UnterminatedString,
+                    EscapeError::UnknownEscape => ValidationErrorKind::UnknownEscape,
+                    EscapeError::InvalidUnicodeEscape => ValidationErrorKind::InvalidUnicodeEscape,
+                };
+                ValidationError { kind, range }
+            }));
+            None
+        }
+        Some(LiteralKind::Bool) | None => None,
+    };
+
+    if let Some(error) = number_error {
+        let kind = match error {
+            NumberError::InvalidDigit => ValidationErrorKind::InvalidDigit,
+            NumberError::MissingDigits => ValidationErrorKind::MissingDigits,
+            NumberError::IntegerTooLarge => ValidationErrorKind::NumberLiteralTooLarge,
+            NumberError::FloatTooLarge => ValidationErrorKind::FloatLiteralTooLarge,
+        };
         errors.push(ValidationError {
-            kind: ValidationErrorKind::NumberLiteralTooLarge,
-            range: literal.inner().first_token().unwrap().text_range(),
+            kind,
+            range: token.text_range(),
         });
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+fn validate_assign(errors: &mut Vec<ValidationError>, assign_expr: &AssignExpr) {
+    match assign_expr.place() {
+        Some(Expr::VariableRef(_)) | None => {}
+        Some(place) => errors.push(ValidationError {
+            kind: ValidationErrorKind::InvalidAssignmentTarget,
+            range: syntax::trimmed_range(place.syntax()),
+        }),
+    }
+}
+
+fn validate_break(errors: &mut Vec<ValidationError>, break_expr: &BreakExpr) {
+    let node = break_expr.syntax();
+    let kind = match enclosing_loop(node) {
+        None => ValidationErrorKind::BreakOutsideLoop,
+        Some(loop_kind) if loop_kind != SyntaxKind::LoopExpr && break_expr.value().is_some() => {
+            ValidationErrorKind::BreakWithValueInWhile
+        }
+        Some(_) => return,
+    };
+
+    errors.push(ValidationError {
+        kind,
+        range: node.first_token().unwrap().text_range(),
+    });
+}
+
+fn validate_continue(errors: &mut Vec<ValidationError>, continue_expr: &ContinueExpr) {
+    let node = continue_expr.syntax();
+    if enclosing_loop(node).is_none() {
+        errors.push(ValidationError {
+            kind: ValidationErrorKind::ContinueOutsideLoop,
+            range: node.first_token().unwrap().text_range(),
+        });
+    }
+}
+
+fn validate_type_ref(errors: &mut Vec<ValidationError>, type_ref: &TypeRef) {
+    if let Some(name) = type_ref.name() {
+        if !matches!(name.text(), "Int" | "Float" | "Bool" | "String") {
+            errors.push(ValidationError {
+                kind: ValidationErrorKind::UnknownType,
+                range: name.text_range(),
+            });
+        }
+    }
+}
+
+/// The kind of loop a `break` or `continue` at `node` would leave. Loops outside of the
+/// enclosing function, and those whose condition (rather than body) holds `node`, don't count.
+fn enclosing_loop(node: &SyntaxNode) -> Option<SyntaxKind> {
+    let mut child = node.clone();
+
+    for ancestor in node.ancestors().skip(1) {
+        match ancestor.kind() {
+            SyntaxKind::LoopExpr => return Some(SyntaxKind::LoopExpr),
+            SyntaxKind::WhileExpr if child.kind() == SyntaxKind::BlockExpr => {
+                return Some(SyntaxKind::WhileExpr)
+            }
+            SyntaxKind::FnDef => return None,
+            _ => {}
+        }
+        child = ancestor;
+    }
+
+    None
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ValidationError {
     kind: ValidationErrorKind,
     range: TextRange,
@@ -43,9 +158,40 @@ impl fmt::Display for ValidationError {
     }
 }
 
+impl From<ValidationError> for Diagnostic {
+    fn from(error: ValidationError) -> Self {
+        let code = match error.kind {
+            ValidationErrorKind::NumberLiteralTooLarge => "E0002",
+            ValidationErrorKind::FloatLiteralTooLarge => "E0007",
+            ValidationErrorKind::InvalidDigit => "E0008",
+            ValidationErrorKind::MissingDigits => "E0009",
+            ValidationErrorKind::UnterminatedString => "E0010",
+            ValidationErrorKind::UnknownEscape => "E0011",
+            ValidationErrorKind::InvalidUnicodeEscape => "E0012",
+            ValidationErrorKind::BreakOutsideLoop => "E0013",
+            ValidationErrorKind::ContinueOutsideLoop => "E0014",
+            ValidationErrorKind::BreakWithValueInWhile => "E0015",
+            ValidationErrorKind::InvalidAssignmentTarget => "E0016",
+            ValidationErrorKind::UnknownType => "E0017",
+        };
+        Self::error(code, error.kind.to_string(), error.range)
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum ValidationErrorKind {
     NumberLiteralTooLarge,
+    FloatLiteralTooLarge,
+    InvalidDigit,
+    MissingDigits,
+    UnterminatedString,
+    UnknownEscape,
+    InvalidUnicodeEscape,
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+    BreakWithValueInWhile,
+    InvalidAssignmentTarget,
+    UnknownType,
 }
 
 impl fmt::Display for ValidationErrorKind {
@@ -53,8 +199,38 @@ impl fmt::Display for ValidationErrorKind {
         match self {
             Self::NumberLiteralTooLarge => write!(
                 f,
-                "Number literal is larger than supported integer maximum value of {}",
-                u64::MAX
+                "number literal is outside the supported integer range of {} to {}",
+                i64::MIN,
+                i64::MAX
+            ),
+            Self::FloatLiteralTooLarge => write!(
+                f,
+                "float literal is larger than supported maximum value of {:e}",
+                f64::MAX
+            ),
+            Self::InvalidDigit => write!(f, "number literal has a digit invalid for its base"),
+            Self::MissingDigits => write!(
+                f,
+                "number literal is missing digits after its base prefix or exponent"
+            ),
+            Self::UnterminatedString => write!(f, "string literal is missing its closing quote"),
+            Self::UnknownEscape => write!(
+                f,
+                "unknown escape sequence; expected one of \\n, \\t, \\\", \\\\ or \\u{{...}}"
+            ),
+            Self::InvalidUnicodeEscape => write!(
+                f,
+                "unicode escapes take one to six hex digits naming a character, as in \\u{{1F600}}"
+            ),
+            Self::BreakOutsideLoop => write!(f, "`break` outside of a loop"),
+            Self::ContinueOutsideLoop => write!(f, "`continue` outside of a loop"),
+            Self::BreakWithValueInWhile => {
+                write!(f, "`break` with a value is only allowed inside `loop`")
+            }
+            Self::InvalidAssignmentTarget => write!(f, "can only assign to a variable"),
+            Self::UnknownType => write!(
+                f,
+                "unknown type; expected one of Int, Float, Bool, String, () or a function type"
             ),
         }
     }
@@ -127,6 +303,179 @@ mod tests {
         check("123", &[]);
     }
 
+    #[test]
+    fn validate_bool_literal() {
+        check("true == false", &[]);
+    }
+
+    #[test]
+    fn into_diagnostic() {
+        let errors = validate(&parse("let a = 99999999999999999999").syntax());
+        let diagnostic = Diagnostic::from(errors[0].clone());
+
+        assert_eq!(diagnostic.code, "E0002");
+        assert_eq!(diagnostic.range, TextRange::new(8.into(), 28.into()));
+    }
+
+    #[test]
+    fn break_and_continue_inside_loops() {
+        check(
+            "loop { break }\nwhile true { if false { continue } else { break } }\nloop { break 1 }",
+            &[],
+        );
+    }
+
+    #[test]
+    fn break_outside_loop() {
+        check(
+            "break\nif true { break 1 }",
+            &[
+                (ValidationErrorKind::BreakOutsideLoop, (0..5)),
+                (ValidationErrorKind::BreakOutsideLoop, (16..21)),
+            ],
+        );
+    }
+
+    #[test]
+    fn continue_outside_loop() {
+        check(
+            "{ continue }",
+            &[(ValidationErrorKind::ContinueOutsideLoop, (2..10))],
+        );
+    }
+
+    #[test]
+    fn loops_do_not_reach_into_fns() {
+        check(
+            "loop { fn f() { break } }",
+            &[(ValidationErrorKind::BreakOutsideLoop, (16..21))],
+        );
+    }
+
+    #[test]
+    fn while_condition_is_outside_its_loop() {
+        check(
+            "while continue {}",
+            &[(ValidationErrorKind::ContinueOutsideLoop, (6..14))],
+        );
+    }
+
+    #[test]
+    fn break_with_value_in_while() {
+        check(
+            "while true { break 1 }\nloop { while true { break } }",
+            &[(ValidationErrorKind::BreakWithValueInWhile, (13..18))],
+        );
+    }
+
+    #[test]
+    fn assign_to_variable() {
+        check("let a = 1\na = 2; a += 3", &[]);
+    }
+
+    #[test]
+    fn assign_to_expression() {
+        check(
+            "1 + a = 2\n(a) -= 1",
+            &[
+                (ValidationErrorKind::InvalidAssignmentTarget, (0..5)),
+                (ValidationErrorKind::InvalidAssignmentTarget, (10..13)),
+            ],
+        );
+    }
+
+    #[test]
+    fn validate_ok_string() {
+        check(r#"let a = "tab\t\u{41}""#, &[]);
+    }
+
+    #[test]
+    fn validate_unterminated_string() {
+        check(
+            "let a = \"abc\nlet b = 1",
+            &[(ValidationErrorKind::UnterminatedString, (8..12))],
+        );
+    }
+
+    #[test]
+    fn validate_bad_escapes() {
+        check(
+            r#""\q \u{110000}""#,
+            &[
+                (ValidationErrorKind::UnknownEscape, (1..3)),
+                (ValidationErrorKind::InvalidUnicodeEscape, (4..14)),
+            ],
+        );
+    }
+
+    #[test]
+    fn escape_error_message() {
+        assert_eq!(
+            ValidationErrorKind::UnknownEscape.to_string(),
+            r#"unknown escape sequence; expected one of \n, \t, \", \\ or \u{...}"#
+        );
+    }
+
+    #[test]
+    fn each_kind_has_its_own_code() {
+        let kinds = [
+            ValidationErrorKind::NumberLiteralTooLarge,
+            ValidationErrorKind::FloatLiteralTooLarge,
+            ValidationErrorKind::InvalidDigit,
+            ValidationErrorKind::MissingDigits,
+            ValidationErrorKind::UnterminatedString,
+            ValidationErrorKind::UnknownEscape,
+            ValidationErrorKind::InvalidUnicodeEscape,
+            ValidationErrorKind::BreakOutsideLoop,
+            ValidationErrorKind::ContinueOutsideLoop,
+            ValidationErrorKind::BreakWithValueInWhile,
+            ValidationErrorKind::InvalidAssignmentTarget,
+            ValidationErrorKind::UnknownType,
+        ];
+        let mut codes: Vec<_> = kinds
+            .into_iter()
+            .map(|kind| {
+                Diagnostic::from(ValidationError {
+                    kind,
+                    range: TextRange::default(),
+                })
+                .code
+            })
+            .collect();
+        codes.sort_unstable();
+        codes.dedup();
+
+        assert_eq!(codes.len(), kinds.len());
+    }
+
+    #[test]
+    fn validate_ok_numbers() {
+        check("0xff + 0o17 + 0b1010 + 1_000; 1.5 + 1e-3", &[]);
+    }
+
+    #[test]
+    fn validate_malformed_numbers() {
+        check(
+            "0b102 + 0x + 1e",
+            &[
+                (ValidationErrorKind::InvalidDigit, (0..5)),
+                (ValidationErrorKind::MissingDigits, (8..10)),
+                (ValidationErrorKind::MissingDigits, (13..15)),
+            ],
+        );
+    }
+
+    #[test]
+    fn validate_out_of_range_numbers() {
+        check(
+            "0x1_0000_0000_0000_0000; 1e400",
+            &[
+                (ValidationErrorKind::NumberLiteralTooLarge, (0..23)),
+                (ValidationErrorKind::FloatLiteralTooLarge, (25..30)),
+            ],
+        );
+    }
+
     #[test]
     fn validate_too_large_literal() {
         check(
@@ -134,4 +483,25 @@ mod tests {
             &[(ValidationErrorKind::NumberLiteralTooLarge, (0..20))],
         );
     }
+
+    #[test]
+    fn validate_literals_against_i64_range() {
+        check("9223372036854775807; -9223372036854775808", &[]);
+        check(
+            "9223372036854775808; -9223372036854775809; -(9223372036854775808); - 0x8000_0000_0000_0000",
+            &[
+                (ValidationErrorKind::NumberLiteralTooLarge, (0..19)),
+                (ValidationErrorKind::NumberLiteralTooLarge, (22..41)),
+                (ValidationErrorKind::NumberLiteralTooLarge, (45..64)),
+            ],
+        );
+    }
+
+    #[test]
+    fn validate_type_names() {
+        check(
+            "let a: fn(Int, Float) -> Bool = f\nfn g(b: Strng) -> () { }",
+            &[(ValidationErrorKind::UnknownType, (42..47))],
+        );
+    }
 }
@@ -0,0 +1,123 @@
+//! The compiled form of a program, and its disassembly.
+
+use crate::{
+    eval::{RuntimeError, Value},
+    hir::{BinaryOp, FnIdx, UnaryOp},
+};
+use la_arena::ArenaMap;
+use smartstring::alias::String;
+use std::fmt;
+
+/// A single instruction. Operands index into the enclosing [`Chunk`] (constants, errors,
+/// local slots and code) or into the [`Program`]'s globals.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// Pushes a constant.
+    Constant(u32),
+    Unit,
+    Pop,
+    /// Exchanges the top two values.
+    Swap,
+    GetLocal(u32),
+    /// Pops a value into a local slot, whether or not it already holds one.
+    DefineLocal(u32),
+    /// Pops a value into a local slot that must already hold one.
+    SetLocal(u32),
+    GetGlobal(u32),
+    DefineGlobal(u32),
+    SetGlobal(u32),
+    /// Pops the right and then the left operand, and pushes the result. Never `&&` or `||`,
+    /// which compile to jumps.
+    Binary(BinaryOp),
+    Unary(UnaryOp),
+    Jump(u32),
+    /// Pops a boolean, and jumps if it is false.
+    JumpIfFalse(u32),
+    /// Checks that the top value is a boolean, leaving it in place.
+    ExpectBool,
+    /// Calls the function below this many arguments, replacing both with its result.
+    Call(u32),
+    /// Leaves the current function with the value on top of the stack.
+    Return,
+    /// Pops the value of a top-level expression statement into the program's results.
+    Yield,
+    /// Stops the program with an error.
+    Raise(u32),
+}
+
+/// The code of a function, or of a program's top-level statements.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Chunk {
+    pub name: String,
+    pub code: Vec<Op>,
+    pub constants: Vec<Value>,
+    pub errors: Vec<RuntimeError>,
+    /// The name of the local in each slot, parameters first.
+    pub locals: Vec<String>,
+    pub params: usize,
+}
+
+/// Compiled code for the top-level statements and every function they define.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Program {
+    pub main: Chunk,
+    pub fns: ArenaMap<FnIdx, Chunk>,
+    /// The name of the top-level local in each global slot.
+    pub globals: Vec<String>,
+}
+
+impl Chunk {
+    fn write_op(&self, f: &mut fmt::Formatter<'_>, op: Op, globals: &[String]) -> fmt::Result {
+        let index = |i: u32| i as usize;
+
+        match op {
+            Op::Constant(i) => write!(f, "constant {i} ({})", self.constants[index(i)]),
+            Op::Unit => write!(f, "unit"),
+            Op::Pop => write!(f, "pop"),
+            Op::Swap => write!(f, "swap"),
+            Op::GetLocal(i) => write!(f, "get_local {i} ({})", self.locals[index(i)]),
+            Op::DefineLocal(i) => write!(f, "define_local {i} ({})", self.locals[index(i)]),
+            Op::SetLocal(i) => write!(f, "set_local {i} ({})", self.locals[index(i)]),
+            Op::GetGlobal(i) => write!(f, "get_global {i} ({})", globals[index(i)]),
+            Op::DefineGlobal(i) => write!(f, "define_global {i} ({})", globals[index(i)]),
+            Op::SetGlobal(i) => write!(f, "set_global {i} ({})", globals[index(i)]),
+            Op::Binary(op) => write!(f, "binary {op}"),
+            Op::Unary(op) => write!(f, "unary {op}"),
+            Op::Jump(target) => write!(f, "jump {target:04}"),
+            Op::JumpIfFalse(target) => write!(f, "jump_if_false {target:04}"),
+            Op::ExpectBool => write!(f, "expect_bool"),
+            Op::Call(args) => write!(f, "call {args}"),
+            Op::Return => write!(f, "return"),
+            Op::Yield => write!(f, "yield"),
+            Op::Raise(i) => write!(f, "raise {i} ({})", self.errors[index(i)]),
+        }
+    }
+
+    /// Writes the chunk's header and one line per instruction.
+    fn disassemble(&self, f: &mut fmt::Formatter<'_>, globals: &[String]) -> fmt::Result {
+        writeln!(
+            f,
+            "{}({}):",
+            self.name,
+            self.locals[..self.params].join(", ")
+        )?;
+        for (offset, op) in self.code.iter().enumerate() {
+            write!(f, "  {offset:04}  ")?;
+            self.write_op(f, *op, globals)?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Disassembles the top-level code, then each function in order of definition.
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.main.disassemble(f, &self.globals)?;
+        for chunk in self.fns.values() {
+            writeln!(f)?;
+            chunk.disassemble(f, &self.globals)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,436 @@
+//! Compiler from [`HirStmt`]s and the [`Database`] expression arena to a [`Program`].
+
+use super::{Chunk, Op, Program};
+use crate::{
+    eval::{RuntimeError, Value},
+    hir::{BinaryOp, Database, ExprIdx, HirExpr, HirFn, HirStmt, LocalId},
+};
+use smartstring::alias::String;
+use std::{collections::HashMap, mem};
+
+/// Compiles `stmts`, and every function in `db`, into a [`Program`].
+///
+/// Like [`eval`](crate::eval::eval), expects HIR that has been through
+/// [`resolve`](crate::hir::resolve). Anything that would fail to evaluate, such as an
+/// unresolved name or a `break` outside of a loop, still compiles, to code that raises the
+/// evaluator's error when it is reached.
+pub fn compile(db: &Database, stmts: &[HirStmt]) -> Program {
+    let mut compiler = Compiler {
+        db,
+        globals: HashMap::new(),
+        global_names: Vec::new(),
+        builder: Builder::new("<main>", false),
+    };
+
+    for stmt in stmts {
+        compiler.stmt(stmt, true);
+    }
+    let main = mem::replace(&mut compiler.builder, Builder::new("", true)).chunk;
+    let fns = db
+        .fns
+        .iter()
+        .map(|(idx, function)| (idx, compiler.function(function)))
+        .collect();
+
+    Program {
+        main,
+        fns,
+        globals: compiler.global_names,
+    }
+}
+
+struct Compiler<'a> {
+    db: &'a Database,
+    /// Slots of the top-level locals, shared by every chunk.
+    globals: HashMap<LocalId, u32>,
+    global_names: Vec<String>,
+    builder: Builder,
+}
+
+/// The chunk being compiled, and what is needed to compile it.
+struct Builder {
+    chunk: Chunk,
+    /// Whether this is a function, rather than the top-level code whose locals are globals.
+    in_fn: bool,
+    slots: HashMap<LocalId, u32>,
+    /// Number of temporaries the code compiled so far leaves on the stack.
+    height: usize,
+    loops: Vec<Loop>,
+}
+
+/// A loop whose body is being compiled.
+struct Loop {
+    start: u32,
+    /// Stack height on entry, which `break` and `continue` return to.
+    height: usize,
+    kind: LoopKind,
+    /// Jumps to the end of the loop, patched once it is known.
+    breaks: Vec<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum LoopKind {
+    /// A `while`, which discards the value of a `break` and produces unit.
+    While,
+    /// A `loop`, which produces the value of a `break`.
+    Loop,
+}
+
+/// Where the value of a local lives.
+#[derive(Clone, Copy)]
+enum Slot {
+    Local(u32),
+    Global(u32),
+}
+
+impl Builder {
+    fn new(name: &str, in_fn: bool) -> Self {
+        Self {
+            chunk: Chunk {
+                name: name.into(),
+                ..Chunk::default()
+            },
+            in_fn,
+            slots: HashMap::new(),
+            height: 0,
+            loops: Vec::new(),
+        }
+    }
+}
+
+impl Compiler<'_> {
+    fn function(&mut self, function: &HirFn) -> Chunk {
+        self.builder = Builder::new(&function.name, true);
+        for param in &function.params {
+            self.define(*param);
+        }
+        self.builder.chunk.params = function.params.len();
+
+        self.expr(function.body);
+        self.emit(Op::Return);
+
+        mem::replace(&mut self.builder, Builder::new("", true)).chunk
+    }
+
+    /// Compiles a statement, leaving the stack as it was. The values of top-level expression
+    /// statements are yielded as results of the program.
+    fn stmt(&mut self, stmt: &HirStmt, top_level: bool) {
+        match stmt {
+            HirStmt::VariableDef { local, value } => {
+                self.expr(*value);
+                match self.define(*local) {
+                    Slot::Local(slot) => self.emit(Op::DefineLocal(slot)),
+                    Slot::Global(slot) => self.emit(Op::DefineGlobal(slot)),
+                }
+            }
+            // Every function is compiled to a chunk of its own.
+            HirStmt::FnDef(_) => {}
+            HirStmt::Expr(expr) => {
+                self.expr(*expr);
+                self.emit(if top_level { Op::Yield } else { Op::Pop });
+            }
+        }
+    }
+
+    /// Compiles an expression, leaving its value on top of the stack.
+    fn expr(&mut self, idx: ExprIdx) {
+        match &self.db.exprs[idx] {
+            HirExpr::Assign { place, op, value } => self.assign(*place, *op, *value),
+            HirExpr::Binary {
+                op: op @ (BinaryOp::And | BinaryOp::Or),
+                lhs,
+                rhs,
+            } => self.short_circuit(*op, *lhs, *rhs),
+            HirExpr::Binary { op, lhs, rhs } => {
+                self.expr(*lhs);
+                self.expr(*rhs);
+                self.emit(Op::Binary(*op));
+            }
+            HirExpr::Block { stmts, tail } => {
+                for stmt in stmts {
+                    self.stmt(stmt, false);
+                }
+                self.expr_or_unit(*tail);
+            }
+            HirExpr::Call { callee, args } => {
+                self.expr(*callee);
+                for arg in args {
+                    self.expr(*arg);
+                }
+                self.emit(Op::Call(index(args.len())));
+            }
+            HirExpr::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expr(*condition);
+                let else_jump = self.emit_jump(Op::JumpIfFalse);
+                self.expr(*then_branch);
+                let end_jump = self.emit_jump(Op::Jump);
+                self.builder.height -= 1;
+                self.patch(else_jump);
+                self.expr_or_unit(*else_branch);
+                self.patch(end_jump);
+            }
+            HirExpr::While { condition, body } => self.while_loop(*condition, *body),
+            HirExpr::Loop { body } => self.loop_(*body),
+            HirExpr::Break { value } => self.break_(*value),
+            HirExpr::Continue => self.continue_(),
+            HirExpr::Literal(literal) => self.constant(Value::from(literal)),
+            HirExpr::Unary { op, expr } => {
+                self.expr(*expr);
+                self.emit(Op::Unary(*op));
+            }
+            HirExpr::VariableRef { var } => {
+                self.raise(RuntimeError::UndefinedVariable { name: var.clone() });
+            }
+            HirExpr::LocalRef(local) => match self.slot(*local) {
+                Slot::Local(slot) => self.emit(Op::GetLocal(slot)),
+                Slot::Global(slot) => self.emit(Op::GetGlobal(slot)),
+            },
+            HirExpr::FnRef(function) => self.constant(Value::Fn(*function)),
+            HirExpr::Missing => self.raise(RuntimeError::MissingExpr),
+        }
+    }
+
+    fn expr_or_unit(&mut self, idx: Option<ExprIdx>) {
+        match idx {
+            Some(idx) => self.expr(idx),
+            None => self.emit(Op::Unit),
+        }
+    }
+
+    /// Compiles `&&` and `||`, which only evaluate their right-hand side if it decides the
+    /// result.
+    fn short_circuit(&mut self, op: BinaryOp, lhs: ExprIdx, rhs: ExprIdx) {
+        self.expr(lhs);
+        let rhs_jump = self.emit_jump(Op::JumpIfFalse);
+
+        if op == BinaryOp::And {
+            self.expr(rhs);
+            self.emit(Op::ExpectBool);
+            let end_jump = self.emit_jump(Op::Jump);
+            self.builder.height -= 1;
+            self.patch(rhs_jump);
+            self.constant(Value::Bool(false));
+            self.patch(end_jump);
+        } else {
+            self.constant(Value::Bool(true));
+            let end_jump = self.emit_jump(Op::Jump);
+            self.builder.height -= 1;
+            self.patch(rhs_jump);
+            self.expr(rhs);
+            self.emit(Op::ExpectBool);
+            self.patch(end_jump);
+        }
+    }
+
+    fn assign(&mut self, place: ExprIdx, op: Option<BinaryOp>, value: ExprIdx) {
+        let local = match &self.db.exprs[place] {
+            HirExpr::LocalRef(local) => *local,
+            HirExpr::VariableRef { var } => {
+                return self.raise(RuntimeError::UndefinedVariable { name: var.clone() });
+            }
+            HirExpr::Missing => return self.raise(RuntimeError::MissingExpr),
+            _ => return self.raise(RuntimeError::NotAssignable),
+        };
+        let slot = self.slot(local);
+
+        self.expr(value);
+        if let Some(op) = op {
+            // The current value is read after the new one is evaluated, as by the evaluator.
+            match slot {
+                Slot::Local(slot) => self.emit(Op::GetLocal(slot)),
+                Slot::Global(slot) => self.emit(Op::GetGlobal(slot)),
+            }
+            self.emit(Op::Swap);
+            self.emit(Op::Binary(op));
+        }
+        match slot {
+            Slot::Local(slot) => self.emit(Op::SetLocal(slot)),
+            Slot::Global(slot) => self.emit(Op::SetGlobal(slot)),
+        }
+        self.emit(Op::Unit);
+    }
+
+    fn while_loop(&mut self, condition: ExprIdx, body: ExprIdx) {
+        let start = index(self.builder.chunk.code.len());
+        self.expr(condition);
+        let exit_jump = self.emit_jump(Op::JumpIfFalse);
+
+        let breaks = self.loop_body(start, LoopKind::While, body);
+        self.patch(exit_jump);
+        for jump in breaks {
+            self.patch(jump);
+        }
+        self.emit(Op::Unit);
+    }
+
+    fn loop_(&mut self, body: ExprIdx) {
+        let start = index(self.builder.chunk.code.len());
+        let breaks = self.loop_body(start, LoopKind::Loop, body);
+        for jump in breaks {
+            self.patch(jump);
+        }
+        // Only a `break` leaves the loop, with its value on the stack.
+        self.builder.height += 1;
+    }
+
+    /// Compiles a loop body that jumps back to `start`, returning the jumps of its `break`s.
+    fn loop_body(&mut self, start: u32, kind: LoopKind, body: ExprIdx) -> Vec<usize> {
+        self.builder.loops.push(Loop {
+            start,
+            height: self.builder.height,
+            kind,
+            breaks: Vec::new(),
+        });
+        self.expr(body);
+        self.emit(Op::Pop);
+        self.emit(Op::Jump(start));
+
+        self.builder.loops.pop().map_or_else(Vec::new, |l| l.breaks)
+    }
+
+    fn break_(&mut self, value: Option<ExprIdx>) {
+        let height = self.builder.height;
+        let Some((loop_height, kind)) = self.builder.loops.last().map(|l| (l.height, l.kind))
+        else {
+            if let Some(value) = value {
+                self.expr(value);
+                self.emit(Op::Pop);
+            }
+            return self.raise(RuntimeError::BreakOutsideLoop);
+        };
+
+        self.pop_to(loop_height);
+        match (kind, value) {
+            (LoopKind::While, Some(value)) => {
+                self.expr(value);
+                self.emit(Op::Pop);
+            }
+            (LoopKind::While, None) => {}
+            (LoopKind::Loop, value) => self.expr_or_unit(value),
+        }
+        let jump = self.emit_jump(Op::Jump);
+        if let Some(l) = self.builder.loops.last_mut() {
+            l.breaks.push(jump);
+        }
+
+        // Code after the jump is unreachable, but is compiled as if the `break` had a value.
+        self.builder.height = height + 1;
+    }
+
+    fn continue_(&mut self) {
+        let height = self.builder.height;
+        let Some((loop_height, start)) = self.builder.loops.last().map(|l| (l.height, l.start))
+        else {
+            return self.raise(RuntimeError::ContinueOutsideLoop);
+        };
+
+        self.pop_to(loop_height);
+        self.emit(Op::Jump(start));
+        self.builder.height = height + 1;
+    }
+
+    /// Drops temporaries until the stack is `height` values high.
+    fn pop_to(&mut self, height: usize) {
+        while self.builder.height > height {
+            self.emit(Op::Pop);
+        }
+    }
+
+    /// Gives `local` a slot for its definition: a new local slot within a function, or a
+    /// global one otherwise.
+    fn define(&mut self, local: LocalId) -> Slot {
+        if !self.builder.in_fn {
+            return self.global(local);
+        }
+
+        let locals = &mut self.builder.chunk.locals;
+        let slot = *self.builder.slots.entry(local).or_insert_with(|| {
+            locals.push(self.db.locals[local].name.clone());
+            index(locals.len() - 1)
+        });
+        Slot::Local(slot)
+    }
+
+    /// The slot of a local being used. Locals not defined by the current function are
+    /// top-level ones, as a function cannot see the locals of any other.
+    fn slot(&mut self, local: LocalId) -> Slot {
+        if let Some(slot) = self.builder.slots.get(&local) {
+            return Slot::Local(*slot);
+        }
+        self.global(local)
+    }
+
+    fn global(&mut self, local: LocalId) -> Slot {
+        let names = &mut self.global_names;
+        let slot = *self.globals.entry(local).or_insert_with(|| {
+            names.push(self.db.locals[local].name.clone());
+            index(names.len() - 1)
+        });
+        Slot::Global(slot)
+    }
+
+    fn constant(&mut self, value: Value) {
+        let constants = &mut self.builder.chunk.constants;
+        let i = constants
+            .iter()
+            .position(|c| *c == value)
+            .unwrap_or_else(|| {
+                constants.push(value);
+                constants.len() - 1
+            });
+        self.emit(Op::Constant(index(i)));
+    }
+
+    fn raise(&mut self, error: RuntimeError) {
+        let errors = &mut self.builder.chunk.errors;
+        let i = errors.iter().position(|e| *e == error).unwrap_or_else(|| {
+            errors.push(error);
+            errors.len() - 1
+        });
+        self.emit(Op::Raise(index(i)));
+    }
+
+    fn emit(&mut self, op: Op) {
+        let builder = &mut self.builder;
+        match op {
+            Op::Constant(_) | Op::Unit | Op::GetLocal(_) | Op::GetGlobal(_) | Op::Raise(_) => {
+                builder.height += 1;
+            }
+            Op::Pop
+            | Op::DefineLocal(_)
+            | Op::SetLocal(_)
+            | Op::DefineGlobal(_)
+            | Op::SetGlobal(_)
+            | Op::Binary(_)
+            | Op::JumpIfFalse(_)
+            | Op::Return
+            | Op::Yield => builder.height -= 1,
+            Op::Call(args) => builder.height -= args as usize,
+            Op::Swap | Op::Unary(_) | Op::Jump(_) | Op::ExpectBool => {}
+        }
+        builder.chunk.code.push(op);
+    }
+
+    /// Emits a jump whose target is filled in later by [`patch`](Self::patch).
+    fn emit_jump(&mut self, jump: fn(u32) -> Op) -> usize {
+        self.emit(jump(u32::MAX));
+        self.builder.chunk.code.len() - 1
+    }
+
+    /// Points the jump at `at` to the next instruction.
+    fn patch(&mut self, at: usize) {
+        let target = index(self.builder.chunk.code.len());
+        match &mut self.builder.chunk.code[at] {
+            Op::Jump(t) | Op::JumpIfFalse(t) => *t = target,
+            op => unreachable!("patching {op:?}, which is not a jump"),
+        }
+    }
+}
+
+fn index(i: usize) -> u32 {
+    u32::try_from(i).expect("chunk too large to address")
+}
@@ -0,0 +1,236 @@
+//! Compilation of lowered HIR to bytecode, and a virtual machine to run it.
+//!
+//! Each function, and the top-level code, compiles to a [`Chunk`] of [`Op`]s working on a
+//! shared operand stack, with its own pool of constants and a slot for each of its locals.
+//! Control flow compiles to jumps. The [`Display`](std::fmt::Display) of a [`Program`] is
+//! its disassembly.
+//!
+//! Running a program gives the same values and errors as the tree-walking
+//! [`eval`](crate::eval::eval) does on the same HIR.
+mod chunk;
+mod compiler;
+mod vm;
+
+pub use chunk::{Chunk, Op, Program};
+pub use compiler::compile;
+pub use vm::run;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast,
+        eval::{self, Overflow, RuntimeError, Value, MAX_CALL_DEPTH},
+        hir, parser,
+    };
+    use expect_test::{expect, Expect};
+
+    fn lower(input: &str) -> (hir::Database, Vec<hir::HirStmt>) {
+        let root = ast::Root::cast(parser::parse(input).syntax()).unwrap();
+        let (mut db, stmts) = hir::lower(&root);
+        hir::resolve(&mut db, &stmts);
+        (db, stmts)
+    }
+
+    /// Runs `input` on the VM, checking that it agrees with the evaluator.
+    fn run_str(input: &str) -> Result<Vec<Value>, RuntimeError> {
+        let (db, stmts) = lower(input);
+        let program = compile(&db, &stmts);
+        let result = run(&program, Overflow::Checked);
+        assert_eq!(result, eval::eval(&db, &stmts), "{program}");
+        result
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn check(input: &str, expected: Expect) {
+        let (db, stmts) = lower(input);
+        expected.assert_eq(&compile(&db, &stmts).to_string());
+    }
+
+    #[test]
+    fn disassemble_arithmetic() {
+        check(
+            "1 + 2 * 1",
+            expect![[r"
+                <main>():
+                  0000  constant 0 (1)
+                  0001  constant 1 (2)
+                  0002  constant 0 (1)
+                  0003  binary *
+                  0004  binary +
+                  0005  yield
+            "]],
+        );
+    }
+
+    #[test]
+    fn disassemble_globals_and_functions() {
+        check(
+            "let a = 1\nfn add(x, y) { let z = x + y; z + a }\nadd(a, 2)",
+            expect![[r"
+                <main>():
+                  0000  constant 0 (1)
+                  0001  define_global 0 (a)
+                  0002  constant 1 (<fn>)
+                  0003  get_global 0 (a)
+                  0004  constant 2 (2)
+                  0005  call 2
+                  0006  yield
+
+                add(x, y):
+                  0000  get_local 0 (x)
+                  0001  get_local 1 (y)
+                  0002  binary +
+                  0003  define_local 2 (z)
+                  0004  get_local 2 (z)
+                  0005  get_global 0 (a)
+                  0006  binary +
+                  0007  return
+            "]],
+        );
+    }
+
+    #[test]
+    fn disassemble_control_flow() {
+        check(
+            "let i = 0\nwhile i < 3 && true { i += 1 }\nif i == 3 { 1 } else { 2 }",
+            expect![[r"
+                <main>():
+                  0000  constant 0 (0)
+                  0001  define_global 0 (i)
+                  0002  get_global 0 (i)
+                  0003  constant 1 (3)
+                  0004  binary <
+                  0005  jump_if_false 0009
+                  0006  constant 2 (true)
+                  0007  expect_bool
+                  0008  jump 0010
+                  0009  constant 3 (false)
+                  0010  jump_if_false 0019
+                  0011  constant 4 (1)
+                  0012  get_global 0 (i)
+                  0013  swap
+                  0014  binary +
+                  0015  set_global 0 (i)
+                  0016  unit
+                  0017  pop
+                  0018  jump 0002
+                  0019  unit
+                  0020  yield
+                  0021  get_global 0 (i)
+                  0022  constant 1 (3)
+                  0023  binary ==
+                  0024  jump_if_false 0027
+                  0025  constant 4 (1)
+                  0026  jump 0028
+                  0027  constant 5 (2)
+                  0028  yield
+            "]],
+        );
+    }
+
+    #[test]
+    fn disassemble_errors() {
+        check(
+            "x; break",
+            expect![[r"
+                <main>():
+                  0000  raise 0 (runtime error: undefined variable `x`)
+                  0001  yield
+                  0002  raise 1 (runtime error: `break` outside of a loop)
+                  0003  yield
+            "]],
+        );
+    }
+
+    #[test]
+    fn run_matches_eval() {
+        assert_eq!(
+            run_str("let a = 2\nfn sq(x) { x * x }\nsq(a) + sq(3)"),
+            Ok(vec![Value::Int(13)])
+        );
+    }
+
+    #[test]
+    fn loops_discard_temporaries_on_break_and_continue() {
+        assert_eq!(
+            run_str(
+                "let i = 0
+                let n = 0
+                1 + loop {
+                    i += 1
+                    if i > 5 { 10 + (2 * break n) }
+                    if i == 2 { 1 + continue }
+                    n += i
+                }
+                while true { 1 + break 3 }",
+            ),
+            Ok(vec![Value::Int(14), Value::Unit])
+        );
+    }
+
+    #[test]
+    fn break_leaves_only_the_innermost_loop() {
+        assert_eq!(
+            run_str(
+                "let n = 0
+                loop { while true { n += 1; break }; if n == 3 { break n } }",
+            ),
+            Ok(vec![Value::Int(3)])
+        );
+    }
+
+    #[test]
+    fn break_cannot_leave_a_function() {
+        assert_eq!(
+            run_str("fn f() { break 1 }\nloop { f() }"),
+            Err(RuntimeError::BreakOutsideLoop)
+        );
+        assert_eq!(
+            run_str("fn f() { continue }\nwhile true { f() }"),
+            Err(RuntimeError::ContinueOutsideLoop)
+        );
+    }
+
+    #[test]
+    fn functions_cannot_see_each_others_locals() {
+        assert_eq!(
+            run_str("fn f(x) { fn g() { x }; g() }\nf(1)"),
+            Err(RuntimeError::UndefinedVariable { name: "x".into() })
+        );
+    }
+
+    #[test]
+    fn recursion_is_not_limited_by_the_native_stack() {
+        // Deep enough to overflow the evaluator's native stack in a debug build.
+        let count_down = "fn f(n) { if n == 0 { 0 } else { 1 + f(n - 1) } }";
+        let depth = i64::try_from(MAX_CALL_DEPTH).unwrap();
+
+        let (db, stmts) = lower(&format!("{count_down}\nf({})", depth - 1));
+        assert_eq!(
+            run(&compile(&db, &stmts), Overflow::Checked),
+            Ok(vec![Value::Int(depth - 1)])
+        );
+
+        let (db, stmts) = lower(&format!("{count_down}\nf({depth})"));
+        assert_eq!(
+            run(&compile(&db, &stmts), Overflow::Checked),
+            Err(RuntimeError::StackOverflow)
+        );
+    }
+
+    #[test]
+    fn errors_stop_the_program() {
+        assert_eq!(
+            run_str("1\nlet a = 1 / 0\n2"),
+            Err(RuntimeError::DivisionByZero)
+        );
+        assert_eq!(
+            run_str("true || 1; false || 1"),
+            Err(RuntimeError::TypeMismatch {
+                expected: "boolean",
+                found: Value::Int(1)
+            })
+        );
+    }
+}
@@ -0,0 +1,188 @@
+//! Stack-based virtual machine running a compiled [`Program`].
+
+use super::{Chunk, Op, Program};
+use crate::eval::{
+    eval_binary, eval_unary, expect_bool, Overflow, RuntimeError, Value, MAX_CALL_DEPTH,
+};
+use std::iter;
+
+/// Runs `program`, collecting the values of its top-level expression statements. Integer
+/// overflow is handled as `overflow` says.
+///
+/// # Errors
+///
+/// Returns the first [`RuntimeError`] raised, the same one an
+/// [`Evaluator`](crate::eval::Evaluator) handling overflow the same way returns for the
+/// program's HIR.
+pub fn run(program: &Program, overflow: Overflow) -> Result<Vec<Value>, RuntimeError> {
+    let mut vm = Vm {
+        program,
+        overflow,
+        stack: Vec::new(),
+        frames: vec![Frame {
+            chunk: &program.main,
+            ip: 0,
+            slots: Vec::new(),
+        }],
+        globals: vec![None; program.globals.len()],
+        results: Vec::new(),
+    };
+    vm.run()?;
+    Ok(vm.results)
+}
+
+struct Vm<'a> {
+    program: &'a Program,
+    overflow: Overflow,
+    /// Temporaries, and the callee and arguments of calls being made, shared by every frame.
+    stack: Vec<Value>,
+    frames: Vec<Frame<'a>>,
+    /// Values of the top-level locals whose definitions have run.
+    globals: Vec<Option<Value>>,
+    results: Vec<Value>,
+}
+
+/// A function call being run, or the top-level code at the bottom of the call stack.
+struct Frame<'a> {
+    chunk: &'a Chunk,
+    ip: usize,
+    /// Values of the chunk's locals whose definitions have run.
+    slots: Vec<Option<Value>>,
+}
+
+impl<'a> Vm<'a> {
+    fn run(&mut self) -> Result<(), RuntimeError> {
+        loop {
+            let frame = self.frame();
+            let chunk = frame.chunk;
+            // Only the top-level code runs off its end; functions return.
+            let Some(&op) = chunk.code.get(frame.ip) else {
+                return Ok(());
+            };
+            self.frame().ip += 1;
+
+            match op {
+                Op::Constant(i) => self.stack.push(chunk.constants[i as usize].clone()),
+                Op::Unit => self.stack.push(Value::Unit),
+                Op::Pop => {
+                    self.pop();
+                }
+                Op::Swap => {
+                    let len = self.stack.len();
+                    self.stack.swap(len - 1, len - 2);
+                }
+                Op::GetLocal(i) => {
+                    let value = self.frame().slots[i as usize].clone();
+                    self.stack
+                        .push(value.ok_or_else(|| undefined(&chunk.locals, i))?);
+                }
+                Op::DefineLocal(i) => {
+                    let value = self.pop();
+                    self.frame().slots[i as usize] = Some(value);
+                }
+                Op::SetLocal(i) => {
+                    let value = self.pop();
+                    let slot = &mut self.frame().slots[i as usize];
+                    *slot.as_mut().ok_or_else(|| undefined(&chunk.locals, i))? = value;
+                }
+                Op::GetGlobal(i) => {
+                    let value = self.globals[i as usize].clone();
+                    let names = &self.program.globals;
+                    self.stack.push(value.ok_or_else(|| undefined(names, i))?);
+                }
+                Op::DefineGlobal(i) => {
+                    self.globals[i as usize] = Some(self.pop());
+                }
+                Op::SetGlobal(i) => {
+                    let value = self.pop();
+                    let names = &self.program.globals;
+                    *self.globals[i as usize]
+                        .as_mut()
+                        .ok_or_else(|| undefined(names, i))? = value;
+                }
+                Op::Binary(op) => {
+                    let rhs = self.pop();
+                    let lhs = self.pop();
+                    self.stack.push(eval_binary(op, lhs, rhs, self.overflow)?);
+                }
+                Op::Unary(op) => {
+                    let operand = self.pop();
+                    self.stack.push(eval_unary(op, operand, self.overflow)?);
+                }
+                Op::Jump(target) => self.frame().ip = target as usize,
+                Op::JumpIfFalse(target) => {
+                    if !expect_bool(self.pop())? {
+                        self.frame().ip = target as usize;
+                    }
+                }
+                Op::ExpectBool => {
+                    let value = self.pop();
+                    expect_bool(value.clone())?;
+                    self.stack.push(value);
+                }
+                Op::Call(args) => self.call(args as usize)?,
+                Op::Return => {
+                    self.frames.pop();
+                }
+                Op::Yield => {
+                    let value = self.pop();
+                    self.results.push(value);
+                }
+                Op::Raise(i) => return Err(chunk.errors[i as usize].clone()),
+            }
+        }
+    }
+
+    /// Pops the callee and `args` arguments, and starts running the callee in a new frame.
+    fn call(&mut self, args: usize) -> Result<(), RuntimeError> {
+        let args = self.stack.split_off(self.stack.len() - args);
+        let callee = self.pop();
+        let Value::Fn(idx) = callee else {
+            return Err(RuntimeError::NotCallable { found: callee });
+        };
+        let chunk = &self.program.fns[idx];
+
+        if chunk.params != args.len() {
+            return Err(RuntimeError::ArityMismatch {
+                expected: chunk.params,
+                found: args.len(),
+            });
+        }
+        // The top-level code's frame is not a call.
+        if self.frames.len() > MAX_CALL_DEPTH {
+            return Err(RuntimeError::StackOverflow);
+        }
+
+        let slots = args
+            .into_iter()
+            .map(Some)
+            .chain(iter::repeat(None))
+            .take(chunk.locals.len())
+            .collect();
+        self.frames.push(Frame {
+            chunk,
+            ip: 0,
+            slots,
+        });
+
+        Ok(())
+    }
+
+    fn frame(&mut self) -> &mut Frame<'a> {
+        self.frames
+            .last_mut()
+            .expect("the top-level frame is never popped")
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack
+            .pop()
+            .expect("compiled code never underflows the stack")
+    }
+}
+
+fn undefined(names: &[smartstring::alias::String], slot: u32) -> RuntimeError {
+    RuntimeError::UndefinedVariable {
+        name: names[slot as usize].clone(),
+    }
+}
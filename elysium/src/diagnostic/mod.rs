@@ -0,0 +1,168 @@
+//! A single error type shared by every stage, from parsing through to evaluation.
+//!
+//! Each stage keeps its own precise error type for matching on, and converts it into a
+//! [`Diagnostic`] for reporting. Codes are stable so tooling can filter on them:
+//!
+//! | Code  | Stage      | Meaning                                  |
+//! |-------|------------|------------------------------------------|
+//! | E0001 | parse      | unexpected or missing token              |
+//! | E0002 | validation | integer literal out of range             |
+//! | E0003 | resolve    | name not found in scope                  |
+//! | E0004 | resolve    | local used before its definition         |
+//! | E0005 | runtime    | evaluation failed                        |
+//! | E0006 | type check | value of the wrong type                  |
+//! | E0007 | validation | float literal out of range               |
+//! | E0008 | validation | digit invalid for the literal's base     |
+//! | E0009 | validation | number literal missing digits            |
+//! | E0010 | validation | string literal missing its closing quote |
+//! | E0011 | validation | unknown escape sequence                  |
+//! | E0012 | validation | malformed unicode escape                 |
+//! | E0013 | validation | `break` outside of a loop                |
+//! | E0014 | validation | `continue` outside of a loop             |
+//! | E0015 | validation | `break` with a value inside `while`      |
+//! | E0016 | validation | assignment to something not a variable   |
+//! | E0017 | validation | unknown type name                        |
+//! | W0001 | fold       | operation on literals that always fails  |
+
+mod render;
+
+use rowan::TextRange;
+use std::fmt;
+
+pub use render::Renderer;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Warning => write!(f, "warning"),
+            Self::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// A secondary location related to a diagnostic, e.g. the definition involved in an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub range: TextRange,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    /// Where the problem is.
+    pub range: TextRange,
+    pub labels: Vec<Label>,
+    /// Extra free-standing context, printed after the labels.
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn error(code: &'static str, message: impl Into<String>, range: TextRange) -> Self {
+        Self::new(Severity::Error, code, message.into(), range)
+    }
+
+    pub fn warning(code: &'static str, message: impl Into<String>, range: TextRange) -> Self {
+        Self::new(Severity::Warning, code, message.into(), range)
+    }
+
+    const fn new(
+        severity: Severity,
+        code: &'static str,
+        message: String,
+        range: TextRange,
+    ) -> Self {
+        Self {
+            severity,
+            code,
+            message,
+            range,
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn with_label(mut self, range: TextRange, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            range,
+            message: message.into(),
+        });
+        self
+    }
+
+    #[must_use]
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] at {}..{}: {}",
+            self.severity,
+            self.code,
+            u32::from(self.range.start()),
+            u32::from(self.range.end()),
+            self.message
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: u32, end: u32) -> TextRange {
+        TextRange::new(start.into(), end.into())
+    }
+
+    #[test]
+    fn display_includes_severity_code_and_range() {
+        let diagnostic = Diagnostic::error("E0003", "cannot find `a` in this scope", range(4, 5));
+
+        assert_eq!(
+            diagnostic.to_string(),
+            "error[E0003] at 4..5: cannot find `a` in this scope"
+        );
+    }
+
+    #[test]
+    fn builder_collects_labels_and_notes() {
+        let diagnostic = Diagnostic::warning("E0001", "message", range(0, 1))
+            .with_label(range(2, 3), "first")
+            .with_label(range(4, 5), "second")
+            .with_note("a note");
+
+        assert!(!diagnostic.is_error());
+        assert_eq!(
+            diagnostic.labels,
+            [
+                Label {
+                    range: range(2, 3),
+                    message: "first".into()
+                },
+                Label {
+                    range: range(4, 5),
+                    message: "second".into()
+                },
+            ]
+        );
+        assert_eq!(diagnostic.notes, ["a note"]);
+    }
+}
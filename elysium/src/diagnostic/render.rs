@@ -0,0 +1,369 @@
+//! Human-readable rendering of [`Diagnostic`]s against the source they were reported for.
+
+use super::{Diagnostic, Severity};
+use crate::line_index::{LineCol, LineIndex};
+use rowan::TextRange;
+use std::fmt::Write;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Renders diagnostics in the style of rustc: a header, the `name:line:col` of the primary
+/// range, then each affected line of source with its ranges underlined.
+///
+/// ```text
+/// error[E0004]: `b` is used before its definition
+///  --> <input>:1:9
+///   |
+/// 1 | let a = b
+///   |         ^
+/// 2 | let b = 1
+///   |     - defined here
+/// ```
+#[derive(Debug)]
+pub struct Renderer<'a> {
+    name: &'a str,
+    source: &'a str,
+    line_index: LineIndex,
+    colour: bool,
+}
+
+/// A range to underline on a single line.
+struct Annotation<'a> {
+    line: u32,
+    col: usize,
+    width: usize,
+    primary: bool,
+    message: &'a str,
+}
+
+impl<'a> Renderer<'a> {
+    /// `name` identifies `source` in the location line, e.g. a file path.
+    pub fn new(name: &'a str, source: &'a str) -> Self {
+        Self {
+            name,
+            source,
+            line_index: LineIndex::new(source),
+            colour: false,
+        }
+    }
+
+    /// Whether to emit ANSI colour codes; off by default.
+    #[must_use]
+    pub const fn with_colour(mut self, colour: bool) -> Self {
+        self.colour = colour;
+        self
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic) -> String {
+        let severity_style = match diagnostic.severity {
+            Severity::Warning => YELLOW,
+            Severity::Error => RED,
+        };
+
+        let mut annotations: Vec<_> = std::iter::once(self.annotate(diagnostic.range, true, ""))
+            .chain(
+                diagnostic
+                    .labels
+                    .iter()
+                    .map(|label| self.annotate(label.range, false, &label.message)),
+            )
+            .collect();
+        annotations.sort_by_key(|annotation| (annotation.line, annotation.col));
+
+        let last_line = annotations.iter().map(|a| a.line).max().unwrap_or(0);
+        let width = (last_line + 1).to_string().len();
+        let gutter = self.paint(BLUE, &format!("{:width$} |", ""));
+
+        let mut out = String::new();
+
+        let header = format!("{}[{}]", diagnostic.severity, diagnostic.code);
+        let _ = writeln!(
+            out,
+            "{}{}",
+            self.paint(severity_style, &header),
+            self.paint(BOLD, &format!(": {}", diagnostic.message))
+        );
+
+        let location = self.line_index.line_col(diagnostic.range.start());
+        let _ = writeln!(
+            out,
+            "{}{}:{}:{}",
+            self.paint(BLUE, &format!("{:width$}--> ", "")),
+            self.name,
+            location.line + 1,
+            self.char_col(location) + 1
+        );
+        let _ = writeln!(out, "{gutter}");
+
+        let mut previous_line = None;
+        for (idx, annotation) in annotations.iter().enumerate() {
+            if previous_line != Some(annotation.line) {
+                if previous_line.is_some_and(|previous| annotation.line > previous + 1) {
+                    let _ = writeln!(out, "{}", self.paint(BLUE, "..."));
+                }
+
+                let number = format!("{:>width$} |", annotation.line + 1);
+                let line = format!(
+                    "{} {}",
+                    self.paint(BLUE, &number),
+                    self.line_text(annotation.line)
+                );
+                let _ = writeln!(out, "{}", line.trim_end());
+                previous_line = Some(annotation.line);
+            }
+
+            let (style, mark) = if annotation.primary {
+                (severity_style, "^")
+            } else {
+                (BLUE, "-")
+            };
+            let mut underline = mark.repeat(annotation.width);
+            if !annotation.message.is_empty() {
+                underline = format!("{underline} {}", annotation.message);
+            }
+            let _ = write!(
+                out,
+                "{gutter} {:col$}{}",
+                "",
+                self.paint(style, &underline),
+                col = annotation.col
+            );
+
+            if idx + 1 < annotations.len() || !diagnostic.notes.is_empty() {
+                out.push('\n');
+            }
+        }
+
+        if !diagnostic.notes.is_empty() {
+            let _ = write!(out, "{gutter}");
+            for note in &diagnostic.notes {
+                let _ = write!(
+                    out,
+                    "\n{} {note}",
+                    self.paint(BLUE, &format!("{:width$} = note:", ""))
+                );
+            }
+        }
+
+        out
+    }
+
+    fn annotate(&self, range: TextRange, primary: bool, message: &'a str) -> Annotation<'a> {
+        let start = self.line_index.line_col(range.start());
+        let line_end = self
+            .line_index
+            .line_range(start.line)
+            .map_or_else(|| range.end(), TextRange::end);
+        let end = range.end().min(line_end);
+
+        let width = self
+            .source
+            .get(usize::from(range.start())..usize::from(end))
+            .map_or(0, |text| {
+                text.trim_end_matches(['\r', '\n']).chars().count()
+            });
+
+        Annotation {
+            line: start.line,
+            col: self.char_col(start),
+            width: width.max(1),
+            primary,
+            message,
+        }
+    }
+
+    fn line_text(&self, line: u32) -> &str {
+        self.line_index
+            .line_range(line)
+            .map_or("", |range| &self.source[range])
+            .trim_end_matches(['\r', '\n'])
+    }
+
+    /// The column of `line_col` in characters rather than bytes.
+    fn char_col(&self, line_col: LineCol) -> usize {
+        let text = self.line_text(line_col.line);
+        let col = usize::try_from(line_col.col).unwrap().min(text.len());
+        text.get(..col).map_or(col, |prefix| prefix.chars().count())
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.colour {
+            format!("{style}{text}{RESET}")
+        } else {
+            text.to_owned()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use expect_test::{expect, Expect};
+
+    fn range(start: u32, end: u32) -> TextRange {
+        TextRange::new(start.into(), end.into())
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn check(source: &str, diagnostic: Diagnostic, expected: Expect) {
+        let rendered = Renderer::new("<input>", source).render(&diagnostic);
+        expected.assert_eq(&rendered);
+    }
+
+    #[test]
+    fn single_line() {
+        check(
+            "let a = 1 +",
+            Diagnostic::error("E0001", "expected number-literal", range(10, 11)),
+            expect![[r"
+                error[E0001]: expected number-literal
+                 --> <input>:1:11
+                  |
+                1 | let a = 1 +
+                  |           ^"]],
+        );
+    }
+
+    #[test]
+    fn underlines_the_whole_range() {
+        check(
+            "let a = 99999999999999999999",
+            Diagnostic::error("E0002", "too large", range(8, 28)),
+            expect![[r"
+                error[E0002]: too large
+                 --> <input>:1:9
+                  |
+                1 | let a = 99999999999999999999
+                  |         ^^^^^^^^^^^^^^^^^^^^"]],
+        );
+    }
+
+    #[test]
+    fn picks_the_right_line() {
+        check(
+            "let a = 1\nlet b = c\nlet d = 2",
+            Diagnostic::error("E0003", "cannot find `c` in this scope", range(18, 19)),
+            expect![[r"
+                error[E0003]: cannot find `c` in this scope
+                 --> <input>:2:9
+                  |
+                2 | let b = c
+                  |         ^"]],
+        );
+    }
+
+    #[test]
+    fn secondary_labels_on_other_lines() {
+        check(
+            "let a = b\nlet b = 1",
+            Diagnostic::error("E0004", "`b` is used before its definition", range(8, 9))
+                .with_label(range(14, 15), "defined here"),
+            expect![[r"
+                error[E0004]: `b` is used before its definition
+                 --> <input>:1:9
+                  |
+                1 | let a = b
+                  |         ^
+                2 | let b = 1
+                  |     - defined here"]],
+        );
+    }
+
+    #[test]
+    fn several_labels_on_one_line() {
+        check(
+            "foo(a, b)",
+            Diagnostic::error("E0005", "bad call", range(0, 9))
+                .with_label(range(4, 5), "first")
+                .with_label(range(7, 8), "second"),
+            expect![[r"
+                error[E0005]: bad call
+                 --> <input>:1:1
+                  |
+                1 | foo(a, b)
+                  | ^^^^^^^^^
+                  |     - first
+                  |        - second"]],
+        );
+    }
+
+    #[test]
+    fn distant_lines_are_separated() {
+        check(
+            "a\n\n\n\n\n\n\n\n\nlet b = 1\nb",
+            Diagnostic::warning("E0001", "warning", range(0, 1))
+                .with_label(range(15, 16), "here")
+                .with_note("a note"),
+            expect![[r"
+                warning[E0001]: warning
+                  --> <input>:1:1
+                   |
+                 1 | a
+                   | ^
+                ...
+                10 | let b = 1
+                   |      - here
+                   |
+                   = note: a note"]],
+        );
+    }
+
+    #[test]
+    fn range_at_end_of_input() {
+        check(
+            "let a =\n",
+            Diagnostic::error("E0001", "expected expression", range(8, 8)),
+            expect![[r"
+                error[E0001]: expected expression
+                 --> <input>:2:1
+                  |
+                2 |
+                  | ^"]],
+        );
+    }
+
+    #[test]
+    fn columns_count_characters() {
+        check(
+            "let é = x",
+            Diagnostic::error("E0003", "cannot find `x` in this scope", range(9, 10)),
+            expect![[r"
+                error[E0003]: cannot find `x` in this scope
+                 --> <input>:1:9
+                  |
+                1 | let é = x
+                  |         ^"]],
+        );
+    }
+
+    #[test]
+    fn carriage_returns_are_not_printed() {
+        check(
+            "let a = b\r\nlet c = 1",
+            Diagnostic::error("E0003", "cannot find `b` in this scope", range(8, 9)),
+            expect![[r"
+                error[E0003]: cannot find `b` in this scope
+                 --> <input>:1:9
+                  |
+                1 | let a = b
+                  |         ^"]],
+        );
+    }
+
+    #[test]
+    fn colour_is_opt_in() {
+        let diagnostic = Diagnostic::error("E0001", "message", range(0, 1));
+
+        let plain = Renderer::new("<input>", "a").render(&diagnostic);
+        let coloured = Renderer::new("<input>", "a")
+            .with_colour(true)
+            .render(&diagnostic);
+
+        assert!(!plain.contains('\x1b'));
+        assert!(coloured.contains(&format!("{RED}error[E0001]{RESET}")));
+    }
+}
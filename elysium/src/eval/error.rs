@@ -0,0 +1,91 @@
+//! Errors raised while evaluating HIR.
+
+use super::{interp::MAX_CALL_DEPTH, Value};
+use crate::{
+    hir::{BinaryOp, UnaryOp},
+    Diagnostic,
+};
+use rowan::TextRange;
+use smartstring::alias::String;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum RuntimeError {
+    DivisionByZero,
+    BinaryOverflow {
+        op: BinaryOp,
+        lhs: i64,
+        rhs: i64,
+    },
+    UnaryOverflow {
+        op: UnaryOp,
+        operand: i64,
+    },
+    UndefinedVariable {
+        name: String,
+    },
+    MissingExpr,
+    TypeMismatch {
+        expected: &'static str,
+        found: Value,
+    },
+    NotCallable {
+        found: Value,
+    },
+    NotAssignable,
+    ArityMismatch {
+        expected: usize,
+        found: usize,
+    },
+    StackOverflow,
+    BreakOutsideLoop,
+    ContinueOutsideLoop,
+}
+
+impl RuntimeError {
+    /// Reports this error at `range`, usually that of the statement being evaluated.
+    pub fn to_diagnostic(&self, range: TextRange) -> Diagnostic {
+        Diagnostic::error("E0005", Message(self).to_string(), range)
+    }
+}
+
+impl fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "runtime error: {}", Message(self))
+    }
+}
+
+/// The error's description without the "runtime error" prefix.
+struct Message<'a>(&'a RuntimeError);
+
+impl fmt::Display for Message<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            RuntimeError::DivisionByZero => write!(f, "attempted to divide by zero"),
+            RuntimeError::BinaryOverflow { op, lhs, rhs } => {
+                write!(f, "overflow evaluating {lhs} {op} {rhs}")
+            }
+            RuntimeError::UnaryOverflow { op, operand } => {
+                write!(f, "overflow evaluating {op}{operand}")
+            }
+            RuntimeError::UndefinedVariable { name } => write!(f, "undefined variable `{name}`"),
+            RuntimeError::MissingExpr => write!(f, "cannot evaluate an incomplete expression"),
+            RuntimeError::TypeMismatch { expected, found } => {
+                write!(f, "expected {expected}, found {}", found.kind_name())
+            }
+            RuntimeError::NotCallable { found } => {
+                write!(f, "cannot call a value of type {}", found.kind_name())
+            }
+            RuntimeError::NotAssignable => write!(f, "cannot assign to this expression"),
+            RuntimeError::ArityMismatch { expected, found } => write!(
+                f,
+                "function takes {expected} argument(s) but {found} were supplied"
+            ),
+            RuntimeError::StackOverflow => {
+                write!(f, "maximum call depth of {MAX_CALL_DEPTH} exceeded")
+            }
+            RuntimeError::BreakOutsideLoop => write!(f, "`break` outside of a loop"),
+            RuntimeError::ContinueOutsideLoop => write!(f, "`continue` outside of a loop"),
+        }
+    }
+}
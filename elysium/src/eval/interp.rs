@@ -0,0 +1,1113 @@
+//! Evaluator walking [`HirStmt`]s and the [`Database`] expression arena.
+
+use super::{RuntimeError, Value};
+use crate::hir::{BinaryOp, Database, ExprIdx, HirExpr, HirStmt, LocalId, UnaryOp};
+use std::collections::HashMap;
+
+/// Deepest nesting of function calls before evaluation gives up with
+/// [`RuntimeError::StackOverflow`].
+pub const MAX_CALL_DEPTH: usize = 1024;
+
+/// Native stack size of a thread that can evaluate calls nested [`MAX_CALL_DEPTH`] deep, which
+/// is more than a thread gets by default since each call recurses through [`Evaluator`].
+pub const EVAL_STACK_SIZE: usize = 128 << 20;
+
+type Frame = HashMap<LocalId, Value>;
+
+/// Holds the values of the locals defined so far.
+///
+/// Expects HIR that has been through [`resolve`](crate::hir::resolve): scoping and shadowing
+/// are already reflected in which [`LocalId`] each reference points at, so the evaluator only
+/// needs to keep the locals of separate function calls apart. Each call gets a fresh frame;
+/// lookups check the current frame and then the top-level one.
+#[derive(Debug, Default)]
+pub struct Evaluator {
+    globals: Frame,
+    frames: Vec<Frame>,
+    overflow: Overflow,
+}
+
+/// What integer arithmetic does when its result doesn't fit in an `i64`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Raises [`RuntimeError::BinaryOverflow`] or [`RuntimeError::UnaryOverflow`].
+    #[default]
+    Checked,
+    /// Wraps around, so `9223372036854775807 + 1` is `-9223372036854775808`.
+    Wrapping,
+}
+
+impl Evaluator {
+    /// Sets how integer overflow is handled, [`Overflow::Checked`] by default.
+    #[must_use]
+    pub const fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub const fn overflow(&self) -> Overflow {
+        self.overflow
+    }
+
+    /// Evaluates a single statement, returning the value of expression statements.
+    ///
+    /// Variable definitions bind their value and produce `None`, as do function definitions,
+    /// which need no runtime work. A definition whose value fails to evaluate leaves the
+    /// environment untouched.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RuntimeError`] if the statement's expression cannot be evaluated.
+    pub fn eval_stmt(
+        &mut self,
+        db: &Database,
+        stmt: &HirStmt,
+    ) -> Result<Option<Value>, RuntimeError> {
+        self.stmt(db, stmt).map_err(Unwind::into_error)
+    }
+
+    /// Evaluates the expression at `idx` against the current bindings.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`RuntimeError`] on arithmetic failure, unresolved or unbound variables, bad
+    /// calls, missing sub-expressions, or a `break` or `continue` outside of a loop.
+    pub fn eval_expr(&mut self, db: &Database, idx: ExprIdx) -> Result<Value, RuntimeError> {
+        self.expr(db, idx).map_err(Unwind::into_error)
+    }
+
+    fn stmt(&mut self, db: &Database, stmt: &HirStmt) -> Result<Option<Value>, Unwind> {
+        match stmt {
+            HirStmt::VariableDef { local, value } => {
+                let value = self.expr(db, *value)?;
+                self.frame_mut().insert(*local, value);
+                Ok(None)
+            }
+            HirStmt::FnDef(_) => Ok(None),
+            HirStmt::Expr(expr) => self.expr(db, *expr).map(Some),
+        }
+    }
+
+    fn expr(&mut self, db: &Database, idx: ExprIdx) -> Result<Value, Unwind> {
+        let value = match &db.exprs[idx] {
+            HirExpr::Assign { place, op, value } => self.eval_assign(db, *place, *op, *value)?,
+            HirExpr::Binary {
+                op: op @ (BinaryOp::And | BinaryOp::Or),
+                lhs,
+                rhs,
+            } => {
+                // Short-circuits: the right-hand side only runs if it decides the result.
+                let lhs = expect_bool(self.expr(db, *lhs)?)?;
+                if lhs == (*op == BinaryOp::Or) {
+                    return Ok(Value::Bool(lhs));
+                }
+                Value::Bool(expect_bool(self.expr(db, *rhs)?)?)
+            }
+            HirExpr::Binary { op, lhs, rhs } => {
+                let lhs = self.expr(db, *lhs)?;
+                let rhs = self.expr(db, *rhs)?;
+                eval_binary(*op, lhs, rhs, self.overflow)?
+            }
+            HirExpr::Block { stmts, tail } => {
+                for stmt in stmts {
+                    self.stmt(db, stmt)?;
+                }
+
+                tail.map_or(Ok(Value::Unit), |tail| self.expr(db, tail))?
+            }
+            HirExpr::Call { callee, args } => {
+                let callee = self.expr(db, *callee)?;
+                let args = args
+                    .iter()
+                    .map(|arg| self.expr(db, *arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.call(db, callee, args)?
+            }
+            HirExpr::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                if expect_bool(self.expr(db, *condition)?)? {
+                    self.expr(db, *then_branch)?
+                } else {
+                    else_branch.map_or(Ok(Value::Unit), |else_branch| self.expr(db, else_branch))?
+                }
+            }
+            HirExpr::While { condition, body } => {
+                while expect_bool(self.expr(db, *condition)?)? {
+                    match self.expr(db, *body) {
+                        Ok(_) | Err(Unwind::Continue) => {}
+                        Err(Unwind::Break(_)) => break,
+                        Err(error @ Unwind::Error(_)) => return Err(error),
+                    }
+                }
+                Value::Unit
+            }
+            HirExpr::Loop { body } => loop {
+                match self.expr(db, *body) {
+                    Ok(_) | Err(Unwind::Continue) => {}
+                    Err(Unwind::Break(value)) => break value,
+                    Err(error @ Unwind::Error(_)) => return Err(error),
+                }
+            },
+            HirExpr::Break { value } => {
+                let value = value.map_or(Ok(Value::Unit), |value| self.expr(db, value))?;
+                return Err(Unwind::Break(value));
+            }
+            HirExpr::Continue => return Err(Unwind::Continue),
+            HirExpr::Literal(literal) => Value::from(literal),
+            HirExpr::Unary { op, expr } => {
+                let operand = self.expr(db, *expr)?;
+                eval_unary(*op, operand, self.overflow)?
+            }
+            HirExpr::VariableRef { var } => {
+                return Err(RuntimeError::UndefinedVariable { name: var.clone() }.into())
+            }
+            HirExpr::LocalRef(local) => {
+                self.lookup(*local)
+                    .ok_or_else(|| RuntimeError::UndefinedVariable {
+                        name: db.locals[*local].name.clone(),
+                    })?
+            }
+            HirExpr::FnRef(function) => Value::Fn(*function),
+            HirExpr::Missing => return Err(RuntimeError::MissingExpr.into()),
+        };
+
+        Ok(value)
+    }
+
+    fn eval_assign(
+        &mut self,
+        db: &Database,
+        place: ExprIdx,
+        op: Option<BinaryOp>,
+        value: ExprIdx,
+    ) -> Result<Value, Unwind> {
+        let local = match &db.exprs[place] {
+            HirExpr::LocalRef(local) => *local,
+            HirExpr::VariableRef { var } => {
+                return Err(RuntimeError::UndefinedVariable { name: var.clone() }.into())
+            }
+            HirExpr::Missing => return Err(RuntimeError::MissingExpr.into()),
+            _ => return Err(RuntimeError::NotAssignable.into()),
+        };
+        let undefined = || RuntimeError::UndefinedVariable {
+            name: db.locals[local].name.clone(),
+        };
+
+        let mut value = self.expr(db, value)?;
+        if let Some(op) = op {
+            let current = self.lookup(local).ok_or_else(undefined)?;
+            value = eval_binary(op, current, value, self.overflow)?;
+        }
+        self.assign(local, value).ok_or_else(undefined)?;
+
+        Ok(Value::Unit)
+    }
+
+    fn call(
+        &mut self,
+        db: &Database,
+        callee: Value,
+        args: Vec<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let Value::Fn(idx) = callee else {
+            return Err(RuntimeError::NotCallable { found: callee });
+        };
+        let function = &db.fns[idx];
+
+        if function.params.len() != args.len() {
+            return Err(RuntimeError::ArityMismatch {
+                expected: function.params.len(),
+                found: args.len(),
+            });
+        }
+
+        if self.frames.len() >= MAX_CALL_DEPTH {
+            return Err(RuntimeError::StackOverflow);
+        }
+
+        self.frames
+            .push(function.params.iter().copied().zip(args).collect());
+        // A `break` or `continue` cannot reach a loop outside of the function.
+        let result = self.eval_expr(db, function.body);
+        self.frames.pop();
+
+        result
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame {
+        self.frames.last_mut().unwrap_or(&mut self.globals)
+    }
+
+    fn lookup(&self, local: LocalId) -> Option<Value> {
+        self.frames
+            .last()
+            .and_then(|frame| frame.get(&local))
+            .or_else(|| self.globals.get(&local))
+            .cloned()
+    }
+
+    /// Overwrites the value of `local`, which must already be bound by its `let`.
+    fn assign(&mut self, local: LocalId, value: Value) -> Option<()> {
+        let frame = match self.frames.last_mut() {
+            Some(frame) if frame.contains_key(&local) => frame,
+            _ => &mut self.globals,
+        };
+        *frame.get_mut(&local)? = value;
+        Some(())
+    }
+
+    /// Value of a top-level local, if its definition has been evaluated.
+    pub fn global(&self, local: LocalId) -> Option<Value> {
+        self.globals.get(&local).cloned()
+    }
+}
+
+/// Why evaluation of an expression stopped before producing a value.
+#[derive(Debug)]
+enum Unwind {
+    Error(RuntimeError),
+    /// A `break` making its way to the innermost loop.
+    Break(Value),
+    /// A `continue` making its way to the innermost loop.
+    Continue,
+}
+
+impl Unwind {
+    /// Turns a `break` or `continue` that escaped every loop into an error.
+    fn into_error(self) -> RuntimeError {
+        match self {
+            Self::Error(error) => error,
+            Self::Break(_) => RuntimeError::BreakOutsideLoop,
+            Self::Continue => RuntimeError::ContinueOutsideLoop,
+        }
+    }
+}
+
+impl From<RuntimeError> for Unwind {
+    fn from(error: RuntimeError) -> Self {
+        Self::Error(error)
+    }
+}
+
+fn expect_int(value: Value) -> Result<i64, RuntimeError> {
+    match value {
+        Value::Int(n) => Ok(n),
+        found => Err(RuntimeError::TypeMismatch {
+            expected: "integer",
+            found,
+        }),
+    }
+}
+
+fn expect_float(value: Value) -> Result<f64, RuntimeError> {
+    match value {
+        Value::Float(x) => Ok(x),
+        found => Err(RuntimeError::TypeMismatch {
+            expected: "float",
+            found,
+        }),
+    }
+}
+
+pub fn expect_bool(value: Value) -> Result<bool, RuntimeError> {
+    match value {
+        Value::Bool(b) => Ok(b),
+        found => Err(RuntimeError::TypeMismatch {
+            expected: "boolean",
+            found,
+        }),
+    }
+}
+
+/// Evaluates every operator but the short-circuiting `&&` and `||`.
+pub fn eval_binary(
+    op: BinaryOp,
+    lhs: Value,
+    rhs: Value,
+    overflow: Overflow,
+) -> Result<Value, RuntimeError> {
+    if matches!(op, BinaryOp::Eq | BinaryOp::Ne) {
+        // Values of any kind can be compared, but only with another of the same kind.
+        if lhs.kind_name() != rhs.kind_name() {
+            return Err(RuntimeError::TypeMismatch {
+                expected: lhs.kind_name(),
+                found: rhs,
+            });
+        }
+        return Ok(Value::Bool((lhs == rhs) == (op == BinaryOp::Eq)));
+    }
+
+    if let (BinaryOp::Add, Value::String(l), Value::String(r)) = (op, &lhs, &rhs) {
+        return Ok(Value::String([&**l, &**r].concat().into()));
+    }
+
+    if let Value::Float(l) = lhs {
+        // Floats never mix with integers, and follow IEEE 754 rather than raising errors.
+        let r = expect_float(rhs)?;
+        return Ok(match op {
+            BinaryOp::Add => Value::Float(l + r),
+            BinaryOp::Sub => Value::Float(l - r),
+            BinaryOp::Mul => Value::Float(l * r),
+            BinaryOp::Div => Value::Float(l / r),
+            BinaryOp::Lt => Value::Bool(l < r),
+            BinaryOp::Le => Value::Bool(l <= r),
+            BinaryOp::Gt => Value::Bool(l > r),
+            BinaryOp::Ge => Value::Bool(l >= r),
+            BinaryOp::Eq | BinaryOp::Ne | BinaryOp::And | BinaryOp::Or => unreachable!(),
+        });
+    }
+
+    let l = expect_int(lhs)?;
+    let r = expect_int(rhs)?;
+
+    let (checked, wrapped) = match op {
+        BinaryOp::Add => (l.checked_add(r), l.wrapping_add(r)),
+        BinaryOp::Sub => (l.checked_sub(r), l.wrapping_sub(r)),
+        BinaryOp::Mul => (l.checked_mul(r), l.wrapping_mul(r)),
+        BinaryOp::Div => {
+            // Dividing by zero fails however overflow is handled.
+            if r == 0 {
+                return Err(RuntimeError::DivisionByZero);
+            }
+            (l.checked_div(r), l.wrapping_div(r))
+        }
+        BinaryOp::Lt => return Ok(Value::Bool(l < r)),
+        BinaryOp::Le => return Ok(Value::Bool(l <= r)),
+        BinaryOp::Gt => return Ok(Value::Bool(l > r)),
+        BinaryOp::Ge => return Ok(Value::Bool(l >= r)),
+        BinaryOp::Eq | BinaryOp::Ne | BinaryOp::And | BinaryOp::Or => unreachable!(),
+    };
+
+    match (checked, overflow) {
+        (Some(n), _) => Ok(Value::Int(n)),
+        (None, Overflow::Wrapping) => Ok(Value::Int(wrapped)),
+        (None, Overflow::Checked) => Err(RuntimeError::BinaryOverflow { op, lhs: l, rhs: r }),
+    }
+}
+
+pub fn eval_unary(op: UnaryOp, operand: Value, overflow: Overflow) -> Result<Value, RuntimeError> {
+    match op {
+        UnaryOp::Neg => {
+            if let Value::Float(x) = operand {
+                return Ok(Value::Float(-x));
+            }
+            let n = expect_int(operand)?;
+            match (n.checked_neg(), overflow) {
+                (Some(negated), _) => Ok(Value::Int(negated)),
+                (None, Overflow::Wrapping) => Ok(Value::Int(n.wrapping_neg())),
+                (None, Overflow::Checked) => Err(RuntimeError::UnaryOverflow { op, operand: n }),
+            }
+        }
+        UnaryOp::Not => expect_bool(operand).map(|b| Value::Bool(!b)),
+    }
+}
+
+/// Evaluates every statement in order with a fresh [`Evaluator`], collecting the values of
+/// expression statements.
+///
+/// # Errors
+///
+/// Returns the first [`RuntimeError`] encountered; later statements are not evaluated.
+pub fn eval(db: &Database, stmts: &[HirStmt]) -> Result<Vec<Value>, RuntimeError> {
+    let mut evaluator = Evaluator::default();
+    let mut values = Vec::new();
+
+    for stmt in stmts {
+        if let Some(value) = evaluator.eval_stmt(db, stmt)? {
+            values.push(value);
+        }
+    }
+
+    Ok(values)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast, codegen, hir, parser};
+    use std::thread;
+
+    fn lower(input: &str) -> (Database, Vec<HirStmt>) {
+        let root = ast::Root::cast(parser::parse(input).syntax()).unwrap();
+        let (mut db, stmts) = hir::lower(&root);
+        hir::resolve(&mut db, &stmts);
+        (db, stmts)
+    }
+
+    fn run(input: &str) -> Result<Vec<Value>, RuntimeError> {
+        run_with(input, Overflow::Checked)
+    }
+
+    /// Evaluates `input`, checking that the compiled program agrees.
+    fn run_with(input: &str, overflow: Overflow) -> Result<Vec<Value>, RuntimeError> {
+        let (db, stmts) = lower(input);
+        let mut evaluator = Evaluator::default().with_overflow(overflow);
+        let result = stmts
+            .iter()
+            .filter_map(|stmt| evaluator.eval_stmt(&db, stmt).transpose())
+            .collect();
+
+        let program = codegen::compile(&db, &stmts);
+        assert_eq!(codegen::run(&program, overflow), result, "{program}");
+        result
+    }
+
+    fn check(input: &str, expected: &[i64]) {
+        let expected: Vec<_> = expected.iter().copied().map(Value::Int).collect();
+        assert_eq!(run(input), Ok(expected));
+    }
+
+    fn check_error(input: &str, expected: RuntimeError) {
+        assert_eq!(run(input), Err(expected));
+    }
+
+    #[test]
+    fn eval_literal() {
+        check("42", &[42]);
+    }
+
+    #[test]
+    fn eval_arithmetic_respects_precedence() {
+        check("1 + 2 * 3 - 4 / 2", &[5]);
+    }
+
+    #[test]
+    fn eval_paren_expr() {
+        check("(1 + 2) * 3", &[9]);
+    }
+
+    #[test]
+    fn eval_negation_of_zero() {
+        check("-0", &[0]);
+    }
+
+    #[test]
+    fn eval_variable_def_and_ref() {
+        check("let a = 5\nlet b = a * 2\nb + a", &[15]);
+    }
+
+    #[test]
+    fn variable_def_produces_no_value() {
+        check("let a = 5", &[]);
+    }
+
+    #[test]
+    fn later_definition_shadows_earlier() {
+        check("let a = 1\nlet a = a + 1\na", &[2]);
+    }
+
+    #[test]
+    fn each_expression_statement_yields_a_value() {
+        check("1\n2\n3", &[1, 2, 3]);
+    }
+
+    #[test]
+    fn division_by_zero() {
+        check_error("10 / (5 - 5)", RuntimeError::DivisionByZero);
+    }
+
+    #[test]
+    fn addition_overflow() {
+        check_error(
+            "9223372036854775807 + 1",
+            RuntimeError::BinaryOverflow {
+                op: BinaryOp::Add,
+                lhs: i64::MAX,
+                rhs: 1,
+            },
+        );
+    }
+
+    #[test]
+    fn subtraction_below_zero() {
+        check("1 - 2; -5 - -3", &[-1, -2]);
+    }
+
+    #[test]
+    fn subtraction_overflow() {
+        check_error(
+            "-9223372036854775808 - 1",
+            RuntimeError::BinaryOverflow {
+                op: BinaryOp::Sub,
+                lhs: i64::MIN,
+                rhs: 1,
+            },
+        );
+    }
+
+    #[test]
+    fn division_overflow() {
+        check_error(
+            "-9223372036854775808 / -1",
+            RuntimeError::BinaryOverflow {
+                op: BinaryOp::Div,
+                lhs: i64::MIN,
+                rhs: -1,
+            },
+        );
+    }
+
+    #[test]
+    fn division_truncates_towards_zero() {
+        check("7 / 2; -7 / 2", &[3, -3]);
+    }
+
+    #[test]
+    fn eval_negative_literals() {
+        check("-10; -9223372036854775808; -(-10)", &[-10, i64::MIN, 10]);
+    }
+
+    #[test]
+    fn negation_overflow() {
+        check_error(
+            "let a = -9223372036854775808\n-a",
+            RuntimeError::UnaryOverflow {
+                op: UnaryOp::Neg,
+                operand: i64::MIN,
+            },
+        );
+    }
+
+    #[test]
+    fn wrapping_overflow() {
+        assert_eq!(
+            run_with(
+                "let a = 9223372036854775807
+                a + 1; -(a + 1); a * 2; (a + 1) / -1; a - -1 == a + 1",
+                Overflow::Wrapping
+            ),
+            Ok(vec![
+                Value::Int(i64::MIN),
+                Value::Int(i64::MIN),
+                Value::Int(-2),
+                Value::Int(i64::MIN),
+                Value::Bool(true),
+            ])
+        );
+    }
+
+    #[test]
+    fn wrapping_still_divides_by_zero() {
+        assert_eq!(
+            run_with("1 / 0", Overflow::Wrapping),
+            Err(RuntimeError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn undefined_variable() {
+        check_error(
+            "foo",
+            RuntimeError::UndefinedVariable { name: "foo".into() },
+        );
+    }
+
+    #[test]
+    fn missing_expr() {
+        check_error("1 +", RuntimeError::MissingExpr);
+    }
+
+    #[test]
+    fn literal_too_large_is_missing() {
+        check_error("99999999999999999999", RuntimeError::MissingExpr);
+    }
+
+    #[test]
+    fn fn_def_produces_no_value() {
+        check("fn f() { 1 }", &[]);
+    }
+
+    #[test]
+    fn call_fn() {
+        check("fn add(a, b) { a + b }\nadd(1, 2)", &[3]);
+    }
+
+    #[test]
+    fn fn_returns_last_expression() {
+        check("fn f(x) {\n  let y = x * 2\n  y + 1\n}\nf(5)", &[11]);
+    }
+
+    #[test]
+    fn fn_without_trailing_expression_returns_unit() {
+        assert_eq!(run("fn f() { let a = 1 }\nf()"), Ok(vec![Value::Unit]));
+    }
+
+    #[test]
+    fn fn_sees_globals() {
+        check("let a = 10\nfn f(x) { x + a }\nf(1)", &[11]);
+    }
+
+    #[test]
+    fn fn_locals_do_not_leak() {
+        check_error(
+            "fn f(x) { let y = x }\nf(1)\ny",
+            RuntimeError::UndefinedVariable { name: "y".into() },
+        );
+    }
+
+    #[test]
+    fn param_shadows_global() {
+        check("let x = 1\nfn f(x) { x }\nf(2)\nx", &[2, 1]);
+    }
+
+    #[test]
+    fn fn_does_not_see_callers_locals() {
+        check_error(
+            "fn g() { y }\nfn f(y) { g() }\nf(1)",
+            RuntimeError::UndefinedVariable { name: "y".into() },
+        );
+    }
+
+    #[test]
+    fn fn_can_call_other_fns() {
+        check(
+            "fn double(x) { x * 2 }\nfn quad(x) { double(double(x)) }\nquad(3)",
+            &[12],
+        );
+    }
+
+    #[test]
+    fn arity_mismatch() {
+        check_error(
+            "fn f(a) { a }\nf(1, 2)",
+            RuntimeError::ArityMismatch {
+                expected: 1,
+                found: 2,
+            },
+        );
+    }
+
+    #[test]
+    fn call_non_fn() {
+        check_error(
+            "let a = 1\na()",
+            RuntimeError::NotCallable {
+                found: Value::Int(1),
+            },
+        );
+    }
+
+    #[test]
+    fn arithmetic_on_fn() {
+        let (db, stmts) = lower("fn f() { 1 }\nf + 1");
+        let HirStmt::FnDef(idx) = stmts[0] else {
+            unreachable!()
+        };
+
+        assert_eq!(
+            eval(&db, &stmts),
+            Err(RuntimeError::TypeMismatch {
+                expected: "integer",
+                found: Value::Fn(idx),
+            })
+        );
+    }
+
+    /// Calls `f` on a thread with a stack of [`EVAL_STACK_SIZE`].
+    fn with_eval_stack(f: impl FnOnce() + Send) {
+        thread::scope(|scope| {
+            thread::Builder::new()
+                .stack_size(EVAL_STACK_SIZE)
+                .spawn_scoped(scope, f)
+                .unwrap()
+                .join()
+                .unwrap();
+        });
+    }
+
+    #[test]
+    fn unbounded_recursion() {
+        with_eval_stack(|| check_error("fn f() { f() }\nf()", RuntimeError::StackOverflow));
+    }
+
+    #[test]
+    fn recursion_up_to_the_call_depth_limit() {
+        let count_down = "fn f(n) { if n == 0 { 0 } else { 1 + f(n - 1) } }";
+        let depth = i64::try_from(MAX_CALL_DEPTH).unwrap();
+
+        with_eval_stack(|| {
+            check(&format!("{count_down}\nf({})", depth - 1), &[depth - 1]);
+            check_error(
+                &format!("{count_down}\nf({depth})"),
+                RuntimeError::StackOverflow,
+            );
+        });
+    }
+
+    #[test]
+    fn eval_block_tail() {
+        check("{ let a = 2 a * 3 }", &[6]);
+    }
+
+    #[test]
+    fn empty_block_is_unit() {
+        assert_eq!(run("{}"), Ok(vec![Value::Unit]));
+    }
+
+    #[test]
+    fn block_sees_outer_bindings() {
+        check("let a = 1\n{ a + 1 }", &[2]);
+    }
+
+    #[test]
+    fn block_bindings_do_not_leak() {
+        check_error(
+            "{ let inner = 1 }\ninner",
+            RuntimeError::UndefinedVariable {
+                name: "inner".into(),
+            },
+        );
+    }
+
+    #[test]
+    fn shadowing_in_block_is_scoped() {
+        check("let a = 1\n{ let a = 2 a }\na", &[2, 1]);
+    }
+
+    #[test]
+    fn nested_blocks_see_enclosing_scopes() {
+        check("{ let a = 1 { let b = 2 { a + b } } }", &[3]);
+    }
+
+    #[test]
+    fn fn_in_block_does_not_see_callers_block() {
+        check_error(
+            "fn f() { y }\n{ let y = 1 f() }",
+            RuntimeError::UndefinedVariable { name: "y".into() },
+        );
+    }
+
+    #[test]
+    fn calls_restore_caller_scopes() {
+        check(
+            "fn f(n) { { let m = n } n }\nlet n = 7\n{ let n = 3 f(n) + n }\nn",
+            &[6, 7],
+        );
+    }
+
+    #[test]
+    fn fn_called_before_its_definition() {
+        check("f()\nfn f() { 4 }", &[4]);
+    }
+
+    #[test]
+    fn fn_sees_binding_visible_at_its_definition() {
+        check("let a = 1\nfn f() { a }\nlet a = 2\nf() + a", &[3]);
+    }
+
+    #[track_caller]
+    fn check_bools(input: &str, expected: &[bool]) {
+        let expected: Vec<_> = expected.iter().copied().map(Value::Bool).collect();
+        assert_eq!(run(input), Ok(expected));
+    }
+
+    #[test]
+    fn eval_comparisons() {
+        check_bools(
+            "1 < 2; 2 <= 2; 1 > 2; 1 >= 2; 3 == 3; 3 != 3",
+            &[true, true, false, false, true, false],
+        );
+    }
+
+    #[test]
+    fn eval_logical_operators() {
+        check_bools(
+            "true && false; true || false; !true; !!true",
+            &[false, true, false, true],
+        );
+    }
+
+    #[test]
+    fn comparison_binds_looser_than_arithmetic() {
+        check_bools("1 + 2 * 3 == 7 && 10 / 2 > 4 || false", &[true]);
+    }
+
+    #[test]
+    fn logical_operators_short_circuit() {
+        check_bools("false && 1 / 0 == 0; true || 1 / 0 == 0", &[false, true]);
+    }
+
+    #[test]
+    fn equality_of_bools_and_fns() {
+        check_bools(
+            "fn f() { 1 }\nfn g() { 1 }\ntrue == !false; f == f; f == g",
+            &[true, true, false],
+        );
+    }
+
+    #[test]
+    fn arithmetic_on_bool() {
+        check_error(
+            "true + 1",
+            RuntimeError::TypeMismatch {
+                expected: "integer",
+                found: Value::Bool(true),
+            },
+        );
+    }
+
+    #[test]
+    fn not_on_integer() {
+        check_error(
+            "!1",
+            RuntimeError::TypeMismatch {
+                expected: "boolean",
+                found: Value::Int(1),
+            },
+        );
+    }
+
+    #[test]
+    fn comparing_different_kinds() {
+        check_error(
+            "1 == true",
+            RuntimeError::TypeMismatch {
+                expected: "integer",
+                found: Value::Bool(true),
+            },
+        );
+    }
+
+    #[test]
+    fn eval_if_else() {
+        check(
+            "if 1 < 2 { 10 } else { 20 }; if 1 > 2 { 10 } else { 20 }",
+            &[10, 20],
+        );
+    }
+
+    #[test]
+    fn eval_else_if_chain() {
+        check(
+            "fn sign(n) { if n == 0 { 0 } else if n < 10 { 1 } else { 2 } }\nsign(0); sign(5); sign(50)",
+            &[0, 1, 2],
+        );
+    }
+
+    #[test]
+    fn if_without_else_is_unit() {
+        assert_eq!(run("if false { 1 }"), Ok(vec![Value::Unit]));
+    }
+
+    #[test]
+    fn untaken_branch_is_not_evaluated() {
+        check("if true { 1 } else { 1 / 0 }", &[1]);
+    }
+
+    #[test]
+    fn if_condition_must_be_bool() {
+        check_error(
+            "if 1 { 2 }",
+            RuntimeError::TypeMismatch {
+                expected: "boolean",
+                found: Value::Int(1),
+            },
+        );
+    }
+
+    #[test]
+    fn recursion_terminates_through_if() {
+        check(
+            "fn fact(n) { if n == 0 { 1 } else { n * fact(n - 1) } }\nfact(5)",
+            &[120],
+        );
+    }
+
+    #[test]
+    fn loop_produces_break_value() {
+        check(
+            "loop { break 5 }; loop { { if true { break 7 } } }",
+            &[5, 7],
+        );
+        assert_eq!(run("loop { break }"), Ok(vec![Value::Unit]));
+    }
+
+    #[test]
+    fn break_leaves_innermost_loop() {
+        check("loop { loop { break 1 }; break 2 }", &[2]);
+    }
+
+    #[test]
+    fn while_runs_while_condition_holds() {
+        assert_eq!(
+            run("while false { 1 / 0 }; while true { break }"),
+            Ok(vec![Value::Unit, Value::Unit])
+        );
+    }
+
+    #[test]
+    fn while_condition_must_be_bool() {
+        check_error(
+            "while 1 {}",
+            RuntimeError::TypeMismatch {
+                expected: "boolean",
+                found: Value::Int(1),
+            },
+        );
+    }
+
+    #[test]
+    fn break_and_continue_outside_loop() {
+        check_error("continue", RuntimeError::ContinueOutsideLoop);
+        check_error(
+            "fn f() { break }\nloop { f() }",
+            RuntimeError::BreakOutsideLoop,
+        );
+    }
+
+    #[test]
+    fn assignment_updates_variable() {
+        assert_eq!(
+            run("let a = 1\na = a + 1\na"),
+            Ok(vec![Value::Unit, Value::Int(2)])
+        );
+    }
+
+    #[test]
+    fn compound_assignment() {
+        check("let a = 10\n{ a -= 4; a *= 3; a /= 2; a += 1; a }", &[10]);
+    }
+
+    #[test]
+    fn while_loop_with_counter() {
+        check(
+            "let i = 0\nlet sum = 0\n{ while i < 10 { i += 1; if i == 5 { continue }; sum += i }; sum }",
+            &[50],
+        );
+    }
+
+    #[test]
+    fn loop_breaks_with_computed_value() {
+        check(
+            "let i = 0\nloop { i += 1; if i * i > 50 { break i } }",
+            &[8],
+        );
+    }
+
+    #[test]
+    fn fns_assign_to_globals() {
+        check(
+            "let n = 0\nfn bump() { n += 1 }\n{ bump(); bump(); n }",
+            &[2],
+        );
+    }
+
+    #[test]
+    fn compound_assignment_overflow() {
+        check_error(
+            "let a = 9223372036854775807\na += 1",
+            RuntimeError::BinaryOverflow {
+                op: BinaryOp::Add,
+                lhs: i64::MAX,
+                rhs: 1,
+            },
+        );
+    }
+
+    #[test]
+    fn assign_to_fn() {
+        check_error("fn f() {}\nf = 1", RuntimeError::NotAssignable);
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(s.into())
+    }
+
+    #[test]
+    fn eval_string_literal() {
+        assert_eq!(
+            run(r#""line\n\"quoted\" \u{1F600}""#),
+            Ok(vec![string("line\n\"quoted\" \u{1F600}")])
+        );
+    }
+
+    #[test]
+    fn concatenate_strings() {
+        assert_eq!(
+            run(r#"let a = "foo"
+a + "bar""#),
+            Ok(vec![string("foobar")])
+        );
+    }
+
+    #[test]
+    fn compare_strings() {
+        check_bools(
+            r#""a" == "a"; "a" != "a"; "a" == "b""#,
+            &[true, false, false],
+        );
+    }
+
+    #[test]
+    fn strings_are_not_ordered() {
+        check_error(
+            r#""a" < "b""#,
+            RuntimeError::TypeMismatch {
+                expected: "integer",
+                found: string("a"),
+            },
+        );
+    }
+
+    #[test]
+    fn invalid_string_is_missing() {
+        check_error(r#""\q""#, RuntimeError::MissingExpr);
+    }
+
+    fn floats(input: &str) -> Vec<Value> {
+        run(input).unwrap()
+    }
+
+    #[test]
+    fn eval_radix_literals() {
+        check("0xff; 0o17; 0b1010; 1_000", &[255, 15, 10, 1000]);
+    }
+
+    #[test]
+    fn eval_float_arithmetic() {
+        assert_eq!(
+            floats("1.5 + 2.25; 1e-3 * 2.0; 1.0 / 4.0; -2.5"),
+            [
+                Value::Float(3.75),
+                Value::Float(0.002),
+                Value::Float(0.25),
+                Value::Float(-2.5)
+            ]
+        );
+        check_bools(
+            "1.5 < 2.0; 1.5 == 1.5; 0.1 + 0.2 == 0.3",
+            &[true, true, false],
+        );
+    }
+
+    #[test]
+    fn float_division_by_zero_is_infinite() {
+        assert_eq!(floats("1.0 / 0.0"), [Value::Float(f64::INFINITY)]);
+    }
+
+    #[test]
+    fn floats_and_integers_do_not_mix() {
+        check_error(
+            "1.5 + 1",
+            RuntimeError::TypeMismatch {
+                expected: "float",
+                found: Value::Int(1),
+            },
+        );
+        check_error(
+            "1 + 1.5",
+            RuntimeError::TypeMismatch {
+                expected: "integer",
+                found: Value::Float(1.5),
+            },
+        );
+    }
+
+    #[test]
+    fn mutual_recursion() {
+        check("fn a(n) { b(n) }\nfn b(n) { n * 2 }\na(21)", &[42]);
+    }
+}
@@ -0,0 +1,9 @@
+//! Tree-walking evaluation of lowered HIR.
+mod error;
+mod interp;
+mod value;
+
+pub use error::RuntimeError;
+pub use interp::{eval, Evaluator, Overflow, EVAL_STACK_SIZE, MAX_CALL_DEPTH};
+pub(crate) use interp::{eval_binary, eval_unary, expect_bool};
+pub use value::Value;
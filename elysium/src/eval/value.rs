@@ -0,0 +1,55 @@
+use crate::{
+    hir::{FnIdx, Literal},
+    lexer,
+};
+use std::{fmt, rc::Rc};
+
+/// Runtime value produced by evaluating a [`HirExpr`](crate::hir::HirExpr).
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    /// Shared, as strings are never modified in place.
+    String(Rc<str>),
+    Fn(FnIdx),
+    Unit,
+}
+
+impl Value {
+    /// Short human-readable name of this value's kind, for error messages.
+    pub const fn kind_name(&self) -> &'static str {
+        match self {
+            Self::Int(_) => "integer",
+            Self::Float(_) => "float",
+            Self::Bool(_) => "boolean",
+            Self::String(_) => "string",
+            Self::Fn(_) => "function",
+            Self::Unit => "unit",
+        }
+    }
+}
+
+impl From<&Literal> for Value {
+    fn from(literal: &Literal) -> Self {
+        match literal {
+            Literal::Int(n) => Self::Int(*n),
+            Literal::Float(x) => Self::Float(*x),
+            Literal::Bool(b) => Self::Bool(*b),
+            Literal::String(s) => Self::String(s.as_str().into()),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(n) => write!(f, "{n}"),
+            Self::Float(x) => write!(f, "{x:?}"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::String(s) => lexer::write_escaped(f, s),
+            Self::Fn(_) => write!(f, "<fn>"),
+            Self::Unit => write!(f, "()"),
+        }
+    }
+}
@@ -0,0 +1,485 @@
+//! Formatting of source code by reprinting its lossless syntax tree.
+//!
+//! Every token and comment of the input is kept, and only the whitespace between them
+//! changes:
+//!
+//! - binary and assignment operators, `->`, and the keywords get a space on each side,
+//!   commas and colons one after them, and parentheses and prefix operators none inside;
+//! - statements go on lines of their own, indented four spaces for each enclosing block,
+//!   with at most one blank line between them and their `;` separators dropped;
+//! - a block written on one line stays on one line, as `{ a; b }`;
+//! - a comment stays at the end of its line, or on a line of its own;
+//! - line breaks are written as `\n`, including `\r\n` ones.
+//!
+//! Line breaks decide where statements end, so input with parse errors is left alone.
+
+use crate::{
+    parse,
+    syntax::{SyntaxElement, SyntaxKind, SyntaxNode, SyntaxToken},
+    trimmed_range, Diagnostic,
+};
+use rowan::TextSize;
+use std::mem;
+
+const INDENT: &str = "    ";
+
+/// Formats `input`, which is returned unchanged if it is already formatted.
+///
+/// # Errors
+///
+/// Returns the parse errors as [`Diagnostic`]s if `input` does not parse.
+pub fn format(input: &str) -> Result<String, Vec<Diagnostic>> {
+    let parse = parse(input);
+    if !parse.errors().is_empty() {
+        return Err(parse.diagnostics());
+    }
+
+    let mut formatter = Formatter::default();
+    formatter.stmts(&parse.syntax(), TextSize::default());
+    if !formatter.out.is_empty() {
+        formatter.out.push('\n');
+    }
+    Ok(formatter.out)
+}
+
+#[derive(Default)]
+struct Formatter {
+    out: String,
+    indent: usize,
+    /// The last token written on the current line, or `None` at the start of a line.
+    last: Option<SyntaxToken>,
+    /// Line breaks in the input since the last token or comment written.
+    newlines: usize,
+    /// Whether the current line ends in a comment, so the next token has to start a new one.
+    after_comment: bool,
+    /// End of the current statement's last token. Comments after it are laid out as part of
+    /// the enclosing list of statements.
+    stmt_end: TextSize,
+}
+
+impl Formatter {
+    /// Writes the statements of the root or of a multi-line block, each on a line of its own.
+    /// `outer_end` is the end of the statement the block is part of.
+    fn stmts(&mut self, node: &SyntaxNode, outer_end: TextSize) {
+        for child in node.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(stmt) => {
+                    self.new_line(self.indent, true);
+                    self.stmt(&stmt);
+                }
+                SyntaxElement::Token(token) => match token.kind() {
+                    SyntaxKind::Semicolon => {}
+                    SyntaxKind::LBrace => {
+                        self.token(&token);
+                        self.indent += 1;
+                    }
+                    SyntaxKind::RBrace => {
+                        self.indent -= 1;
+                        self.stmt_end = outer_end;
+                        self.new_line(self.indent, false);
+                        self.token(&token);
+                    }
+                    _ => self.element_token(&token),
+                },
+            }
+        }
+    }
+
+    fn stmt(&mut self, stmt: &SyntaxNode) {
+        let outer_end = mem::replace(&mut self.stmt_end, trimmed_range(stmt).end());
+        if stmt.kind() == SyntaxKind::BlockExpr {
+            self.block(stmt);
+        } else {
+            self.node(stmt);
+        }
+        self.stmt_end = outer_end;
+    }
+
+    fn node(&mut self, node: &SyntaxNode) {
+        for child in node.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(block) if block.kind() == SyntaxKind::BlockExpr => {
+                    self.block(&block);
+                }
+                SyntaxElement::Node(node) => self.node(&node),
+                SyntaxElement::Token(token) => self.element_token(&token),
+            }
+        }
+    }
+
+    fn block(&mut self, block: &SyntaxNode) {
+        if is_multi_line(block) {
+            let outer_end = mem::take(&mut self.stmt_end);
+            self.stmts(block, outer_end);
+            return;
+        }
+
+        let mut stmts = 0;
+        for child in block.children_with_tokens() {
+            match child {
+                SyntaxElement::Node(stmt) => {
+                    if stmts > 0 {
+                        self.out.push(';');
+                    }
+                    self.out.push(' ');
+                    self.last = None;
+                    self.stmt(&stmt);
+                    stmts += 1;
+                }
+                SyntaxElement::Token(token) if token.kind() == SyntaxKind::LBrace => {
+                    self.token(&token);
+                }
+                SyntaxElement::Token(token) if token.kind() == SyntaxKind::RBrace => {
+                    if stmts > 0 {
+                        self.out.push(' ');
+                    }
+                    self.out.push('}');
+                    self.last = Some(token);
+                }
+                SyntaxElement::Token(token) if token.kind() == SyntaxKind::Semicolon => {}
+                // Only spaces are left before the `}`, and comments can follow it.
+                SyntaxElement::Token(token) => self.element_token(&token),
+            }
+        }
+    }
+
+    /// Writes a token found inside a statement, or between statements.
+    fn element_token(&mut self, token: &SyntaxToken) {
+        match token.kind() {
+            SyntaxKind::Whitespace => {}
+            SyntaxKind::Newline => self.newlines += token.text().matches('\n').count(),
+            SyntaxKind::Comment => self.comment(token),
+            _ => self.token(token),
+        }
+    }
+
+    fn token(&mut self, token: &SyntaxToken) {
+        if self.after_comment {
+            self.new_line(self.indent + 1, true);
+        } else if self
+            .last
+            .as_ref()
+            .is_some_and(|last| space_between(last, token))
+        {
+            self.out.push(' ');
+        }
+
+        self.out.push_str(token.text());
+        self.last = Some(token.clone());
+        self.newlines = 0;
+    }
+
+    fn comment(&mut self, comment: &SyntaxToken) {
+        if self.newlines > 0 || self.out.is_empty() {
+            // Own-line comments inside a statement are indented like the rest of it.
+            let indent = if comment.text_range().start() < self.stmt_end {
+                self.indent + 1
+            } else {
+                self.indent
+            };
+            self.new_line(indent, true);
+        } else {
+            self.out.push(' ');
+        }
+
+        self.out.push_str(comment.text().trim_end());
+        self.after_comment = true;
+        self.newlines = 0;
+    }
+
+    /// Starts a new line, leaving a blank line before it if the input had one and `blank`
+    /// allows it.
+    fn new_line(&mut self, indent: usize, blank: bool) {
+        if !self.out.is_empty() {
+            self.out.push('\n');
+            if blank && self.newlines > 1 && !self.out.ends_with("{\n") {
+                self.out.push('\n');
+            }
+        }
+
+        for _ in 0..indent {
+            self.out.push_str(INDENT);
+        }
+        self.last = None;
+        self.after_comment = false;
+    }
+}
+
+/// Whether a block has to be laid out over several lines: it does if it has statements and
+/// was written over several lines, or if it contains a comment.
+fn is_multi_line(block: &SyntaxNode) -> bool {
+    let mut inside = block
+        .descendants_with_tokens()
+        .filter_map(SyntaxElement::into_token)
+        .take_while(|token| {
+            token.kind() != SyntaxKind::RBrace || token.parent().as_ref() != Some(block)
+        });
+
+    if block.children().next().is_some() {
+        inside.any(|token| matches!(token.kind(), SyntaxKind::Newline | SyntaxKind::Comment))
+    } else {
+        inside.any(|token| token.kind() == SyntaxKind::Comment)
+    }
+}
+
+fn space_between(last: &SyntaxToken, next: &SyntaxToken) -> bool {
+    let parent = |token: &SyntaxToken| token.parent().map(|parent| parent.kind());
+
+    match (last.kind(), next.kind()) {
+        (SyntaxKind::LParen, _)
+        | (_, SyntaxKind::RParen | SyntaxKind::Comma | SyntaxKind::Colon) => false,
+        (SyntaxKind::Minus | SyntaxKind::Bang, _) => parent(last) != Some(SyntaxKind::PrefixExpr),
+        // Calls, parameter lists, and function types.
+        (last, SyntaxKind::LParen) => {
+            last != SyntaxKind::FnKw
+                && !matches!(
+                    parent(next),
+                    Some(SyntaxKind::ArgList | SyntaxKind::ParamList)
+                )
+        }
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::Rng;
+    use expect_test::{expect, Expect};
+    use std::fmt::Write;
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn check(input: &str, expected: Expect) {
+        let formatted = format(input).unwrap();
+        expected.assert_eq(&formatted);
+        assert_eq!(format(&formatted).unwrap(), formatted, "not idempotent");
+    }
+
+    /// The values and diagnostics of evaluating `input`, without the ranges that formatting moves.
+    fn outcome(input: &str) -> (Vec<String>, Vec<(&'static str, String)>) {
+        let evaluation = crate::Session::default().eval(input);
+        let values = evaluation.values.iter().map(ToString::to_string).collect();
+        let diagnostics = evaluation
+            .diagnostics
+            .into_iter()
+            .map(|diagnostic| (diagnostic.code, diagnostic.message))
+            .collect();
+        (values, diagnostics)
+    }
+
+    /// The syntax tree of `input` without the whitespace and semicolons that formatting changes.
+    fn structure(input: &str) -> String {
+        let mut s = String::new();
+        for element in parse(input).syntax().descendants_with_tokens() {
+            match element {
+                SyntaxElement::Node(node) => {
+                    let _ = write!(s, "{:?} ", node.kind());
+                }
+                SyntaxElement::Token(token) if token.kind() == SyntaxKind::Comment => {
+                    let _ = write!(s, "{} ", token.text().trim_end());
+                }
+                SyntaxElement::Token(token)
+                    if !token.kind().is_trivia() && token.kind() != SyntaxKind::Semicolon =>
+                {
+                    let _ = write!(s, "{} ", token.text());
+                }
+                SyntaxElement::Token(_) => {}
+            }
+        }
+        s
+    }
+
+    #[test]
+    fn format_spacing() {
+        check(
+            "let  x:Int=1+2*-3\nfn add( a:Int,b ) ->Int{a+b}\nadd (x,! true)",
+            expect![[r"
+                let x: Int = 1 + 2 * -3
+                fn add(a: Int, b) -> Int { a + b }
+                add(x, !true)
+            "]],
+        );
+    }
+
+    #[test]
+    fn format_types() {
+        check(
+            "fn apply(f:fn( Int,fn()->())->Int)->(){ }",
+            expect![[r"
+                fn apply(f: fn(Int, fn() -> ()) -> Int) -> () {}
+            "]],
+        );
+    }
+
+    #[test]
+    fn format_control_flow() {
+        check(
+            "if(a<=b)&&!c{x+=1}else if d {y}else{}\nwhile i!=0{i-=1;break}\nloop{continue}",
+            expect![[r"
+                if (a <= b) && !c { x += 1 } else if d { y } else {}
+                while i != 0 { i -= 1; break }
+                loop { continue }
+            "]],
+        );
+    }
+
+    #[test]
+    fn format_indentation() {
+        check(
+            "fn f(x) {\nlet y = {\n        x * 2\n  }\n      if y > 3 { y } else {\n0\n}\n}",
+            expect![[r"
+                fn f(x) {
+                    let y = {
+                        x * 2
+                    }
+                    if y > 3 { y } else {
+                        0
+                    }
+                }
+            "]],
+        );
+    }
+
+    #[test]
+    fn format_blank_lines_and_semicolons() {
+        check(
+            "\n\nlet a = 1;;\n\n\n\nlet b = 2; a + b\nfn f() {\n\n  a\n\n  b;\n\n}\n\n\n",
+            expect![[r"
+                let a = 1
+
+                let b = 2
+                a + b
+                fn f() {
+                    a
+
+                    b
+                }
+            "]],
+        );
+    }
+
+    #[test]
+    fn format_comments() {
+        check(
+            "# header   \nlet a = 1 # one\n\n  # two\nfn f(x) { # body\nx # x\n# end\n}\nf(a, # first\n  2) # last",
+            expect![[r"
+                # header
+                let a = 1 # one
+
+                # two
+                fn f(x) { # body
+                    x # x
+                    # end
+                }
+                f(a, # first
+                    2) # last
+            "]],
+        );
+    }
+
+    #[test]
+    fn comments_make_blocks_multi_line() {
+        check(
+            "let a = { # a\n}\nlet b = { 1 } # b",
+            expect![[r"
+                let a = { # a
+                }
+                let b = { 1 } # b
+            "]],
+        );
+    }
+
+    #[test]
+    fn empty_input() {
+        check("", expect![""]);
+        check(" \n\n ", expect![""]);
+    }
+
+    #[test]
+    fn format_crlf_and_tabs() {
+        check(
+            "fn f() {\r\n\tlet a =\t1 # a\r\n\r\n\ta\r\n}\r\n",
+            expect![[r"
+                fn f() {
+                    let a = 1 # a
+
+                    a
+                }
+            "]],
+        );
+    }
+
+    #[test]
+    fn format_bare_blocks() {
+        check(
+            "{\n  let a = 1\n  a\n}\n{\n# only\n}\n{ 2;3 }",
+            expect![[r"
+                {
+                    let a = 1
+                    a
+                }
+                {
+                    # only
+                }
+                { 2; 3 }
+            "]],
+        );
+    }
+
+    #[test]
+    fn unrecognized_characters_are_not_formatted() {
+        let errors = format("let a = 1 # café\nlet b = a @ 2\nlet c = 3\n").unwrap_err();
+        assert!(errors
+            .iter()
+            .any(|error| error.message.ends_with("but found an unrecognized token")));
+        assert!(format("let a = 1\r\n\r a").is_err());
+    }
+
+    #[test]
+    fn parse_errors_are_not_formatted() {
+        let errors = format("let a =\nlet b = 1").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, "E0001");
+    }
+
+    #[test]
+    fn randomized_formatting_is_idempotent_and_keeps_meaning() {
+        const TEXTS: [&str; 4] = [
+            "let a = 1\nfn f(x: Int, y) -> Int {\n  let z = { x * y }\n  z + a # sum\n}\nf(a, { 2 })",
+            "fn g() { { { 1 } } }\n-g() / (3 + 4)\nif g() < 2 { 1 } else { while false { break } }",
+            "let x = {\n  let y = 10\n\n  # y\n  { y - 1 }\n}\nx += !true",
+            "let n = 2\n{\n  let m = n * 3\n  # m\n  m - 1\n}\n{ n; 4 }",
+        ];
+        const INSERTS: [&str; 20] = [
+            "", "", "1", "a", " ", "  ", "\n", "\n\n", ";", "{", "}", "{ 1 }", "+", "-", "(", ")",
+            ",", "# c\n", "let b = ", "fn h() ",
+        ];
+
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let mut formatted_count = 0;
+        for seed in TEXTS {
+            for _ in 0..300 {
+                let mut text = seed.to_owned();
+                for _ in 0..=rng.below(2) {
+                    let start = rng.below(text.len() + 1);
+                    let end = (start + rng.below(4)).min(text.len());
+                    text.replace_range(start..end, INSERTS[rng.below(INSERTS.len())]);
+                }
+
+                let Ok(formatted) = format(&text) else {
+                    continue;
+                };
+                formatted_count += 1;
+
+                assert!(parse(&formatted).errors().is_empty(), "from {text:?}");
+                assert_eq!(format(&formatted).unwrap(), formatted, "from {text:?}");
+                assert_eq!(structure(&formatted), structure(&text), "from {text:?}");
+                assert_eq!(outcome(&formatted), outcome(&text), "from {text:?}");
+            }
+        }
+        assert!(
+            formatted_count > 100,
+            "only {formatted_count} inputs parsed"
+        );
+    }
+}
@@ -3,76 +3,275 @@
 use la_arena::Arena;
 
 // use crate::arena::Arena;
-use super::{BinaryOp, HirExpr, HirStmt};
-use crate::ast::{self, BinaryExpr, Stmt, UnaryExpr};
+use super::{BinaryOp, ExprIdx, HirExpr, HirFn, HirStmt, Literal, Local, SourceMap, TypeRef};
+use crate::ast::{
+    self, AssignExpr, BinaryExpr, BlockExpr, CallExpr, FnDef, IfExpr, LiteralKind, LoopExpr, Stmt,
+    UnaryExpr, WhileExpr,
+};
 use crate::hir::UnaryOp;
-use crate::syntax::SyntaxKind;
+use crate::syntax::{SyntaxKind, SyntaxToken};
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Default, PartialEq)]
 pub struct Database {
     pub exprs: Arena<HirExpr>,
+    pub fns: Arena<HirFn>,
+    pub locals: Arena<Local>,
+    pub source_map: SourceMap,
 }
 
 impl Database {
     pub fn lower_stmt(&mut self, ast: ast::Stmt) -> Option<HirStmt> {
         let result = match ast {
-            Stmt::VariableDef(var_def) => HirStmt::VariableDef {
-                name: var_def.name()?.text().into(),
-                value: self.lower_expr(var_def.value()),
-            },
+            Stmt::VariableDef(var_def) => {
+                let name = var_def.name()?;
+                let value = self.lower_expr(var_def.value());
+                let local = self.locals.alloc(Local {
+                    name: name.text().into(),
+                    ty: var_def.ty().map(|ty| Self::lower_type_ref(&ty)),
+                });
+                self.source_map.insert_local(local, var_def.syntax(), &name);
+                HirStmt::VariableDef { local, value }
+            }
+            Stmt::FnDef(fn_def) => self.lower_fn_def(&fn_def)?,
             Stmt::Expr(expr) => HirStmt::Expr(self.lower_expr(Some(expr))),
         };
 
         Some(result)
     }
 
-    pub fn lower_expr(&mut self, ast: Option<ast::Expr>) -> HirExpr {
-        ast.map_or_else(
-            || HirExpr::Missing,
-            |ast| match ast {
-                ast::Expr::BinaryExpr(binary_expr) => self.lower_binary(binary_expr),
-                ast::Expr::Literal(literal) => HirExpr::Literal { n: literal.parse() },
-                ast::Expr::ParenExpr(paren_expr) => self.lower_expr(paren_expr.expr()),
-                ast::Expr::UnaryExpr(unary_expr) => self.lower_unary(unary_expr),
-                ast::Expr::VariableRef(variable_ref) => Self::lower_var_ref(variable_ref),
+    fn lower_fn_def(&mut self, ast: &FnDef) -> Option<HirStmt> {
+        let name = ast.name()?;
+
+        let params = ast
+            .param_list()
+            .map(|param_list| {
+                param_list
+                    .params()
+                    .filter_map(|param| Some((param.name()?, param)))
+                    .map(|(name, param)| {
+                        let local = self.locals.alloc(Local {
+                            name: name.text().into(),
+                            ty: param.ty().map(|ty| Self::lower_type_ref(&ty)),
+                        });
+                        self.source_map.insert_local(local, param.syntax(), &name);
+                        local
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let body = self.lower_expr(ast.body().map(ast::Expr::BlockExpr));
+
+        let idx = self.fns.alloc(HirFn {
+            name: name.text().into(),
+            params,
+            ret_ty: ast.ret_ty().map(|ty| Self::lower_type_ref(&ty)),
+            body,
+        });
+        self.source_map.insert_fn(idx, ast.syntax(), &name);
+        Some(HirStmt::FnDef(idx))
+    }
+
+    pub fn lower_type_ref(ast: &ast::TypeRef) -> TypeRef {
+        if ast.is_fn() {
+            let params = ast
+                .params()
+                .map(|param| Self::lower_type_ref(&param))
+                .collect();
+            let ret = ast
+                .ret()
+                .map_or(TypeRef::Error, |ret| Self::lower_type_ref(&ret));
+            return TypeRef::Fn {
+                params,
+                ret: Box::new(ret),
+            };
+        }
+        if ast.is_unit() {
+            return TypeRef::Unit;
+        }
+
+        match ast.name().as_ref().map(SyntaxToken::text) {
+            Some("Int") => TypeRef::Int,
+            Some("Float") => TypeRef::Float,
+            Some("Bool") => TypeRef::Bool,
+            Some("String") => TypeRef::String,
+            _ => TypeRef::Error,
+        }
+    }
+
+    /// Lowers `ast` into the expression arena, returning the index of the new expression.
+    ///
+    /// Parentheses are transparent: lowering a `ParenExpr` yields its inner expression.
+    pub fn lower_expr(&mut self, ast: Option<ast::Expr>) -> ExprIdx {
+        let Some(ast) = ast else {
+            return self.exprs.alloc(HirExpr::Missing);
+        };
+
+        let syntax = ast.syntax().clone();
+
+        let expr = match ast {
+            ast::Expr::AssignExpr(assign_expr) => self.lower_assign(&assign_expr),
+            ast::Expr::BinaryExpr(binary_expr) => self.lower_binary(&binary_expr),
+            ast::Expr::BlockExpr(block_expr) => self.lower_block(&block_expr),
+            ast::Expr::CallExpr(call_expr) => self.lower_call(&call_expr),
+            ast::Expr::IfExpr(if_expr) => self.lower_if(&if_expr),
+            ast::Expr::WhileExpr(while_expr) => self.lower_while(&while_expr),
+            ast::Expr::LoopExpr(loop_expr) => self.lower_loop(&loop_expr),
+            ast::Expr::BreakExpr(break_expr) => HirExpr::Break {
+                value: break_expr.value().map(|value| self.lower_expr(Some(value))),
             },
-        )
+            ast::Expr::ContinueExpr(_) => HirExpr::Continue,
+            ast::Expr::Literal(literal) => Self::lower_literal(&literal),
+            ast::Expr::ParenExpr(paren_expr) => {
+                let idx = self.lower_expr(paren_expr.expr());
+                self.source_map.alias_expr(&syntax, idx);
+                return idx;
+            }
+            ast::Expr::UnaryExpr(unary_expr) => self.lower_unary(&unary_expr),
+            ast::Expr::VariableRef(variable_ref) => Self::lower_var_ref(&variable_ref),
+        };
+
+        let idx = self.exprs.alloc(expr);
+        self.source_map.insert_expr(idx, &syntax);
+        idx
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the parser produced an `AssignExpr` without an operator token.
+    pub fn lower_assign(&mut self, ast: &AssignExpr) -> HirExpr {
+        let op = match ast.op().unwrap().kind() {
+            SyntaxKind::Equals => None,
+            SyntaxKind::PlusEq => Some(BinaryOp::Add),
+            SyntaxKind::MinusEq => Some(BinaryOp::Sub),
+            SyntaxKind::StarEq => Some(BinaryOp::Mul),
+            SyntaxKind::SlashEq => Some(BinaryOp::Div),
+            _ => unreachable!(),
+        };
+
+        let place = self.lower_expr(ast.place());
+        let value = self.lower_expr(ast.value());
+
+        HirExpr::Assign { place, op, value }
     }
 
-    pub fn lower_binary(&mut self, ast: BinaryExpr) -> HirExpr {
+    /// # Panics
+    ///
+    /// Panics if the parser produced an `InfixExpr` without an operator token.
+    pub fn lower_binary(&mut self, ast: &BinaryExpr) -> HirExpr {
         let op = match ast.op().unwrap().kind() {
             SyntaxKind::Plus => BinaryOp::Add,
             SyntaxKind::Minus => BinaryOp::Sub,
             SyntaxKind::Star => BinaryOp::Mul,
             SyntaxKind::Slash => BinaryOp::Div,
+            SyntaxKind::EqEq => BinaryOp::Eq,
+            SyntaxKind::BangEq => BinaryOp::Ne,
+            SyntaxKind::Less => BinaryOp::Lt,
+            SyntaxKind::LessEq => BinaryOp::Le,
+            SyntaxKind::Greater => BinaryOp::Gt,
+            SyntaxKind::GreaterEq => BinaryOp::Ge,
+            SyntaxKind::AmpAmp => BinaryOp::And,
+            SyntaxKind::PipePipe => BinaryOp::Or,
             _ => unreachable!(),
         };
 
         let lhs = self.lower_expr(ast.lhs());
-
         let rhs = self.lower_expr(ast.rhs());
-        HirExpr::Binary {
-            op,
-            lhs: self.exprs.alloc(lhs),
-            rhs: self.exprs.alloc(rhs),
+
+        HirExpr::Binary { op, lhs, rhs }
+    }
+
+    pub fn lower_block(&mut self, ast: &BlockExpr) -> HirExpr {
+        let stmts = ast
+            .stmts()
+            .filter_map(|stmt| self.lower_stmt(stmt))
+            .collect();
+
+        let tail = ast.tail_expr().map(|tail| self.lower_expr(Some(tail)));
+
+        HirExpr::Block { stmts, tail }
+    }
+
+    pub fn lower_call(&mut self, ast: &CallExpr) -> HirExpr {
+        let callee = self.lower_expr(ast.callee());
+
+        let args = ast
+            .arg_list()
+            .into_iter()
+            .flat_map(|arg_list| arg_list.args())
+            .map(|arg| self.lower_expr(Some(arg)))
+            .collect();
+
+        HirExpr::Call { callee, args }
+    }
+
+    /// Lowers literals that fail validation to [`HirExpr::Missing`].
+    pub fn lower_literal(ast: &ast::Literal) -> HirExpr {
+        let literal = match ast.kind() {
+            Some(LiteralKind::Number) => ast
+                .parse()
+                .and_then(|n| i64::try_from(n).ok())
+                .map(Literal::Int),
+            Some(LiteralKind::Float) => ast.float().map(Literal::Float),
+            Some(LiteralKind::Bool) => ast.bool().map(Literal::Bool),
+            Some(LiteralKind::String) => ast.string().map(|s| Literal::String(s.into())),
+            None => None,
+        };
+
+        literal.map_or(HirExpr::Missing, HirExpr::Literal)
+    }
+
+    pub fn lower_if(&mut self, ast: &IfExpr) -> HirExpr {
+        let condition = self.lower_expr(ast.condition());
+        let then_branch = self.lower_expr(ast.then_branch().map(ast::Expr::BlockExpr));
+        let else_branch = ast
+            .else_branch()
+            .map(|else_branch| self.lower_expr(Some(else_branch)));
+
+        HirExpr::If {
+            condition,
+            then_branch,
+            else_branch,
         }
     }
 
-    pub fn lower_unary(&mut self, ast: UnaryExpr) -> HirExpr {
+    pub fn lower_while(&mut self, ast: &WhileExpr) -> HirExpr {
+        let condition = self.lower_expr(ast.condition());
+        let body = self.lower_expr(ast.body().map(ast::Expr::BlockExpr));
+
+        HirExpr::While { condition, body }
+    }
+
+    pub fn lower_loop(&mut self, ast: &LoopExpr) -> HirExpr {
+        let body = self.lower_expr(ast.body().map(ast::Expr::BlockExpr));
+
+        HirExpr::Loop { body }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if the parser produced a `PrefixExpr` without an operator token.
+    pub fn lower_unary(&mut self, ast: &UnaryExpr) -> HirExpr {
         let op = match ast.op().unwrap().kind() {
             SyntaxKind::Minus => UnaryOp::Neg,
+            SyntaxKind::Bang => UnaryOp::Not,
             _ => unreachable!(),
         };
 
+        // Negated integer literals lower to negative ones, as `-9223372036854775808` could not
+        // be lowered as a negation of a literal too large for an `i64`.
+        if let Some(ast::Expr::Literal(literal)) = ast.expr().filter(|_| op == UnaryOp::Neg) {
+            if let Some(n) = literal.parse().and_then(|n| 0_i64.checked_sub_unsigned(n)) {
+                return HirExpr::Literal(Literal::Int(n));
+            }
+        }
+
         let expr = self.lower_expr(ast.expr());
 
-        HirExpr::Unary {
-            op,
-            expr: self.exprs.alloc(expr),
-        }
+        HirExpr::Unary { op, expr }
     }
 
-    fn lower_var_ref(ast: ast::VariableRef) -> HirExpr {
+    fn lower_var_ref(ast: &ast::VariableRef) -> HirExpr {
         HirExpr::VariableRef {
             var: ast.name().unwrap().text().into(),
         }
@@ -83,20 +282,33 @@ impl Database {
 mod tests {
     use super::*;
     use crate::parser;
+    use rowan::TextRange;
 
     fn parse(input: &str) -> ast::Root {
         ast::Root::cast(parser::parse(input).syntax()).unwrap()
     }
 
-    fn check_stmt(input: &str, expected_hir: HirStmt) {
+    /// Compares everything but `source_map`, which would make expected values unwieldy.
+    fn assert_database_eq(actual: &Database, expected: &Database) {
+        assert_eq!(actual.exprs, expected.exprs);
+        assert_eq!(actual.fns, expected.fns);
+        assert_eq!(actual.locals, expected.locals);
+    }
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn check_stmt(input: &str, expected_hir: HirStmt, expected_database: Database) {
         let root = parse(input);
         let ast = root.stmts().next().unwrap();
-        let hir = Database::default().lower_stmt(ast).unwrap();
+        let mut database = Database::default();
+        let hir = database.lower_stmt(ast).unwrap();
 
         assert_eq!(hir, expected_hir);
+        assert_database_eq(&database, &expected_database);
     }
 
-    fn check_expr(input: &str, expected_hir: HirExpr, expected_database: Database) {
+    /// `expected_database` holds every expression allocated before `expected_hir`, which is
+    /// expected to be the last one.
+    fn check_expr(input: &str, expected_hir: HirExpr, mut expected_database: Database) {
         let root = parse(input);
         let first_stmt = root.stmts().next().unwrap();
 
@@ -106,34 +318,56 @@ mod tests {
 
         let mut database = Database::default();
 
-        let hir = database.lower_expr(Some(ast));
+        let idx = database.lower_expr(Some(ast));
 
-        assert_eq!(hir, expected_hir);
-        assert_eq!(database, expected_database);
+        assert_eq!(database.exprs[idx], expected_hir);
+
+        expected_database.exprs.alloc(expected_hir);
+        assert_database_eq(&database, &expected_database);
     }
 
     #[test]
     fn lower_variable_def() {
+        let mut exprs = Arena::new();
+        let value = exprs.alloc(HirExpr::VariableRef { var: "bar".into() });
+        let mut locals = Arena::new();
+        let local = locals.alloc(Local {
+            name: "foo".into(),
+            ty: None,
+        });
+
         check_stmt(
             "let foo = bar",
-            HirStmt::VariableDef {
-                name: "foo".into(),
-                value: HirExpr::VariableRef { var: "bar".into() },
+            HirStmt::VariableDef { local, value },
+            Database {
+                exprs,
+                locals,
+                ..Database::default()
             },
         );
     }
 
     #[test]
     fn lower_expr_stmt() {
-        check_stmt("123", HirStmt::Expr(HirExpr::Literal { n: Some(123) }));
+        let mut exprs = Arena::new();
+        let expr = exprs.alloc(HirExpr::Literal(Literal::Int(123)));
+
+        check_stmt(
+            "123",
+            HirStmt::Expr(expr),
+            Database {
+                exprs,
+                ..Database::default()
+            },
+        );
     }
 
     #[test]
     fn lower_binary_expr() {
         let mut exprs = Arena::new();
 
-        let lhs = exprs.alloc(HirExpr::Literal { n: Some(1) });
-        let rhs = exprs.alloc(HirExpr::Literal { n: Some(2) });
+        let lhs = exprs.alloc(HirExpr::Literal(Literal::Int(1)));
+        let rhs = exprs.alloc(HirExpr::Literal(Literal::Int(2)));
 
         check_expr(
             "1+2",
@@ -142,14 +376,17 @@ mod tests {
                 lhs,
                 rhs,
             },
-            Database { exprs },
+            Database {
+                exprs,
+                ..Database::default()
+            },
         );
     }
     #[test]
     fn lower_literal() {
         check_expr(
             "999",
-            HirExpr::Literal { n: Some(999) },
+            HirExpr::Literal(Literal::Int(999)),
             Database::default(),
         );
     }
@@ -166,15 +403,65 @@ mod tests {
     #[test]
     fn lower_unary_expr() {
         let mut exprs = Arena::new();
-        let ten = exprs.alloc(HirExpr::Literal { n: Some(10) });
+        let ten = exprs.alloc(HirExpr::Literal(Literal::Int(10)));
 
         check_expr(
-            "-10",
+            "-(10)",
             HirExpr::Unary {
                 op: UnaryOp::Neg,
                 expr: ten,
             },
-            Database { exprs },
+            Database {
+                exprs,
+                ..Database::default()
+            },
+        );
+    }
+
+    #[test]
+    fn lower_negative_literals() {
+        check_expr(
+            "-10",
+            HirExpr::Literal(Literal::Int(-10)),
+            Database::default(),
+        );
+        check_expr(
+            "-9223372036854775808",
+            HirExpr::Literal(Literal::Int(i64::MIN)),
+            Database::default(),
+        );
+    }
+
+    #[test]
+    fn lower_bool_literal() {
+        check_expr(
+            "true",
+            HirExpr::Literal(Literal::Bool(true)),
+            Database::default(),
+        );
+    }
+
+    #[test]
+    fn lower_comparison_and_not() {
+        let mut exprs = Arena::new();
+        let lhs = exprs.alloc(HirExpr::Literal(Literal::Int(1)));
+        let rhs = exprs.alloc(HirExpr::Literal(Literal::Int(2)));
+        let comparison = exprs.alloc(HirExpr::Binary {
+            op: BinaryOp::Le,
+            lhs,
+            rhs,
+        });
+
+        check_expr(
+            "!(1 <= 2)",
+            HirExpr::Unary {
+                op: UnaryOp::Not,
+                expr: comparison,
+            },
+            Database {
+                exprs,
+                ..Database::default()
+            },
         );
     }
 
@@ -187,6 +474,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn lower_annotated_variable_def() {
+        let mut exprs = Arena::new();
+        let value = exprs.alloc(HirExpr::Literal(Literal::Int(3)));
+        let mut locals = Arena::new();
+        let local = locals.alloc(Local {
+            name: "x".into(),
+            ty: Some(TypeRef::Int),
+        });
+
+        check_stmt(
+            "let x: Int = 3",
+            HirStmt::VariableDef { local, value },
+            Database {
+                exprs,
+                locals,
+                ..Database::default()
+            },
+        );
+    }
+
+    #[test]
+    fn lower_type_refs() {
+        let root = parse("fn f(a: fn(Float, ()) -> Bool, b: Strung) -> String { 1 }");
+        let mut database = Database::default();
+        let Some(HirStmt::FnDef(idx)) = database.lower_stmt(root.stmts().next().unwrap()) else {
+            unreachable!()
+        };
+        let function = &database.fns[idx];
+
+        assert_eq!(
+            database.locals[function.params[0]].ty,
+            Some(TypeRef::Fn {
+                params: vec![TypeRef::Float, TypeRef::Unit],
+                ret: Box::new(TypeRef::Bool),
+            })
+        );
+        assert_eq!(database.locals[function.params[1]].ty, Some(TypeRef::Error));
+        assert_eq!(function.ret_ty, Some(TypeRef::String));
+    }
+
     #[test]
     fn lower_variable_def_without_name() {
         let root = parse("let = 10");
@@ -197,11 +525,21 @@ mod tests {
 
     #[test]
     fn lower_variable_def_without_value() {
+        let mut exprs = Arena::new();
+        let value = exprs.alloc(HirExpr::Missing);
+        let mut locals = Arena::new();
+        let local = locals.alloc(Local {
+            name: "a".into(),
+            ty: None,
+        });
+
         check_stmt(
             "let a =",
-            HirStmt::VariableDef {
-                name: "a".into(),
-                value: HirExpr::Missing,
+            HirStmt::VariableDef { local, value },
+            Database {
+                exprs,
+                locals,
+                ..Database::default()
             },
         );
     }
@@ -209,7 +547,7 @@ mod tests {
     #[test]
     fn lower_binary_expr_without_rhs() {
         let mut exprs = Arena::new();
-        let lhs = exprs.alloc(HirExpr::Literal { n: Some(10) });
+        let lhs = exprs.alloc(HirExpr::Literal(Literal::Int(10)));
         let rhs = exprs.alloc(HirExpr::Missing);
         check_expr(
             "10 -",
@@ -218,7 +556,10 @@ mod tests {
                 lhs,
                 rhs,
             },
-            Database { exprs },
+            Database {
+                exprs,
+                ..Database::default()
+            },
         );
     }
 
@@ -233,7 +574,326 @@ mod tests {
                 op: UnaryOp::Neg,
                 expr,
             },
-            Database { exprs },
+            Database {
+                exprs,
+                ..Database::default()
+            },
         );
     }
+
+    #[test]
+    fn lower_fn_def() {
+        let mut exprs = Arena::new();
+        let lhs = exprs.alloc(HirExpr::VariableRef { var: "a".into() });
+        let rhs = exprs.alloc(HirExpr::VariableRef { var: "b".into() });
+        let tail = exprs.alloc(HirExpr::Binary {
+            op: BinaryOp::Add,
+            lhs,
+            rhs,
+        });
+        let body = exprs.alloc(HirExpr::Block {
+            stmts: Vec::new(),
+            tail: Some(tail),
+        });
+
+        let mut locals = Arena::new();
+        let a = locals.alloc(Local {
+            name: "a".into(),
+            ty: None,
+        });
+        let b = locals.alloc(Local {
+            name: "b".into(),
+            ty: None,
+        });
+
+        let mut fns = Arena::new();
+        let idx = fns.alloc(HirFn {
+            name: "add".into(),
+            params: vec![a, b],
+            ret_ty: None,
+            body,
+        });
+
+        check_stmt(
+            "fn add(a, b) { a + b }",
+            HirStmt::FnDef(idx),
+            Database {
+                exprs,
+                fns,
+                locals,
+                ..Database::default()
+            },
+        );
+    }
+
+    #[test]
+    fn lower_fn_def_without_name() {
+        let root = parse("fn (a) { a }");
+        let ast = root.stmts().next().unwrap();
+
+        assert!(Database::default().lower_stmt(ast).is_none());
+    }
+
+    #[test]
+    fn lower_fn_def_without_body() {
+        let root = parse("fn f(a)");
+        let ast = root.stmts().next().unwrap();
+        let mut database = Database::default();
+
+        let HirStmt::FnDef(idx) = database.lower_stmt(ast).unwrap() else {
+            unreachable!()
+        };
+
+        let body = database.fns[idx].body;
+        assert_eq!(database.exprs[body], HirExpr::Missing);
+    }
+
+    #[test]
+    fn lower_call() {
+        let mut exprs = Arena::new();
+        let callee = exprs.alloc(HirExpr::VariableRef { var: "f".into() });
+        let one = exprs.alloc(HirExpr::Literal(Literal::Int(1)));
+        let two = exprs.alloc(HirExpr::Literal(Literal::Int(2)));
+
+        check_expr(
+            "f(1, 2)",
+            HirExpr::Call {
+                callee,
+                args: vec![one, two],
+            },
+            Database {
+                exprs,
+                ..Database::default()
+            },
+        );
+    }
+
+    #[test]
+    fn lower_block() {
+        let mut exprs = Arena::new();
+        let value = exprs.alloc(HirExpr::Literal(Literal::Int(1)));
+        let tail = exprs.alloc(HirExpr::VariableRef { var: "a".into() });
+        let mut locals = Arena::new();
+        let local = locals.alloc(Local {
+            name: "a".into(),
+            ty: None,
+        });
+
+        check_expr(
+            "{ let a = 1 a }",
+            HirExpr::Block {
+                stmts: vec![HirStmt::VariableDef { local, value }],
+                tail: Some(tail),
+            },
+            Database {
+                exprs,
+                locals,
+                ..Database::default()
+            },
+        );
+    }
+
+    #[test]
+    fn lower_block_without_tail() {
+        let mut exprs = Arena::new();
+        let value = exprs.alloc(HirExpr::Literal(Literal::Int(1)));
+        let mut locals = Arena::new();
+        let local = locals.alloc(Local {
+            name: "a".into(),
+            ty: None,
+        });
+
+        check_expr(
+            "{ let a = 1 }",
+            HirExpr::Block {
+                stmts: vec![HirStmt::VariableDef { local, value }],
+                tail: None,
+            },
+            Database {
+                exprs,
+                locals,
+                ..Database::default()
+            },
+        );
+    }
+
+    #[test]
+    fn lower_if_else() {
+        let mut exprs = Arena::new();
+        let condition = exprs.alloc(HirExpr::Literal(Literal::Bool(true)));
+        let one = exprs.alloc(HirExpr::Literal(Literal::Int(1)));
+        let then_branch = exprs.alloc(HirExpr::Block {
+            stmts: Vec::new(),
+            tail: Some(one),
+        });
+        let two = exprs.alloc(HirExpr::Literal(Literal::Int(2)));
+        let else_branch = exprs.alloc(HirExpr::Block {
+            stmts: Vec::new(),
+            tail: Some(two),
+        });
+
+        check_expr(
+            "if true { 1 } else { 2 }",
+            HirExpr::If {
+                condition,
+                then_branch,
+                else_branch: Some(else_branch),
+            },
+            Database {
+                exprs,
+                ..Database::default()
+            },
+        );
+    }
+
+    #[test]
+    fn lower_if_without_condition_or_else() {
+        let mut exprs = Arena::new();
+        let condition = exprs.alloc(HirExpr::Missing);
+        let then_branch = exprs.alloc(HirExpr::Block {
+            stmts: Vec::new(),
+            tail: None,
+        });
+
+        check_expr(
+            "if {}",
+            HirExpr::If {
+                condition,
+                then_branch,
+                else_branch: None,
+            },
+            Database {
+                exprs,
+                ..Database::default()
+            },
+        );
+    }
+
+    #[test]
+    fn lower_while() {
+        let mut exprs = Arena::new();
+        let condition = exprs.alloc(HirExpr::Literal(Literal::Bool(true)));
+        let continue_expr = exprs.alloc(HirExpr::Continue);
+        let body = exprs.alloc(HirExpr::Block {
+            stmts: Vec::new(),
+            tail: Some(continue_expr),
+        });
+
+        check_expr(
+            "while true { continue }",
+            HirExpr::While { condition, body },
+            Database {
+                exprs,
+                ..Database::default()
+            },
+        );
+    }
+
+    #[test]
+    fn lower_loop_with_break() {
+        let mut exprs = Arena::new();
+        let empty_break = exprs.alloc(HirExpr::Break { value: None });
+        let value = exprs.alloc(HirExpr::Literal(Literal::Int(1)));
+        let break_with_value = exprs.alloc(HirExpr::Break { value: Some(value) });
+        let body = exprs.alloc(HirExpr::Block {
+            stmts: vec![HirStmt::Expr(empty_break)],
+            tail: Some(break_with_value),
+        });
+
+        check_expr(
+            "loop { break; break 1 }",
+            HirExpr::Loop { body },
+            Database {
+                exprs,
+                ..Database::default()
+            },
+        );
+    }
+
+    #[test]
+    fn lower_compound_assignment() {
+        let mut exprs = Arena::new();
+        let place = exprs.alloc(HirExpr::VariableRef { var: "a".into() });
+        let value = exprs.alloc(HirExpr::Literal(Literal::Int(2)));
+
+        check_expr(
+            "a /= 2",
+            HirExpr::Assign {
+                place,
+                op: Some(BinaryOp::Div),
+                value,
+            },
+            Database {
+                exprs,
+                ..Database::default()
+            },
+        );
+    }
+
+    #[test]
+    fn lower_string_literal() {
+        check_expr(
+            r#""a\tb""#,
+            HirExpr::Literal(Literal::String("a\tb".into())),
+            Database::default(),
+        );
+    }
+
+    #[test]
+    fn lower_number_literals() {
+        check_expr(
+            "0b1_01",
+            HirExpr::Literal(Literal::Int(5)),
+            Database::default(),
+        );
+        check_expr(
+            "2.5e1",
+            HirExpr::Literal(Literal::Float(25.0)),
+            Database::default(),
+        );
+    }
+
+    #[test]
+    fn lower_invalid_literals_to_missing() {
+        check_expr(
+            "99999999999999999999",
+            HirExpr::Missing,
+            Database::default(),
+        );
+        check_expr("0b2", HirExpr::Missing, Database::default());
+        check_expr("1e", HirExpr::Missing, Database::default());
+        check_expr(r#""\u{}""#, HirExpr::Missing, Database::default());
+    }
+
+    #[test]
+    fn lower_records_expr_ranges() {
+        let root = parse("let a = (1 + b)");
+        let ast = root.stmts().next().unwrap();
+        let mut database = Database::default();
+
+        let HirStmt::VariableDef { value, .. } = database.lower_stmt(ast).unwrap() else {
+            unreachable!()
+        };
+        let HirExpr::Binary { lhs, rhs, .. } = database.exprs[value] else {
+            unreachable!()
+        };
+
+        let range = |start: u32, end: u32| Some(TextRange::new(start.into(), end.into()));
+        assert_eq!(database.source_map.expr_range(value), range(9, 14));
+        assert_eq!(database.source_map.expr_range(lhs), range(9, 10));
+        assert_eq!(database.source_map.expr_range(rhs), range(13, 14));
+    }
+
+    #[test]
+    fn missing_exprs_have_no_range() {
+        let root = parse("let a =");
+        let ast = root.stmts().next().unwrap();
+        let mut database = Database::default();
+
+        let HirStmt::VariableDef { value, .. } = database.lower_stmt(ast).unwrap() else {
+            unreachable!()
+        };
+
+        assert_eq!(database.source_map.expr_range(value), None);
+    }
 }
@@ -0,0 +1,226 @@
+//! Readable dump of lowered HIR, for `elysium dump --stage hir`.
+
+use super::{Database, ExprIdx, HirExpr, HirStmt, LocalId, Ty};
+use std::fmt::{self, Write};
+
+impl Database {
+    /// Prints `stmts` as a tree with one expression per line. Locals show with their index, as
+    /// in `x#0`, so that references can be told apart from the ones to other locals of the same
+    /// name, and annotations show as the types they stand for.
+    pub fn debug_tree(&self, stmts: &[HirStmt]) -> String {
+        let mut printer = Printer {
+            db: self,
+            out: String::new(),
+            depth: 0,
+        };
+        for stmt in stmts {
+            printer.stmt(stmt);
+        }
+        printer.out
+    }
+}
+
+struct Printer<'a> {
+    db: &'a Database,
+    out: String,
+    depth: usize,
+}
+
+impl Printer<'_> {
+    fn line(&mut self, text: fmt::Arguments) {
+        let _ = writeln!(self.out, "{:indent$}{text}", "", indent = self.depth * 2);
+    }
+
+    /// Prints `exprs` one level further in.
+    fn nested(&mut self, exprs: impl IntoIterator<Item = ExprIdx>) {
+        self.depth += 1;
+        for expr in exprs {
+            self.expr(expr);
+        }
+        self.depth -= 1;
+    }
+
+    fn stmt(&mut self, stmt: &HirStmt) {
+        match stmt {
+            HirStmt::VariableDef { local, value } => {
+                self.line(format_args!("VariableDef {}", self.local(*local)));
+                self.nested([*value]);
+            }
+            HirStmt::FnDef(function) => {
+                let function = &self.db.fns[*function];
+                let params: Vec<_> = function
+                    .params
+                    .iter()
+                    .map(|param| self.local(*param))
+                    .collect();
+                let ret = function
+                    .ret_ty
+                    .as_ref()
+                    .map_or_else(String::new, |ty| format!(" -> {}", Ty::from(ty)));
+                self.line(format_args!(
+                    "FnDef {}({}){ret}",
+                    function.name,
+                    params.join(", ")
+                ));
+                self.nested([function.body]);
+            }
+            HirStmt::Expr(expr) => self.expr(*expr),
+        }
+    }
+
+    fn expr(&mut self, idx: ExprIdx) {
+        match &self.db.exprs[idx] {
+            HirExpr::Assign { place, op, value } => {
+                let op = op.map_or_else(String::new, |op| op.to_string());
+                self.line(format_args!("Assign {op}="));
+                self.nested([*place, *value]);
+            }
+            HirExpr::Binary { op, lhs, rhs } => {
+                self.line(format_args!("Binary {op}"));
+                self.nested([*lhs, *rhs]);
+            }
+            HirExpr::Block { stmts, tail } => {
+                self.line(format_args!("Block"));
+                self.depth += 1;
+                for stmt in stmts {
+                    self.stmt(stmt);
+                }
+                self.depth -= 1;
+                self.nested(*tail);
+            }
+            HirExpr::Call { callee, args } => {
+                self.line(format_args!("Call"));
+                self.nested(std::iter::once(*callee).chain(args.iter().copied()));
+            }
+            HirExpr::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.line(format_args!("If"));
+                self.nested([*condition, *then_branch].into_iter().chain(*else_branch));
+            }
+            HirExpr::While { condition, body } => {
+                self.line(format_args!("While"));
+                self.nested([*condition, *body]);
+            }
+            HirExpr::Loop { body } => {
+                self.line(format_args!("Loop"));
+                self.nested([*body]);
+            }
+            HirExpr::Break { value } => {
+                self.line(format_args!("Break"));
+                self.nested(*value);
+            }
+            HirExpr::Continue => self.line(format_args!("Continue")),
+            HirExpr::Literal(literal) => self.line(format_args!("Literal {literal}")),
+            HirExpr::Unary { op, expr } => {
+                self.line(format_args!("Unary {op}"));
+                self.nested([*expr]);
+            }
+            HirExpr::VariableRef { var } => self.line(format_args!("VariableRef {var}")),
+            HirExpr::LocalRef(local) => {
+                let local = self.local_name(*local);
+                self.line(format_args!("LocalRef {local}"));
+            }
+            HirExpr::FnRef(function) => {
+                let name = &self.db.fns[*function].name;
+                self.line(format_args!("FnRef {name}"));
+            }
+            HirExpr::Missing => self.line(format_args!("Missing")),
+        }
+    }
+
+    fn local_name(&self, local: LocalId) -> String {
+        format!("{}#{}", self.db.locals[local].name, local.into_raw())
+    }
+
+    /// A local with its annotation, if it has one.
+    fn local(&self, local: LocalId) -> String {
+        let name = self.local_name(local);
+        match &self.db.locals[local].ty {
+            Some(ty) => format!("{name}: {}", Ty::from(ty)),
+            None => name,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast, hir, parser::parse};
+    use expect_test::{expect, Expect};
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn check(input: &str, expected: Expect) {
+        let root = ast::Root::cast(parse(input).syntax()).unwrap();
+        let (mut db, stmts) = hir::lower(&root);
+        hir::resolve(&mut db, &stmts);
+        expected.assert_eq(&db.debug_tree(&stmts));
+    }
+
+    #[test]
+    fn debug_definitions() {
+        check(
+            "let a: Int = 1\nfn f(x, y: Bool) -> Int { let a = x; a + 1 }\nf(a, true)",
+            expect![[r"
+                VariableDef a#0: Int
+                  Literal 1
+                FnDef f(x#1, y#2: Bool) -> Int
+                  Block
+                    VariableDef a#3
+                      LocalRef x#1
+                    Binary +
+                      LocalRef a#3
+                      Literal 1
+                Call
+                  FnRef f
+                  LocalRef a#0
+                  Literal true
+            "]],
+        );
+    }
+
+    #[test]
+    fn debug_control_flow() {
+        check(
+            "let i = 0\nwhile i < 3 { i += 1 }\nloop { if !true { break -i } else { continue } }",
+            expect![[r"
+                VariableDef i#0
+                  Literal 0
+                While
+                  Binary <
+                    LocalRef i#0
+                    Literal 3
+                  Block
+                    Assign +=
+                      LocalRef i#0
+                      Literal 1
+                Loop
+                  Block
+                    If
+                      Unary !
+                        Literal true
+                      Block
+                        Break
+                          Unary -
+                            LocalRef i#0
+                      Block
+                        Continue
+            "]],
+        );
+    }
+
+    #[test]
+    fn debug_unresolved_and_missing() {
+        check(
+            "x + (1\nlet b = 99999999999999999999",
+            expect![[r"
+            Binary +
+              VariableRef x
+              Literal 1
+            VariableDef b#0
+              Missing
+        "]],
+        );
+    }
+}
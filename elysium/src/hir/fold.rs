@@ -0,0 +1,458 @@
+//! Constant folding: evaluates operators over literals ahead of time, and removes operations
+//! that leave their operand unchanged.
+//!
+//! Folding reuses the evaluator's arithmetic, so a folded literal is always the value the
+//! operation would have produced. Operations that would fail instead, such as `1 / 0`, are
+//! left for the evaluator to raise and reported as warnings. Overflow only fails with
+//! [`Overflow::Checked`], so it is only reported then; with [`Overflow::Wrapping`] the wrapped
+//! result is folded like any other.
+
+use super::{BinaryOp, Database, ExprIdx, HirExpr, HirStmt, Literal};
+use crate::{
+    eval::{eval_binary, eval_unary, Overflow, RuntimeError, Value},
+    Diagnostic,
+};
+use rowan::TextRange;
+use std::{fmt, mem};
+
+/// Folds the expressions reachable from `stmts` in place, including the bodies of the
+/// functions they define, with arithmetic overflowing as `overflow` says it will when evaluated.
+///
+/// Expects HIR that has passed [type checking](super::infer): identities such as `x + 0` are
+/// only removed because `x` is known to be an integer. Removing `-(-x)` also drops any overflow
+/// that negating `x` would raise.
+pub fn fold(db: &mut Database, stmts: &[HirStmt], overflow: Overflow) -> Vec<FoldWarning> {
+    let mut folder = Folder {
+        db,
+        overflow,
+        warnings: Vec::new(),
+    };
+    folder.fold_stmts(stmts);
+    folder.warnings
+}
+
+struct Folder<'a> {
+    db: &'a mut Database,
+    overflow: Overflow,
+    warnings: Vec<FoldWarning>,
+}
+
+impl Folder<'_> {
+    fn fold_stmts(&mut self, stmts: &[HirStmt]) {
+        for stmt in stmts {
+            match stmt {
+                HirStmt::VariableDef { value, .. } => self.fold_expr(*value),
+                HirStmt::FnDef(function) => self.fold_expr(self.db.fns[*function].body),
+                HirStmt::Expr(expr) => self.fold_expr(*expr),
+            }
+        }
+    }
+
+    fn fold_expr(&mut self, idx: ExprIdx) {
+        self.remove_double_negation(idx);
+        let expr = mem::replace(&mut self.db.exprs[idx], HirExpr::Missing);
+
+        match &expr {
+            HirExpr::Assign { place, value, .. } => {
+                self.fold_expr(*place);
+                self.fold_expr(*value);
+            }
+            HirExpr::Binary { lhs, rhs, .. } => {
+                self.fold_expr(*lhs);
+                self.fold_expr(*rhs);
+            }
+            HirExpr::Block { stmts, tail } => {
+                self.fold_stmts(stmts);
+                if let Some(tail) = tail {
+                    self.fold_expr(*tail);
+                }
+            }
+            HirExpr::Call { callee, args } => {
+                self.fold_expr(*callee);
+                for arg in args {
+                    self.fold_expr(*arg);
+                }
+            }
+            HirExpr::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.fold_expr(*condition);
+                self.fold_expr(*then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.fold_expr(*else_branch);
+                }
+            }
+            HirExpr::While { condition, body } => {
+                self.fold_expr(*condition);
+                self.fold_expr(*body);
+            }
+            HirExpr::Loop { body } => self.fold_expr(*body),
+            HirExpr::Break { value } => {
+                if let Some(value) = value {
+                    self.fold_expr(*value);
+                }
+            }
+            HirExpr::Unary { expr, .. } => self.fold_expr(*expr),
+            HirExpr::Continue
+            | HirExpr::Literal(_)
+            | HirExpr::VariableRef { .. }
+            | HirExpr::LocalRef(_)
+            | HirExpr::FnRef(_)
+            | HirExpr::Missing => {}
+        }
+
+        self.db.exprs[idx] = self.simplify(idx, expr);
+    }
+
+    /// Replaces `-(-x)` or `!!x` at `idx` with `x`. Done before folding `x`, so that no overflow
+    /// is reported for a negation that is no longer evaluated.
+    fn remove_double_negation(&mut self, idx: ExprIdx) {
+        while let HirExpr::Unary { op, expr: inner } = self.db.exprs[idx] {
+            match self.db.exprs[inner] {
+                HirExpr::Unary {
+                    op: inner_op,
+                    expr: operand,
+                } if inner_op == op => self.db.exprs[idx] = self.take(operand),
+                _ => return,
+            }
+        }
+    }
+
+    /// The folded form of `expr`, whose sub-expressions have already been folded.
+    fn simplify(&mut self, idx: ExprIdx, expr: HirExpr) -> HirExpr {
+        let folded = match &expr {
+            HirExpr::Binary { op, lhs, rhs } => match (self.value(*lhs), self.value(*rhs)) {
+                (Some(lhs), Some(rhs)) => self.fold_binary(idx, *op, lhs, rhs),
+                (None, Some(rhs)) if is_right_identity(*op, &rhs) => Some(self.take(*lhs)),
+                (Some(lhs), None) if is_left_identity(*op, &lhs) => Some(self.take(*rhs)),
+                _ => None,
+            },
+            HirExpr::Unary { op, expr: operand } => self
+                .value(*operand)
+                .and_then(|operand| self.fold_result(idx, eval_unary(*op, operand, self.overflow))),
+            _ => None,
+        };
+
+        folded.unwrap_or(expr)
+    }
+
+    fn fold_binary(
+        &mut self,
+        idx: ExprIdx,
+        op: BinaryOp,
+        lhs: Value,
+        rhs: Value,
+    ) -> Option<HirExpr> {
+        let result = match (op, lhs, rhs) {
+            (BinaryOp::And, Value::Bool(lhs), Value::Bool(rhs)) => Ok(Value::Bool(lhs && rhs)),
+            (BinaryOp::Or, Value::Bool(lhs), Value::Bool(rhs)) => Ok(Value::Bool(lhs || rhs)),
+            (BinaryOp::And | BinaryOp::Or, ..) => return None,
+            (op, lhs, rhs) => eval_binary(op, lhs, rhs, self.overflow),
+        };
+        self.fold_result(idx, result)
+    }
+
+    /// The literal holding a successfully computed value. Failures are reported if they are
+    /// arithmetic ones, and left to type checking otherwise.
+    fn fold_result(
+        &mut self,
+        idx: ExprIdx,
+        result: Result<Value, RuntimeError>,
+    ) -> Option<HirExpr> {
+        let kind = match result {
+            Ok(value) => return literal(value).map(HirExpr::Literal),
+            Err(RuntimeError::DivisionByZero) => FoldWarningKind::DivisionByZero,
+            Err(RuntimeError::BinaryOverflow { .. } | RuntimeError::UnaryOverflow { .. }) => {
+                FoldWarningKind::Overflow
+            }
+            Err(_) => return None,
+        };
+
+        self.warnings.push(FoldWarning {
+            kind,
+            range: self.db.source_map.expr_range(idx).unwrap_or_default(),
+        });
+        None
+    }
+
+    fn value(&self, idx: ExprIdx) -> Option<Value> {
+        match &self.db.exprs[idx] {
+            HirExpr::Literal(literal) => Some(Value::from(literal)),
+            _ => None,
+        }
+    }
+
+    /// Moves the expression at `idx` out of the arena, to take the place of one containing it.
+    fn take(&mut self, idx: ExprIdx) -> HirExpr {
+        mem::replace(&mut self.db.exprs[idx], HirExpr::Missing)
+    }
+}
+
+/// Whether `x op value` is always `x`.
+const fn is_right_identity(op: BinaryOp, value: &Value) -> bool {
+    matches!(
+        (op, value),
+        (BinaryOp::Add | BinaryOp::Sub, Value::Int(0))
+            | (BinaryOp::Mul | BinaryOp::Div, Value::Int(1))
+    )
+}
+
+/// Whether `value op x` is always `x`.
+const fn is_left_identity(op: BinaryOp, value: &Value) -> bool {
+    matches!(
+        (op, value),
+        (BinaryOp::Add, Value::Int(0)) | (BinaryOp::Mul, Value::Int(1))
+    )
+}
+
+fn literal(value: Value) -> Option<Literal> {
+    match value {
+        Value::Int(n) => Some(Literal::Int(n)),
+        Value::Float(x) => Some(Literal::Float(x)),
+        Value::Bool(b) => Some(Literal::Bool(b)),
+        Value::String(s) => Some(Literal::String((*s).into())),
+        Value::Fn(_) | Value::Unit => None,
+    }
+}
+
+/// An operation on literals that will fail when evaluated.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FoldWarning {
+    kind: FoldWarningKind,
+    range: TextRange,
+}
+
+impl FoldWarning {
+    pub const fn kind(&self) -> FoldWarningKind {
+        self.kind
+    }
+
+    pub const fn range(&self) -> TextRange {
+        self.range
+    }
+}
+
+impl fmt::Display for FoldWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "warning at {}..{}: {}",
+            u32::from(self.range.start()),
+            u32::from(self.range.end()),
+            self.kind
+        )
+    }
+}
+
+impl From<FoldWarning> for Diagnostic {
+    fn from(warning: FoldWarning) -> Self {
+        Self::warning("W0001", warning.kind.to_string(), warning.range)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldWarningKind {
+    DivisionByZero,
+    Overflow,
+}
+
+impl fmt::Display for FoldWarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DivisionByZero => write!(f, "this will divide by zero when evaluated"),
+            Self::Overflow => write!(f, "this will overflow when evaluated"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast, eval, hir, parser};
+    use expect_test::{expect, Expect};
+    use std::fmt::Write;
+
+    fn lower(input: &str) -> (Database, Vec<HirStmt>) {
+        let root = ast::Root::cast(parser::parse(input).syntax()).unwrap();
+        let (mut db, stmts) = hir::lower(&root);
+        hir::resolve(&mut db, &stmts);
+        (db, stmts)
+    }
+
+    fn check(input: &str, expected: Expect) {
+        check_with(input, Overflow::Checked, expected);
+    }
+
+    /// Prints each top-level expression statement after folding, then the warnings.
+    #[allow(clippy::needless_pass_by_value)]
+    fn check_with(input: &str, overflow: Overflow, expected: Expect) {
+        let (mut db, stmts) = lower(input);
+        let warnings = fold(&mut db, &stmts, overflow);
+
+        let mut actual = String::new();
+        for stmt in &stmts {
+            if let HirStmt::Expr(expr) = stmt {
+                let _ = writeln!(actual, "{:?}", db.exprs[*expr]);
+            }
+        }
+        for warning in warnings {
+            let _ = writeln!(actual, "{warning}");
+        }
+
+        expected.assert_eq(&actual);
+    }
+
+    #[test]
+    fn fold_arithmetic() {
+        check(
+            "1 + 2 * 3; 10 / 3 - 1",
+            expect![[r"
+                Literal(Int(7))
+                Literal(Int(2))
+            "]],
+        );
+    }
+
+    #[test]
+    fn fold_other_literals() {
+        check(
+            r#"1.5 * 2.0; "a" + "b"; 1 < 2 && !false; 1 == 1 || false; 1.0 / 0.0"#,
+            expect![[r#"
+                Literal(Float(3.0))
+                Literal(String("ab"))
+                Literal(Bool(true))
+                Literal(Bool(true))
+                Literal(Float(inf))
+            "#]],
+        );
+    }
+
+    #[test]
+    fn fold_inside_definitions_and_functions() {
+        let (mut db, stmts) = lower("let a = 2 * 3\nfn f() { if 1 < 2 { 4 - 1 } else { 0 } }");
+        fold(&mut db, &stmts, Overflow::Checked);
+
+        let HirStmt::VariableDef { value, .. } = stmts[0] else {
+            panic!("expected a variable definition");
+        };
+        assert_eq!(db.exprs[value], HirExpr::Literal(Literal::Int(6)));
+
+        let HirExpr::Block {
+            tail: Some(tail), ..
+        } = db.exprs[db.fns.iter().next().unwrap().1.body]
+        else {
+            panic!("expected a block");
+        };
+        let HirExpr::If {
+            condition,
+            then_branch,
+            ..
+        } = db.exprs[tail]
+        else {
+            panic!("expected an if");
+        };
+        assert_eq!(db.exprs[condition], HirExpr::Literal(Literal::Bool(true)));
+        let HirExpr::Block {
+            tail: Some(then_tail),
+            ..
+        } = db.exprs[then_branch]
+        else {
+            panic!("expected a block");
+        };
+        assert_eq!(db.exprs[then_tail], HirExpr::Literal(Literal::Int(3)));
+    }
+
+    #[test]
+    fn remove_identities() {
+        check(
+            "let x = 5\nx * 1; 1 * x; x + 0; 0 + x; x - 0; x / 1; (x + 0) * 1; 0 - x",
+            expect![[r"
+                LocalRef(Idx::<Local>(0))
+                LocalRef(Idx::<Local>(0))
+                LocalRef(Idx::<Local>(0))
+                LocalRef(Idx::<Local>(0))
+                LocalRef(Idx::<Local>(0))
+                LocalRef(Idx::<Local>(0))
+                LocalRef(Idx::<Local>(0))
+                Binary { op: Sub, lhs: Idx::<HirExpr>(24), rhs: Idx::<HirExpr>(25) }
+            "]],
+        );
+    }
+
+    #[test]
+    fn remove_double_negation() {
+        check(
+            "let x = 5\nlet b = true\n-(-x); !!b; !(!(!b)); -(-(-9223372036854775808))",
+            expect![[r"
+                LocalRef(Idx::<Local>(0))
+                LocalRef(Idx::<Local>(1))
+                Unary { op: Not, expr: Idx::<HirExpr>(8) }
+                Literal(Int(-9223372036854775808))
+            "]],
+        );
+    }
+
+    #[test]
+    fn report_failing_operations() {
+        check(
+            "1 / 0; 9223372036854775807 + 1; -9223372036854775808 - 1; -(-9223372036854775808); 2 * (1 / 0)",
+            expect![[r"
+                Binary { op: Div, lhs: Idx::<HirExpr>(0), rhs: Idx::<HirExpr>(1) }
+                Binary { op: Add, lhs: Idx::<HirExpr>(3), rhs: Idx::<HirExpr>(4) }
+                Binary { op: Sub, lhs: Idx::<HirExpr>(6), rhs: Idx::<HirExpr>(7) }
+                Unary { op: Neg, expr: Idx::<HirExpr>(9) }
+                Binary { op: Mul, lhs: Idx::<HirExpr>(11), rhs: Idx::<HirExpr>(14) }
+                warning at 0..5: this will divide by zero when evaluated
+                warning at 7..30: this will overflow when evaluated
+                warning at 32..56: this will overflow when evaluated
+                warning at 58..81: this will overflow when evaluated
+                warning at 88..93: this will divide by zero when evaluated
+            "]],
+        );
+    }
+
+    #[test]
+    fn wrapping_overflow_is_folded_without_warning() {
+        check_with(
+            "9223372036854775807 + 1; -(-9223372036854775808); 1 / 0",
+            Overflow::Wrapping,
+            expect![[r"
+                Literal(Int(-9223372036854775808))
+                Literal(Int(-9223372036854775808))
+                Binary { op: Div, lhs: Idx::<HirExpr>(5), rhs: Idx::<HirExpr>(6) }
+                warning at 50..55: this will divide by zero when evaluated
+            "]],
+        );
+    }
+
+    #[test]
+    fn ill_typed_operations_are_left_alone() {
+        check(
+            "1 + true; 1.0 + 1",
+            expect![[r"
+                Binary { op: Add, lhs: Idx::<HirExpr>(0), rhs: Idx::<HirExpr>(1) }
+                Binary { op: Add, lhs: Idx::<HirExpr>(3), rhs: Idx::<HirExpr>(4) }
+            "]],
+        );
+    }
+
+    #[test]
+    fn folding_preserves_results() {
+        for input in [
+            "let x = 3\n(x + 0) * (2 * 3) - 1 * x",
+            "fn f(n) { n * 1 + 0 }\nf(4) + f(2 * 2)",
+            r#""a" + "b" == "ab" && !!(1 < 2)"#,
+            "let i = 0\nwhile i < 2 * 2 { i += 1 + 0 }\ni",
+            "let a = 1\n1 + 2\na / (1 - 1)",
+        ] {
+            let (db, stmts) = lower(input);
+            let expected = eval::eval(&db, &stmts);
+
+            let (mut db, stmts) = lower(input);
+            fold(&mut db, &stmts, Overflow::Checked);
+            assert_eq!(eval::eval(&db, &stmts), expected, "{input}");
+        }
+    }
+}
@@ -1,50 +1,184 @@
 use super::Database;
-use crate::ast;
+use crate::{ast, lexer};
 use la_arena::Idx;
 use smartstring::alias::String;
+use std::fmt;
 
-type ExprIdx = Idx<HirExpr>;
+pub type ExprIdx = Idx<HirExpr>;
+pub type FnIdx = Idx<HirFn>;
+pub type LocalId = Idx<Local>;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum HirStmt {
-    VariableDef { name: String, value: HirExpr },
-    Expr(HirExpr),
+    VariableDef { local: LocalId, value: ExprIdx },
+    FnDef(FnIdx),
+    Expr(ExprIdx),
+}
+
+/// A function definition, stored in [`Database::fns`] so that values can refer to it.
+#[derive(Debug, PartialEq, Eq)]
+pub struct HirFn {
+    pub name: String,
+    pub params: Vec<LocalId>,
+    /// The annotated return type, if any.
+    pub ret_ty: Option<TypeRef>,
+    pub body: ExprIdx,
 }
 
+/// A variable introduced by a `let` or a function parameter.
 #[derive(Debug, PartialEq, Eq)]
+pub struct Local {
+    pub name: String,
+    /// The annotated type, if any.
+    pub ty: Option<TypeRef>,
+}
+
+/// A type annotation. Names that aren't built-in types lower to [`TypeRef::Error`], having
+/// already been reported by validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeRef {
+    Int,
+    Float,
+    Bool,
+    String,
+    Unit,
+    Fn { params: Vec<Self>, ret: Box<Self> },
+    Error,
+}
+
+#[derive(Debug, PartialEq)]
 pub enum HirExpr {
+    /// Stores `value` in `place`, first combining it with the current value through `op` for
+    /// compound assignments. Produces unit.
+    Assign {
+        place: ExprIdx,
+        op: Option<BinaryOp>,
+        value: ExprIdx,
+    },
     Binary {
         op: BinaryOp,
         lhs: ExprIdx,
         rhs: ExprIdx,
     },
-    Literal {
-        n: Option<u64>,
+    /// Evaluates `stmts` in a new scope, then takes the value of `tail` (or unit if absent).
+    Block {
+        stmts: Vec<HirStmt>,
+        tail: Option<ExprIdx>,
+    },
+    Call {
+        callee: ExprIdx,
+        args: Vec<ExprIdx>,
+    },
+    /// Takes `then_branch` if `condition` holds, otherwise `else_branch` (or unit if absent).
+    If {
+        condition: ExprIdx,
+        then_branch: ExprIdx,
+        else_branch: Option<ExprIdx>,
+    },
+    /// Runs `body` for as long as `condition` holds, producing unit.
+    While {
+        condition: ExprIdx,
+        body: ExprIdx,
     },
+    /// Runs `body` until a `break`, producing the value it breaks with.
+    Loop {
+        body: ExprIdx,
+    },
+    /// Leaves the innermost loop, with `value` (or unit if absent) as the loop's result.
+    Break {
+        value: Option<ExprIdx>,
+    },
+    /// Skips to the next iteration of the innermost loop.
+    Continue,
+    Literal(Literal),
     Unary {
         op: UnaryOp,
         expr: ExprIdx,
     },
+    /// A reference to a name that has not (yet) been resolved; see [`resolve`](super::resolve).
     VariableRef {
         var: String,
     },
+    LocalRef(LocalId),
+    FnRef(FnIdx),
     Missing,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+/// The value of a literal that passed validation; others lower to [`HirExpr::Missing`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(n) => write!(f, "{n}"),
+            // Debug formatting always shows a `.` or exponent, so floats never look like ints.
+            Self::Float(x) => write!(f, "{x:?}"),
+            Self::Bool(b) => write!(f, "{b}"),
+            Self::String(s) => lexer::write_escaped(f, s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BinaryOp {
     Add,
     Sub,
     Mul,
     Div,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+impl fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Eq => "==",
+            Self::Ne => "!=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::And => "&&",
+            Self::Or => "||",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum UnaryOp {
     Neg,
+    Not,
+}
+
+impl fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Neg => "-",
+            Self::Not => "!",
+        })
+    }
 }
 
-pub fn lower(ast: ast::Root) -> (Database, Vec<HirStmt>) {
+/// Lowers every statement of `ast` into a fresh [`Database`].
+///
+/// Variable references are left unresolved; run [`resolve`](super::resolve) afterwards.
+pub fn lower(ast: &ast::Root) -> (Database, Vec<HirStmt>) {
     let mut db = Database::default();
     let stmts = ast.stmts().filter_map(|stmt| db.lower_stmt(stmt)).collect();
     (db, stmts)
@@ -0,0 +1,1380 @@
+//! Type inference: assigns a [`Ty`] to every expression, reporting operands and values of the
+//! wrong type before anything is evaluated.
+//!
+//! Inference is Hindley–Milner style. Every expression starts out with a type variable that
+//! unification narrows down, and functions are generalised once their bodies have been checked,
+//! so `fn id(x) { x }` can be called with an `Int` in one place and a `Bool` in another. The
+//! functions defined in a block are checked together, as each may call any other.
+//!
+//! Arithmetic and comparisons work on several types, so the variables of their operands carry a
+//! [`Constraint`] instead of being pinned to one type: `fn add(a, b) { a + b }` accepts two
+//! `Int`s, two `Float`s or two `String`s.
+
+use super::{
+    BinaryOp, Database, ExprIdx, FnIdx, HirExpr, HirStmt, Literal, LocalId, TypeRef, UnaryOp,
+};
+use crate::Diagnostic;
+use la_arena::ArenaMap;
+use rowan::TextRange;
+use std::{fmt, ops::Index};
+
+/// The static type of an expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Ty {
+    Int,
+    Float,
+    Bool,
+    String,
+    Unit,
+    Fn {
+        params: Vec<Self>,
+        ret: Box<Self>,
+    },
+    /// A type not yet (or never) pinned down, such as the parameter of a generic function.
+    Var(TyVar),
+    /// The type of an expression that could not be checked, e.g. an unresolved name. Unifies
+    /// with anything so that one mistake is only reported once.
+    Error,
+}
+
+impl Ty {
+    /// Displays the type with its variables named `'a`, `'b` and so on in order of appearance,
+    /// rather than by the checker's numbering, so `fn(?3) -> ?3` shows as `fn('a) -> 'a`.
+    ///
+    /// Each type is named on its own, so this is for types shown by themselves: two of them
+    /// side by side could give different variables the same name.
+    pub fn display_named(&self) -> impl fmt::Display + '_ {
+        let mut vars = Vec::new();
+        self.vars(&mut vars);
+        NamedVars { ty: self, vars }
+    }
+
+    /// Adds the variables in the type to `vars` in order of appearance, each once.
+    fn vars(&self, vars: &mut Vec<TyVar>) {
+        match self {
+            Self::Fn { params, ret } => {
+                for param in params {
+                    param.vars(vars);
+                }
+                ret.vars(vars);
+            }
+            Self::Var(var) if !vars.contains(var) => vars.push(*var),
+            _ => {}
+        }
+    }
+
+    fn write(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        var: &dyn Fn(&mut fmt::Formatter<'_>, TyVar) -> fmt::Result,
+    ) -> fmt::Result {
+        match self {
+            Self::Int => write!(f, "Int"),
+            Self::Float => write!(f, "Float"),
+            Self::Bool => write!(f, "Bool"),
+            Self::String => write!(f, "String"),
+            Self::Unit => write!(f, "()"),
+            Self::Fn { params, ret } => {
+                write!(f, "fn(")?;
+                for (i, param) in params.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    param.write(f, var)?;
+                }
+                write!(f, ") -> ")?;
+                ret.write(f, var)
+            }
+            Self::Var(v) => var(f, *v),
+            Self::Error => write!(f, "{{unknown}}"),
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, &|f, var| write!(f, "{var}"))
+    }
+}
+
+struct NamedVars<'a> {
+    ty: &'a Ty,
+    vars: Vec<TyVar>,
+}
+
+impl fmt::Display for NamedVars<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.ty.write(f, &|f, var| {
+            let index = self.vars.iter().position(|v| *v == var).unwrap_or_default();
+            match u8::try_from(index) {
+                Ok(letter @ 0..26) => write!(f, "'{}", char::from(b'a' + letter)),
+                _ => write!(f, "'t{index}"),
+            }
+        })
+    }
+}
+
+impl From<&TypeRef> for Ty {
+    fn from(type_ref: &TypeRef) -> Self {
+        match type_ref {
+            TypeRef::Int => Self::Int,
+            TypeRef::Float => Self::Float,
+            TypeRef::Bool => Self::Bool,
+            TypeRef::String => Self::String,
+            TypeRef::Unit => Self::Unit,
+            TypeRef::Fn { params, ret } => Self::Fn {
+                params: params.iter().map(Self::from).collect(),
+                ret: Box::new(Self::from(&**ret)),
+            },
+            TypeRef::Error => Self::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TyVar(u32);
+
+impl fmt::Display for TyVar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "?{}", self.0)
+    }
+}
+
+/// The types an operator accepts, carried by type variables used as its operands.
+///
+/// Ordered from most to least restrictive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Constraint {
+    /// `Int` or `Float`, for arithmetic other than `+` and for ordering.
+    Numeric,
+    /// `Int`, `Float` or `String`, for `+`.
+    Addable,
+}
+
+impl Constraint {
+    fn for_op(op: BinaryOp) -> Self {
+        if op == BinaryOp::Add {
+            Self::Addable
+        } else {
+            Self::Numeric
+        }
+    }
+
+    fn admits(self, ty: &Ty) -> bool {
+        match ty {
+            Ty::Int | Ty::Float | Ty::Error => true,
+            Ty::String => self == Self::Addable,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Numeric => write!(f, "a number"),
+            Self::Addable => write!(f, "a number or string"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum VarState {
+    Unbound(Option<Constraint>),
+    Bound(Ty),
+}
+
+/// The type of a function, generic over `vars`.
+#[derive(Debug, Clone)]
+struct Scheme {
+    vars: Vec<TyVar>,
+    ty: Ty,
+}
+
+/// Why two types could not be unified.
+enum UnifyError {
+    Mismatch,
+    Unsatisfied(Constraint, Ty),
+    Infinite,
+}
+
+/// Infers types against the types of earlier definitions.
+///
+/// Top-level definitions persist between calls to [`TypeChecker::infer`], so statements lowered
+/// from later inputs can use definitions from earlier ones.
+#[derive(Debug, Default, Clone)]
+pub struct TypeChecker {
+    vars: Vec<VarState>,
+    locals: ArenaMap<LocalId, Ty>,
+    fns: ArenaMap<FnIdx, Scheme>,
+    /// Top-level locals, the only ones a function body can see besides its own.
+    globals: Vec<LocalId>,
+    /// Functions whose bodies are being checked, and so are not yet generalised.
+    pending_fns: Vec<FnIdx>,
+    /// The result type of each enclosing loop, innermost last.
+    loops: Vec<Ty>,
+}
+
+impl TypeChecker {
+    /// Infers the type of every expression reachable from `stmts`, declaring top-level
+    /// definitions along the way.
+    ///
+    /// Names are expected to be resolved already; any left unresolved have an unknown type.
+    pub fn infer(&mut self, db: &Database, stmts: &[HirStmt]) -> InferenceResult {
+        let mut result = InferenceResult::default();
+        self.infer_stmts(db, stmts, true, &mut result);
+
+        for ty in result.exprs.values_mut() {
+            *ty = self.resolve(ty);
+        }
+        for ty in result.locals.values_mut() {
+            *ty = self.resolve(ty);
+        }
+        for ty in result.fns.values_mut() {
+            *ty = self.resolve(ty);
+        }
+        for error in &mut result.errors {
+            error.kind = error.kind.resolve(self);
+        }
+
+        result
+    }
+
+    /// Forgets the types of the top-level definitions in `stmts`.
+    pub fn forget(&mut self, stmts: &[HirStmt]) {
+        for stmt in stmts {
+            match stmt {
+                HirStmt::VariableDef { local, .. } => {
+                    self.locals.remove(*local);
+                    self.globals.retain(|global| global != local);
+                }
+                HirStmt::FnDef(function) => {
+                    self.fns.remove(*function);
+                }
+                HirStmt::Expr(_) => {}
+            }
+        }
+    }
+
+    /// The type of a top-level local, if its definition has been checked.
+    pub fn global(&self, local: LocalId) -> Option<Ty> {
+        self.locals.get(local).map(|ty| self.resolve(ty))
+    }
+
+    /// The type of a function, if its definition has been checked.
+    pub fn fn_ty(&self, function: FnIdx) -> Option<Ty> {
+        self.fns
+            .get(function)
+            .map(|scheme| self.resolve(&scheme.ty))
+    }
+
+    fn infer_stmts(
+        &mut self,
+        db: &Database,
+        stmts: &[HirStmt],
+        top_level: bool,
+        result: &mut InferenceResult,
+    ) {
+        let fns: Vec<_> = stmts
+            .iter()
+            .filter_map(|stmt| match stmt {
+                HirStmt::FnDef(function) => Some(*function),
+                _ => None,
+            })
+            .collect();
+        self.infer_fns(db, &fns, result);
+
+        for stmt in stmts {
+            match stmt {
+                HirStmt::VariableDef { local, value } => {
+                    let ty = self.annotated(db.locals[*local].ty.as_ref());
+                    self.expect(db, *value, &ty, result);
+                    if let Some(existing) = self.locals.get(*local).cloned() {
+                        // Used by a function body before its definition was reached.
+                        self.unify_at(db, *value, &existing, &ty, result);
+                    } else {
+                        self.locals.insert(*local, ty.clone());
+                        if top_level {
+                            self.globals.push(*local);
+                        }
+                    }
+                    result.locals.insert(*local, ty);
+                }
+                HirStmt::Expr(expr) => {
+                    self.infer_expr(db, *expr, result);
+                }
+                HirStmt::FnDef(_) => {}
+            }
+        }
+    }
+
+    /// Checks the bodies of functions defined side by side, then generalises them.
+    fn infer_fns(&mut self, db: &Database, fns: &[FnIdx], result: &mut InferenceResult) {
+        for function in fns {
+            let function_data = &db.fns[*function];
+            let params = function_data
+                .params
+                .iter()
+                .map(|param| {
+                    let ty = self.annotated(db.locals[*param].ty.as_ref());
+                    self.locals.insert(*param, ty.clone());
+                    result.locals.insert(*param, ty.clone());
+                    ty
+                })
+                .collect();
+            let ty = Ty::Fn {
+                params,
+                ret: Box::new(self.annotated(function_data.ret_ty.as_ref())),
+            };
+            self.fns.insert(
+                *function,
+                Scheme {
+                    vars: Vec::new(),
+                    ty,
+                },
+            );
+            self.pending_fns.push(*function);
+        }
+
+        for function in fns {
+            let Ty::Fn { ret, .. } = self.fns[*function].ty.clone() else {
+                unreachable!("functions are declared with a function type")
+            };
+            // A `break` or `continue` cannot reach a loop outside of the function.
+            let loops = std::mem::take(&mut self.loops);
+            self.expect(db, db.fns[*function].body, &ret, result);
+            self.loops = loops;
+        }
+
+        self.pending_fns
+            .truncate(self.pending_fns.len() - fns.len());
+        for function in fns {
+            self.generalize(*function);
+            result.fns.insert(*function, self.fns[*function].ty.clone());
+        }
+    }
+
+    /// Quantifies the function's type over the variables that nothing outside it can constrain.
+    fn generalize(&mut self, function: FnIdx) {
+        let ty = self.resolve(&self.fns[function].ty);
+
+        let mut env = Vec::new();
+        for local in &self.globals {
+            if let Some(ty) = self.locals.get(*local) {
+                self.free_vars(ty, &mut env);
+            }
+        }
+        for pending in &self.pending_fns {
+            self.free_vars(&self.fns[*pending].ty, &mut env);
+        }
+
+        let mut vars = Vec::new();
+        self.free_vars(&ty, &mut vars);
+        vars.retain(|var| !env.contains(var));
+
+        self.fns.insert(function, Scheme { vars, ty });
+    }
+
+    fn infer_expr(&mut self, db: &Database, idx: ExprIdx, result: &mut InferenceResult) -> Ty {
+        let ty = match &db.exprs[idx] {
+            HirExpr::Assign { place, op, value } => {
+                if let Some(op) = op {
+                    // Compound operators produce the type of their left-hand side.
+                    self.infer_binary(db, *op, *place, *value, result);
+                } else {
+                    let place = self.infer_expr(db, *place, result);
+                    self.expect(db, *value, &place, result);
+                }
+                Ty::Unit
+            }
+            HirExpr::Binary { op, lhs, rhs } => self.infer_binary(db, *op, *lhs, *rhs, result),
+            HirExpr::Block { stmts, tail } => {
+                self.infer_stmts(db, stmts, false, result);
+                tail.map_or(Ty::Unit, |tail| self.infer_expr(db, tail, result))
+            }
+            HirExpr::Call { callee, args } => self.infer_call(db, idx, *callee, args, result),
+            HirExpr::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.expect(db, *condition, &Ty::Bool, result);
+                if let Some(else_branch) = else_branch {
+                    let ty = self.infer_expr(db, *then_branch, result);
+                    self.expect(db, *else_branch, &ty, result);
+                    ty
+                } else {
+                    self.expect(db, *then_branch, &Ty::Unit, result);
+                    Ty::Unit
+                }
+            }
+            HirExpr::While { condition, body } => {
+                self.expect(db, *condition, &Ty::Bool, result);
+                self.loops.push(Ty::Unit);
+                self.infer_expr(db, *body, result);
+                self.loops.pop();
+                Ty::Unit
+            }
+            HirExpr::Loop { body } => {
+                let ty = self.fresh(None);
+                self.loops.push(ty.clone());
+                self.infer_expr(db, *body, result);
+                self.loops.pop();
+                ty
+            }
+            HirExpr::Break { value } => {
+                let expected = self.loops.last().cloned().unwrap_or(Ty::Error);
+                match value {
+                    Some(value) => {
+                        self.expect(db, *value, &expected, result);
+                    }
+                    None => self.unify_at(db, idx, &expected, &Ty::Unit, result),
+                }
+                // Control never continues past a `break`, so it fits wherever it appears.
+                self.fresh(None)
+            }
+            HirExpr::Continue => self.fresh(None),
+            HirExpr::Literal(literal) => match literal {
+                Literal::Int(_) => Ty::Int,
+                Literal::Float(_) => Ty::Float,
+                Literal::Bool(_) => Ty::Bool,
+                Literal::String(_) => Ty::String,
+            },
+            HirExpr::Unary { op, expr } => match op {
+                UnaryOp::Neg => {
+                    let ty = self.infer_expr(db, *expr, result);
+                    if self.constrain_at(db, *expr, &ty, Constraint::Numeric, result) {
+                        ty
+                    } else {
+                        Ty::Error
+                    }
+                }
+                UnaryOp::Not => {
+                    self.expect(db, *expr, &Ty::Bool, result);
+                    Ty::Bool
+                }
+            },
+            HirExpr::LocalRef(local) => {
+                if let Some(ty) = self.locals.get(*local) {
+                    ty.clone()
+                } else {
+                    // Only a top-level `let` can be used by a function before it is checked.
+                    let ty = self.fresh(None);
+                    self.locals.insert(*local, ty.clone());
+                    self.globals.push(*local);
+                    ty
+                }
+            }
+            HirExpr::FnRef(function) => self
+                .fns
+                .get(*function)
+                .cloned()
+                .map_or(Ty::Error, |scheme| self.instantiate(&scheme)),
+            HirExpr::VariableRef { .. } | HirExpr::Missing => Ty::Error,
+        };
+
+        result.exprs.insert(idx, ty.clone());
+        ty
+    }
+
+    fn infer_binary(
+        &mut self,
+        db: &Database,
+        op: BinaryOp,
+        lhs: ExprIdx,
+        rhs: ExprIdx,
+        result: &mut InferenceResult,
+    ) -> Ty {
+        match op {
+            BinaryOp::And | BinaryOp::Or => {
+                self.expect(db, lhs, &Ty::Bool, result);
+                self.expect(db, rhs, &Ty::Bool, result);
+                Ty::Bool
+            }
+            BinaryOp::Eq | BinaryOp::Ne => {
+                // Values of any type can be compared, but only with another of the same type.
+                let ty = self.infer_expr(db, lhs, result);
+                self.expect(db, rhs, &ty, result);
+                Ty::Bool
+            }
+            BinaryOp::Add
+            | BinaryOp::Sub
+            | BinaryOp::Mul
+            | BinaryOp::Div
+            | BinaryOp::Lt
+            | BinaryOp::Le
+            | BinaryOp::Gt
+            | BinaryOp::Ge => {
+                let ty = self.infer_expr(db, lhs, result);
+                let ty = if self.constrain_at(db, lhs, &ty, Constraint::for_op(op), result) {
+                    self.expect(db, rhs, &ty, result);
+                    ty
+                } else {
+                    self.infer_expr(db, rhs, result);
+                    Ty::Error
+                };
+
+                if matches!(
+                    op,
+                    BinaryOp::Lt | BinaryOp::Le | BinaryOp::Gt | BinaryOp::Ge
+                ) {
+                    Ty::Bool
+                } else {
+                    ty
+                }
+            }
+        }
+    }
+
+    fn infer_call(
+        &mut self,
+        db: &Database,
+        idx: ExprIdx,
+        callee: ExprIdx,
+        args: &[ExprIdx],
+        result: &mut InferenceResult,
+    ) -> Ty {
+        let callee_ty = self.infer_expr(db, callee, result);
+
+        match self.shallow(&callee_ty) {
+            Ty::Fn { params, ret } if params.len() == args.len() => {
+                for (arg, param) in args.iter().zip(&params) {
+                    self.expect(db, *arg, param, result);
+                }
+                *ret
+            }
+            Ty::Fn { params, ret } => {
+                for arg in args {
+                    self.infer_expr(db, *arg, result);
+                }
+                result.error(
+                    db,
+                    idx,
+                    TypeErrorKind::ArityMismatch {
+                        expected: params.len(),
+                        found: args.len(),
+                    },
+                );
+                *ret
+            }
+            Ty::Var(_) => {
+                let params = args
+                    .iter()
+                    .map(|arg| self.infer_expr(db, *arg, result))
+                    .collect();
+                let ret = self.fresh(None);
+                let ty = Ty::Fn {
+                    params,
+                    ret: Box::new(ret.clone()),
+                };
+                self.unify_at(db, callee, &ty, &callee_ty, result);
+                ret
+            }
+            Ty::Error => {
+                for arg in args {
+                    self.infer_expr(db, *arg, result);
+                }
+                Ty::Error
+            }
+            ty => {
+                for arg in args {
+                    self.infer_expr(db, *arg, result);
+                }
+                result.error(db, callee, TypeErrorKind::NotCallable { ty });
+                Ty::Error
+            }
+        }
+    }
+
+    /// Infers the type of `idx`, reporting an error if it isn't `expected`.
+    fn expect(
+        &mut self,
+        db: &Database,
+        idx: ExprIdx,
+        expected: &Ty,
+        result: &mut InferenceResult,
+    ) -> Ty {
+        let ty = self.infer_expr(db, idx, result);
+        self.unify_at(db, idx, expected, &ty, result);
+        ty
+    }
+
+    /// Unifies the type `found` for `idx` with `expected`, reporting an error at `idx` if they
+    /// differ.
+    fn unify_at(
+        &mut self,
+        db: &Database,
+        idx: ExprIdx,
+        expected: &Ty,
+        found: &Ty,
+        result: &mut InferenceResult,
+    ) {
+        let kind = match self.unify(expected, found) {
+            Ok(()) => return,
+            Err(UnifyError::Mismatch) => TypeErrorKind::Mismatch {
+                expected: expected.clone(),
+                found: found.clone(),
+            },
+            Err(UnifyError::Unsatisfied(constraint, ty)) => {
+                TypeErrorKind::Unsatisfied { constraint, ty }
+            }
+            Err(UnifyError::Infinite) => TypeErrorKind::InfiniteType,
+        };
+        result.error(db, idx, kind);
+    }
+
+    /// Restricts `ty`, the type of `idx`, to those admitted by `constraint`, reporting an error
+    /// at `idx` if it isn't. Returns whether it is.
+    fn constrain_at(
+        &mut self,
+        db: &Database,
+        idx: ExprIdx,
+        ty: &Ty,
+        constraint: Constraint,
+        result: &mut InferenceResult,
+    ) -> bool {
+        match self.constrain(ty, constraint) {
+            Ok(()) => true,
+            Err(ty) => {
+                result.error(db, idx, TypeErrorKind::Unsatisfied { constraint, ty });
+                false
+            }
+        }
+    }
+
+    fn constrain(&mut self, ty: &Ty, constraint: Constraint) -> Result<(), Ty> {
+        match self.shallow(ty) {
+            Ty::Var(var) => {
+                let VarState::Unbound(existing) = self.vars[var.0 as usize] else {
+                    unreachable!("shallow resolution stops at unbound variables")
+                };
+                let constraint = existing.map_or(constraint, |existing| existing.min(constraint));
+                self.vars[var.0 as usize] = VarState::Unbound(Some(constraint));
+                Ok(())
+            }
+            ty if constraint.admits(&ty) => Ok(()),
+            ty => Err(ty),
+        }
+    }
+
+    fn unify(&mut self, a: &Ty, b: &Ty) -> Result<(), UnifyError> {
+        match (self.shallow(a), self.shallow(b)) {
+            (Ty::Error, _) | (_, Ty::Error) => Ok(()),
+            (Ty::Var(a), Ty::Var(b)) if a == b => Ok(()),
+            (Ty::Var(var), ty) | (ty, Ty::Var(var)) => self.bind(var, ty),
+            (
+                Ty::Fn {
+                    params: a_params,
+                    ret: a_ret,
+                },
+                Ty::Fn {
+                    params: b_params,
+                    ret: b_ret,
+                },
+            ) if a_params.len() == b_params.len() => {
+                for (a, b) in a_params.iter().zip(&b_params) {
+                    self.unify(a, b)?;
+                }
+                self.unify(&a_ret, &b_ret)
+            }
+            (a, b) if a == b => Ok(()),
+            _ => Err(UnifyError::Mismatch),
+        }
+    }
+
+    fn bind(&mut self, var: TyVar, ty: Ty) -> Result<(), UnifyError> {
+        let VarState::Unbound(constraint) = self.vars[var.0 as usize] else {
+            unreachable!("shallow resolution stops at unbound variables")
+        };
+
+        if let Some(constraint) = constraint {
+            self.constrain(&ty, constraint)
+                .map_err(|ty| UnifyError::Unsatisfied(constraint, ty))?;
+        }
+
+        let mut vars = Vec::new();
+        self.free_vars(&ty, &mut vars);
+        if vars.contains(&var) {
+            return Err(UnifyError::Infinite);
+        }
+
+        self.vars[var.0 as usize] = VarState::Bound(ty);
+        Ok(())
+    }
+
+    fn instantiate(&mut self, scheme: &Scheme) -> Ty {
+        let substitution: Vec<_> = scheme
+            .vars
+            .iter()
+            .map(|var| {
+                let VarState::Unbound(constraint) = self.vars[var.0 as usize] else {
+                    unreachable!("generalised variables are never bound")
+                };
+                (*var, self.fresh(constraint))
+            })
+            .collect();
+
+        substitute(&scheme.ty, &substitution)
+    }
+
+    /// The annotated type, or a variable to infer it if there is no annotation.
+    fn annotated(&mut self, annotation: Option<&TypeRef>) -> Ty {
+        annotation.map_or_else(|| self.fresh(None), Ty::from)
+    }
+
+    fn fresh(&mut self, constraint: Option<Constraint>) -> Ty {
+        let var = TyVar(u32::try_from(self.vars.len()).expect("too many type variables"));
+        self.vars.push(VarState::Unbound(constraint));
+        Ty::Var(var)
+    }
+
+    /// Follows bound variables until reaching a type that isn't one.
+    fn shallow(&self, ty: &Ty) -> Ty {
+        let mut ty = ty;
+        while let Ty::Var(var) = ty {
+            match &self.vars[var.0 as usize] {
+                VarState::Bound(bound) => ty = bound,
+                VarState::Unbound(_) => break,
+            }
+        }
+        ty.clone()
+    }
+
+    /// Replaces every bound variable in `ty`, however deeply nested, with its type.
+    fn resolve(&self, ty: &Ty) -> Ty {
+        match self.shallow(ty) {
+            Ty::Fn { params, ret } => Ty::Fn {
+                params: params.iter().map(|param| self.resolve(param)).collect(),
+                ret: Box::new(self.resolve(&ret)),
+            },
+            ty => ty,
+        }
+    }
+
+    /// Adds the unbound variables in `ty` to `vars`, in order of appearance.
+    fn free_vars(&self, ty: &Ty, vars: &mut Vec<TyVar>) {
+        match self.shallow(ty) {
+            Ty::Fn { params, ret } => {
+                for param in &params {
+                    self.free_vars(param, vars);
+                }
+                self.free_vars(&ret, vars);
+            }
+            Ty::Var(var) if !vars.contains(&var) => vars.push(var),
+            _ => {}
+        }
+    }
+}
+
+fn substitute(ty: &Ty, substitution: &[(TyVar, Ty)]) -> Ty {
+    match ty {
+        Ty::Fn { params, ret } => Ty::Fn {
+            params: params
+                .iter()
+                .map(|param| substitute(param, substitution))
+                .collect(),
+            ret: Box::new(substitute(ret, substitution)),
+        },
+        Ty::Var(var) => substitution
+            .iter()
+            .find(|(from, _)| from == var)
+            .map_or_else(|| ty.clone(), |(_, to)| to.clone()),
+        ty => ty.clone(),
+    }
+}
+
+/// Infers types in `stmts` with a fresh [`TypeChecker`]; see [`TypeChecker::infer`].
+pub fn infer(db: &Database, stmts: &[HirStmt]) -> InferenceResult {
+    TypeChecker::default().infer(db, stmts)
+}
+
+/// The types found by one call to [`TypeChecker::infer`], with variables resolved as far as
+/// they could be.
+#[derive(Debug, Default)]
+pub struct InferenceResult {
+    exprs: ArenaMap<ExprIdx, Ty>,
+    locals: ArenaMap<LocalId, Ty>,
+    fns: ArenaMap<FnIdx, Ty>,
+    errors: Vec<TypeError>,
+}
+
+impl InferenceResult {
+    pub fn expr_ty(&self, idx: ExprIdx) -> Option<&Ty> {
+        self.exprs.get(idx)
+    }
+
+    pub fn local_ty(&self, local: LocalId) -> Option<&Ty> {
+        self.locals.get(local)
+    }
+
+    pub fn fn_ty(&self, function: FnIdx) -> Option<&Ty> {
+        self.fns.get(function)
+    }
+
+    pub fn errors(&self) -> &[TypeError] {
+        &self.errors
+    }
+
+    fn error(&mut self, db: &Database, idx: ExprIdx, kind: TypeErrorKind) {
+        self.errors.push(TypeError {
+            kind,
+            range: db.source_map.expr_range(idx).unwrap_or_default(),
+        });
+    }
+}
+
+impl Index<ExprIdx> for InferenceResult {
+    type Output = Ty;
+
+    fn index(&self, idx: ExprIdx) -> &Ty {
+        &self.exprs[idx]
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TypeError {
+    kind: TypeErrorKind,
+    range: TextRange,
+}
+
+impl TypeError {
+    pub const fn kind(&self) -> &TypeErrorKind {
+        &self.kind
+    }
+
+    pub const fn range(&self) -> TextRange {
+        self.range
+    }
+}
+
+impl fmt::Display for TypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "error at {}..{}: {}",
+            u32::from(self.range.start()),
+            u32::from(self.range.end()),
+            self.kind
+        )
+    }
+}
+
+impl From<TypeError> for Diagnostic {
+    fn from(error: TypeError) -> Self {
+        Self::error("E0006", error.kind.to_string(), error.range)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TypeErrorKind {
+    Mismatch {
+        expected: Ty,
+        found: Ty,
+    },
+    /// A value used with an operator that doesn't accept its type.
+    Unsatisfied {
+        constraint: Constraint,
+        ty: Ty,
+    },
+    NotCallable {
+        ty: Ty,
+    },
+    ArityMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// A type that would have to contain itself, as in `fn f(x) { x(x) }`.
+    InfiniteType,
+}
+
+impl TypeErrorKind {
+    /// Replaces variables bound since the error was found with their types.
+    fn resolve(&self, checker: &TypeChecker) -> Self {
+        match self {
+            Self::Mismatch { expected, found } => Self::Mismatch {
+                expected: checker.resolve(expected),
+                found: checker.resolve(found),
+            },
+            Self::Unsatisfied { constraint, ty } => Self::Unsatisfied {
+                constraint: *constraint,
+                ty: checker.resolve(ty),
+            },
+            Self::NotCallable { ty } => Self::NotCallable {
+                ty: checker.resolve(ty),
+            },
+            Self::ArityMismatch { .. } | Self::InfiniteType => self.clone(),
+        }
+    }
+}
+
+impl fmt::Display for TypeErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Mismatch { expected, found } => {
+                write!(f, "expected `{expected}`, found `{found}`")
+            }
+            Self::Unsatisfied { constraint, ty } => {
+                write!(f, "expected {constraint}, found `{ty}`")
+            }
+            Self::NotCallable { ty } => write!(f, "`{ty}` is not a function"),
+            Self::ArityMismatch { expected, found } => write!(
+                f,
+                "expected {expected} argument{}, found {found}",
+                if *expected == 1 { "" } else { "s" }
+            ),
+            Self::InfiniteType => write!(f, "cannot construct a type that contains itself"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast, hir, parser};
+    use expect_test::{expect, Expect};
+    use std::fmt::Write;
+
+    fn lower(input: &str) -> (Database, Vec<HirStmt>) {
+        let root = ast::Root::cast(parser::parse(input).syntax()).unwrap();
+        let (mut db, stmts) = hir::lower(&root);
+        assert_eq!(hir::resolve(&mut db, &stmts), []);
+        (db, stmts)
+    }
+
+    /// Shows the type of every top-level definition and expression, then the errors.
+    #[allow(clippy::needless_pass_by_value)]
+    fn check(input: &str, expected: Expect) {
+        let (db, stmts) = lower(input);
+        let result = infer(&db, &stmts);
+
+        let mut actual = String::new();
+        for stmt in &stmts {
+            let _ = match stmt {
+                HirStmt::VariableDef { local, .. } => writeln!(
+                    actual,
+                    "{} : {}",
+                    db.locals[*local].name,
+                    result.local_ty(*local).unwrap()
+                ),
+                HirStmt::FnDef(function) => writeln!(
+                    actual,
+                    "{} : {}",
+                    db.fns[*function].name,
+                    result.fn_ty(*function).unwrap()
+                ),
+                HirStmt::Expr(expr) => {
+                    let range = db.source_map.expr_range(*expr).unwrap();
+                    writeln!(actual, "{} : {}", &input[range], result[*expr])
+                }
+            };
+        }
+        for error in result.errors() {
+            let _ = writeln!(actual, "{error}");
+        }
+
+        expected.assert_eq(&actual);
+    }
+
+    #[test]
+    fn literals() {
+        check(
+            "1; 1.5; true; \"a\"",
+            expect![[r#"
+                1 : Int
+                1.5 : Float
+                true : Bool
+                "a" : String
+            "#]],
+        );
+    }
+
+    #[test]
+    fn let_takes_type_of_value() {
+        check(
+            "let a = 1 + 2\nlet b = a < 3",
+            expect![[r"
+                a : Int
+                b : Bool
+            "]],
+        );
+    }
+
+    #[test]
+    fn arithmetic_on_each_numeric_type() {
+        check(
+            "1 * 2; 1.5 / 2.5; \"a\" + \"b\"; -1.5",
+            expect![[r#"
+                1 * 2 : Int
+                1.5 / 2.5 : Float
+                "a" + "b" : String
+                -1.5 : Float
+            "#]],
+        );
+    }
+
+    #[test]
+    fn mismatched_operands() {
+        check(
+            "1 + true; 1 == 1.0",
+            expect![[r"
+                1 + true : Int
+                1 == 1.0 : Bool
+                error at 4..8: expected `Int`, found `Bool`
+                error at 15..18: expected `Int`, found `Float`
+            "]],
+        );
+    }
+
+    #[test]
+    fn operator_not_defined_for_type() {
+        check(
+            "true + 1; \"a\" * \"b\"; -true; !1",
+            expect![[r#"
+                true + 1 : {unknown}
+                "a" * "b" : {unknown}
+                -true : {unknown}
+                !1 : Bool
+                error at 0..4: expected a number or string, found `Bool`
+                error at 10..13: expected a number, found `String`
+                error at 22..26: expected a number, found `Bool`
+                error at 29..30: expected `Bool`, found `Int`
+            "#]],
+        );
+    }
+
+    #[test]
+    fn logical_operators_need_bools() {
+        check(
+            "true && 1",
+            expect![[r"
+                true && 1 : Bool
+                error at 8..9: expected `Bool`, found `Int`
+            "]],
+        );
+    }
+
+    #[test]
+    fn fn_types_are_inferred_from_their_bodies() {
+        check(
+            "fn inc(x) { x + 1 }\nfn not(b) { !b }",
+            expect![[r"
+                inc : fn(Int) -> Int
+                not : fn(Bool) -> Bool
+            "]],
+        );
+    }
+
+    #[test]
+    fn named_vars() {
+        let var = |n| Ty::Var(TyVar(n));
+        let ty = Ty::Fn {
+            params: vec![
+                Ty::Fn {
+                    params: vec![var(7)],
+                    ret: Box::new(var(3)),
+                },
+                var(7),
+            ],
+            ret: Box::new(var(3)),
+        };
+
+        assert_eq!(ty.display_named().to_string(), "fn(fn('a) -> 'b, 'a) -> 'b");
+        assert_eq!(ty.to_string(), "fn(fn(?7) -> ?3, ?7) -> ?3");
+        assert_eq!(Ty::Int.display_named().to_string(), "Int");
+    }
+
+    #[test]
+    fn generic_fns() {
+        check(
+            "fn id(x) { x }\nid(1)\nid(true)",
+            expect![[r"
+                id : fn(?0) -> ?0
+                id(1) : Int
+                id(true) : Bool
+            "]],
+        );
+    }
+
+    #[test]
+    fn constrained_generic_fns() {
+        check(
+            "fn add(a, b) { a + b }\nadd(1, 2)\nadd(\"a\", \"b\")\nadd(true, false)",
+            expect![[r#"
+                add : fn(?1, ?1) -> ?1
+                add(1, 2) : Int
+                add("a", "b") : String
+                add(true, false) : ?5
+                error at 51..55: expected a number or string, found `Bool`
+                error at 57..62: expected a number or string, found `Bool`
+            "#]],
+        );
+    }
+
+    #[test]
+    fn fn_called_before_its_definition() {
+        check(
+            "f(1)\nfn f(x) { x * 2 }",
+            expect![[r"
+                f(1) : Int
+                f : fn(Int) -> Int
+            "]],
+        );
+    }
+
+    #[test]
+    fn mutually_recursive_fns() {
+        check(
+            "fn even(n) { if n == 0 { true } else { odd(n - 1) } }\n\
+             fn odd(n) { if n == 0 { false } else { even(n - 1) } }",
+            expect![[r"
+                even : fn(Int) -> Bool
+                odd : fn(Int) -> Bool
+            "]],
+        );
+    }
+
+    #[test]
+    fn fn_using_global_is_not_generic_in_it() {
+        check(
+            "let a = 1\nfn f() { a }\nf()",
+            expect![[r"
+                a : Int
+                f : fn() -> Int
+                f() : Int
+            "]],
+        );
+    }
+
+    #[test]
+    fn argument_mismatch() {
+        check(
+            "fn f(x) { x + 1 }\nf(true)",
+            expect![[r"
+                f : fn(Int) -> Int
+                f(true) : Int
+                error at 20..24: expected `Int`, found `Bool`
+            "]],
+        );
+    }
+
+    #[test]
+    fn arity_mismatch() {
+        check(
+            "fn f(x) { x }\nf(1, 2)",
+            expect![[r"
+                f : fn(?0) -> ?0
+                f(1, 2) : ?2
+                error at 14..21: expected 1 argument, found 2
+            "]],
+        );
+    }
+
+    #[test]
+    fn calling_a_non_fn() {
+        check(
+            "let a = 1\na()",
+            expect![[r"
+                a : Int
+                a() : {unknown}
+                error at 10..11: `Int` is not a function
+            "]],
+        );
+    }
+
+    #[test]
+    fn params_called_as_fns() {
+        check(
+            "fn apply(f, x) { f(x) }\napply(small, 1)\nfn small(n) { n < 2 }",
+            expect![[r"
+                apply : fn(fn(?1) -> ?5, ?1) -> ?5
+                apply(small, 1) : Bool
+                small : fn(Int) -> Bool
+            "]],
+        );
+    }
+
+    #[test]
+    fn self_application_is_infinite() {
+        check(
+            "fn f(x) { x(x) }",
+            expect![[r"
+                f : fn(?0) -> ?2
+                error at 10..11: cannot construct a type that contains itself
+            "]],
+        );
+    }
+
+    #[test]
+    fn if_branches_must_agree() {
+        check(
+            "if true { 1 } else { 2 }\nif true { 1 } else { false }",
+            expect![[r"
+                if true { 1 } else { 2 } : Int
+                if true { 1 } else { false } : Int
+                error at 44..53: expected `Int`, found `Bool`
+            "]],
+        );
+    }
+
+    #[test]
+    fn if_without_else_is_unit() {
+        check(
+            "if true { }\nif 1 { 2 }",
+            expect![[r"
+                if true { } : ()
+                if 1 { 2 } : ()
+                error at 15..16: expected `Bool`, found `Int`
+                error at 17..22: expected `()`, found `Int`
+            "]],
+        );
+    }
+
+    #[test]
+    fn loops_take_type_of_break() {
+        check(
+            "loop { break 1 }\nwhile true { break }\nloop { if true { break 1 } else { break false } }",
+            expect![[r"
+                loop { break 1 } : Int
+                while true { break } : ()
+                loop { if true { break 1 } else { break false } } : Int
+                error at 78..83: expected `Int`, found `Bool`
+            "]],
+        );
+    }
+
+    #[test]
+    fn assignment() {
+        check(
+            "let a = 1\na = 2\na += 3\na = true",
+            expect![[r"
+                a : Int
+                a = 2 : ()
+                a += 3 : ()
+                a = true : ()
+                error at 27..31: expected `Int`, found `Bool`
+            "]],
+        );
+    }
+
+    #[test]
+    fn compound_assignment_needs_operator_type() {
+        check(
+            "let b = true\nb -= 1",
+            expect![[r"
+                b : Bool
+                b -= 1 : ()
+                error at 13..14: expected a number, found `Bool`
+            "]],
+        );
+    }
+
+    #[test]
+    fn block_takes_type_of_tail() {
+        check(
+            "{ let a = 1.5 a }\n{ let b = 1 }",
+            expect![[r"
+                { let a = 1.5 a } : Float
+                { let b = 1 } : ()
+            "]],
+        );
+    }
+
+    #[test]
+    fn fns_in_blocks_are_generic() {
+        check(
+            "{ fn id(x) { x } id(1) id(true) }",
+            expect![[r"
+                { fn id(x) { x } id(1) id(true) } : Bool
+            "]],
+        );
+    }
+
+    #[test]
+    fn annotations_fix_types() {
+        check(
+            "fn add(a: Float, b) -> Float { a + b }\nlet f: fn(Float, Float) -> Float = add",
+            expect![[r"
+                add : fn(Float, Float) -> Float
+                f : fn(Float, Float) -> Float
+            "]],
+        );
+    }
+
+    #[test]
+    fn annotations_are_checked() {
+        check(
+            "let a: Int = true\nfn f(x: Bool) -> Int { x }\nf(1)\nlet b: () = 1",
+            expect![[r"
+                a : Int
+                f : fn(Bool) -> Int
+                f(1) : Int
+                b : ()
+                error at 39..44: expected `Int`, found `Bool`
+                error at 13..17: expected `Int`, found `Bool`
+                error at 47..48: expected `Bool`, found `Int`
+                error at 62..63: expected `()`, found `Int`
+            "]],
+        );
+    }
+
+    #[test]
+    fn annotated_fns_are_not_generic() {
+        check(
+            "fn id(x: Int) { x }\nid(true)",
+            expect![[r"
+            id : fn(Int) -> Int
+            id(true) : Int
+            error at 23..27: expected `Int`, found `Bool`
+        "]],
+        );
+    }
+
+    #[test]
+    fn unknown_type_names_are_not_checked() {
+        let (db, stmts) = lower("let a: Strng = 1\na");
+        let result = infer(&db, &stmts);
+
+        assert!(result.errors().is_empty());
+        assert_eq!(
+            result.local_ty(db.locals.iter().next().unwrap().0),
+            Some(&Ty::Error)
+        );
+    }
+
+    #[test]
+    fn checker_remembers_earlier_definitions() {
+        let (db, stmts) = lower("let a = 1\nfn f(x) { x }");
+        let HirStmt::FnDef(function) = stmts[1] else {
+            unreachable!()
+        };
+        let HirStmt::VariableDef { local, .. } = stmts[0] else {
+            unreachable!()
+        };
+
+        let mut checker = TypeChecker::default();
+        assert!(checker.infer(&db, &stmts).errors().is_empty());
+
+        assert_eq!(checker.global(local), Some(Ty::Int));
+        assert_eq!(
+            checker.fn_ty(function).map(|ty| ty.to_string()),
+            Some("fn(?0) -> ?0".to_string())
+        );
+    }
+
+    #[test]
+    fn type_errors_become_diagnostics() {
+        let error = TypeError {
+            kind: TypeErrorKind::NotCallable { ty: Ty::Int },
+            range: TextRange::new(1.into(), 2.into()),
+        };
+
+        assert_eq!(
+            Diagnostic::from(error),
+            Diagnostic::error(
+                "E0006",
+                "`Int` is not a function",
+                TextRange::new(1.into(), 2.into())
+            )
+        );
+    }
+}
@@ -1,5 +1,19 @@
 mod alloc;
+mod debug;
+mod fold;
 #[allow(clippy::module_inception)]
 mod hir;
+mod infer;
+mod resolve;
+mod source_map;
 pub use alloc::Database;
-pub use hir::{lower, BinaryOp, HirExpr, HirStmt, UnaryOp};
+pub use fold::{fold, FoldWarning, FoldWarningKind};
+pub use hir::{
+    lower, BinaryOp, ExprIdx, FnIdx, HirExpr, HirFn, HirStmt, Literal, Local, LocalId, TypeRef,
+    UnaryOp,
+};
+pub use infer::{
+    infer, Constraint, InferenceResult, Ty, TyVar, TypeChecker, TypeError, TypeErrorKind,
+};
+pub use resolve::{resolve, Binding, ResolveError, ResolveErrorKind, Resolver};
+pub use source_map::SourceMap;
@@ -0,0 +1,617 @@
+//! Name resolution: binds every [`HirExpr::VariableRef`] to the definition it refers to.
+//!
+//! Scoping follows the evaluator: `let` bindings are visible from the statement after their
+//! definition to the end of the enclosing block, while functions are hoisted to the top of
+//! theirs so they can be called before (and from within) their definition. Function bodies
+//! see their own parameters and locals, top-level bindings, and every function in an
+//! enclosing scope, but not the `let` bindings of an enclosing function or block.
+
+use super::{Database, ExprIdx, FnIdx, HirExpr, HirStmt, LocalId};
+use crate::Diagnostic;
+use rowan::TextRange;
+use smartstring::alias::String;
+use std::{collections::HashMap, fmt, mem};
+
+/// What a name refers to once resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Binding {
+    Local(LocalId),
+    Fn(FnIdx),
+}
+
+#[derive(Debug, Default, Clone)]
+struct Scope {
+    bindings: HashMap<String, Binding>,
+    /// `let`s further down this scope, used to report use-before-definition.
+    pending: Vec<LocalId>,
+    /// Whether this is the outermost scope of a function body.
+    is_fn: bool,
+}
+
+/// Resolves names against a stack of scopes whose bottom entry holds top-level bindings.
+///
+/// Top-level bindings persist between calls to [`Resolver::resolve`], so statements lowered
+/// from later inputs can refer to definitions from earlier ones.
+#[derive(Debug, Clone)]
+pub struct Resolver {
+    scopes: Vec<Scope>,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self {
+            scopes: vec![Scope::default()],
+        }
+    }
+}
+
+impl Resolver {
+    /// Rewrites the variable references reachable from `stmts` into [`HirExpr::LocalRef`] or
+    /// [`HirExpr::FnRef`], declaring top-level definitions along the way.
+    ///
+    /// References that cannot be resolved are left as [`HirExpr::VariableRef`] and reported.
+    pub fn resolve(&mut self, db: &mut Database, stmts: &[HirStmt]) -> Vec<ResolveError> {
+        let mut errors = Vec::new();
+        self.resolve_stmts(db, stmts, &mut errors);
+        errors
+    }
+
+    /// Declares the top-level definitions in `stmts` as [`Resolver::resolve`] does, without
+    /// resolving the statements again.
+    pub fn declare(&mut self, db: &Database, stmts: &[HirStmt]) {
+        let scope = self.scope_mut();
+        for stmt in stmts {
+            if let HirStmt::FnDef(idx) = stmt {
+                scope
+                    .bindings
+                    .insert(db.fns[*idx].name.clone(), Binding::Fn(*idx));
+            }
+        }
+        for stmt in stmts {
+            if let HirStmt::VariableDef { local, .. } = stmt {
+                scope
+                    .bindings
+                    .insert(db.locals[*local].name.clone(), Binding::Local(*local));
+            }
+        }
+    }
+
+    /// Every top-level binding, in no particular order.
+    pub fn top_level(&self) -> impl Iterator<Item = (&str, Binding)> {
+        self.scopes[0]
+            .bindings
+            .iter()
+            .map(|(name, binding)| (name.as_str(), *binding))
+    }
+
+    fn resolve_stmts(
+        &mut self,
+        db: &mut Database,
+        stmts: &[HirStmt],
+        errors: &mut Vec<ResolveError>,
+    ) {
+        for stmt in stmts {
+            match stmt {
+                HirStmt::FnDef(idx) => {
+                    let name = db.fns[*idx].name.clone();
+                    self.scope_mut().bindings.insert(name, Binding::Fn(*idx));
+                }
+                HirStmt::VariableDef { local, .. } => self.scope_mut().pending.push(*local),
+                HirStmt::Expr(_) => {}
+            }
+        }
+
+        for stmt in stmts {
+            match stmt {
+                HirStmt::VariableDef { local, value } => {
+                    self.resolve_expr(db, *value, errors);
+
+                    let scope = self.scope_mut();
+                    scope.pending.retain(|pending| pending != local);
+                    scope
+                        .bindings
+                        .insert(db.locals[*local].name.clone(), Binding::Local(*local));
+                }
+                HirStmt::FnDef(idx) => self.resolve_fn(db, *idx, errors),
+                HirStmt::Expr(expr) => self.resolve_expr(db, *expr, errors),
+            }
+        }
+    }
+
+    fn resolve_fn(&mut self, db: &mut Database, idx: FnIdx, errors: &mut Vec<ResolveError>) {
+        let function = &db.fns[idx];
+        let body = function.body;
+        let bindings = function
+            .params
+            .iter()
+            .map(|param| (db.locals[*param].name.clone(), Binding::Local(*param)))
+            .collect();
+
+        self.scopes.push(Scope {
+            bindings,
+            pending: Vec::new(),
+            is_fn: true,
+        });
+        self.resolve_expr(db, body, errors);
+        self.scopes.pop();
+    }
+
+    fn resolve_expr(&mut self, db: &mut Database, idx: ExprIdx, errors: &mut Vec<ResolveError>) {
+        let mut expr = mem::replace(&mut db.exprs[idx], HirExpr::Missing);
+
+        match &expr {
+            HirExpr::Assign { place, value, .. } => {
+                self.resolve_expr(db, *place, errors);
+                self.resolve_expr(db, *value, errors);
+            }
+            HirExpr::Binary { lhs, rhs, .. } => {
+                self.resolve_expr(db, *lhs, errors);
+                self.resolve_expr(db, *rhs, errors);
+            }
+            HirExpr::Block { stmts, tail } => {
+                self.scopes.push(Scope::default());
+                self.resolve_stmts(db, stmts, errors);
+                if let Some(tail) = tail {
+                    self.resolve_expr(db, *tail, errors);
+                }
+                self.scopes.pop();
+            }
+            HirExpr::Call { callee, args } => {
+                self.resolve_expr(db, *callee, errors);
+                for arg in args {
+                    self.resolve_expr(db, *arg, errors);
+                }
+            }
+            HirExpr::If {
+                condition,
+                then_branch,
+                else_branch,
+            } => {
+                self.resolve_expr(db, *condition, errors);
+                self.resolve_expr(db, *then_branch, errors);
+                if let Some(else_branch) = else_branch {
+                    self.resolve_expr(db, *else_branch, errors);
+                }
+            }
+            HirExpr::While { condition, body } => {
+                self.resolve_expr(db, *condition, errors);
+                self.resolve_expr(db, *body, errors);
+            }
+            HirExpr::Loop { body } => self.resolve_expr(db, *body, errors),
+            HirExpr::Break { value } => {
+                if let Some(value) = value {
+                    self.resolve_expr(db, *value, errors);
+                }
+            }
+            HirExpr::Unary { expr, .. } => self.resolve_expr(db, *expr, errors),
+            HirExpr::VariableRef { var } => match self.lookup(var) {
+                Some(Binding::Local(local)) => expr = HirExpr::LocalRef(local),
+                Some(Binding::Fn(function)) => expr = HirExpr::FnRef(function),
+                None => {
+                    let name = var.clone();
+                    let pending = self.pending(db, &name);
+                    let kind = if pending.is_some() {
+                        ResolveErrorKind::UseBeforeDefinition { name }
+                    } else {
+                        ResolveErrorKind::Unresolved { name }
+                    };
+
+                    errors.push(ResolveError {
+                        kind,
+                        range: db.source_map.expr_range(idx).unwrap_or_default(),
+                        definition: pending.and_then(|local| db.source_map.local_range(local)),
+                    });
+                }
+            },
+            HirExpr::Literal(_)
+            | HirExpr::Continue
+            | HirExpr::LocalRef(_)
+            | HirExpr::FnRef(_)
+            | HirExpr::Missing => {}
+        }
+
+        db.exprs[idx] = expr;
+    }
+
+    fn lookup(&self, name: &str) -> Option<Binding> {
+        self.visible_scopes()
+            .find_map(|(scope, crossed_fn)| match scope.bindings.get(name)? {
+                Binding::Local(_) if crossed_fn => None,
+                binding => Some(*binding),
+            })
+    }
+
+    /// The closest `let` named `name` that is visible from here once its statement is reached.
+    fn pending(&self, db: &Database, name: &str) -> Option<LocalId> {
+        self.visible_scopes()
+            .filter(|(_, crossed_fn)| !crossed_fn)
+            .find_map(|(scope, _)| {
+                scope
+                    .pending
+                    .iter()
+                    .copied()
+                    .find(|pending| db.locals[*pending].name == name)
+            })
+    }
+
+    /// Scopes from innermost to outermost, paired with whether a function boundary separates
+    /// them from the current scope. Top-level bindings are never considered separated.
+    fn visible_scopes(&self) -> impl Iterator<Item = (&Scope, bool)> {
+        let mut crossed_fn = false;
+
+        self.scopes
+            .iter()
+            .enumerate()
+            .rev()
+            .map(move |(depth, scope)| {
+                let item = (scope, crossed_fn && depth != 0);
+                crossed_fn |= scope.is_fn;
+                item
+            })
+    }
+
+    fn scope_mut(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("the top-level scope is never popped")
+    }
+}
+
+/// Resolves `stmts` with a fresh [`Resolver`]; see [`Resolver::resolve`].
+pub fn resolve(db: &mut Database, stmts: &[HirStmt]) -> Vec<ResolveError> {
+    Resolver::default().resolve(db, stmts)
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ResolveError {
+    kind: ResolveErrorKind,
+    range: TextRange,
+    /// Where the local was defined, for [`ResolveErrorKind::UseBeforeDefinition`].
+    definition: Option<TextRange>,
+}
+
+impl ResolveError {
+    pub const fn kind(&self) -> &ResolveErrorKind {
+        &self.kind
+    }
+
+    pub const fn range(&self) -> TextRange {
+        self.range
+    }
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "error at {}..{}: {}",
+            u32::from(self.range.start()),
+            u32::from(self.range.end()),
+            self.kind
+        )
+    }
+}
+
+impl From<ResolveError> for Diagnostic {
+    fn from(error: ResolveError) -> Self {
+        let code = match error.kind {
+            ResolveErrorKind::Unresolved { .. } => "E0003",
+            ResolveErrorKind::UseBeforeDefinition { .. } => "E0004",
+        };
+        let diagnostic = Self::error(code, error.kind.to_string(), error.range);
+
+        match error.definition {
+            Some(definition) => diagnostic.with_label(definition, "defined here"),
+            None => diagnostic,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ResolveErrorKind {
+    Unresolved { name: String },
+    UseBeforeDefinition { name: String },
+}
+
+impl fmt::Display for ResolveErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unresolved { name } => write!(f, "cannot find `{name}` in this scope"),
+            Self::UseBeforeDefinition { name } => {
+                write!(f, "`{name}` is used before its definition")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast, hir, parser};
+    use std::ops::Range;
+
+    fn lower(input: &str) -> (Database, Vec<HirStmt>) {
+        let root = ast::Root::cast(parser::parse(input).syntax()).unwrap();
+        hir::lower(&root)
+    }
+
+    fn check(input: &str, expected_errors: &[(ResolveErrorKind, Range<u32>)]) {
+        let (mut db, stmts) = lower(input);
+
+        let expected_errors: Vec<_> = expected_errors
+            .iter()
+            .map(|(kind, range)| {
+                (
+                    kind.clone(),
+                    TextRange::new(range.start.into(), range.end.into()),
+                )
+            })
+            .collect();
+
+        let errors: Vec<_> = resolve(&mut db, &stmts)
+            .into_iter()
+            .map(|error| (error.kind, error.range))
+            .collect();
+
+        assert_eq!(errors, expected_errors);
+    }
+
+    fn unresolved(name: &str) -> ResolveErrorKind {
+        ResolveErrorKind::Unresolved { name: name.into() }
+    }
+
+    fn use_before_definition(name: &str) -> ResolveErrorKind {
+        ResolveErrorKind::UseBeforeDefinition { name: name.into() }
+    }
+
+    /// Resolves `input` and returns what the final expression statement was rewritten to.
+    fn resolve_last(input: &str) -> (Database, Vec<HirStmt>, HirExpr) {
+        let (mut db, stmts) = lower(input);
+        assert_eq!(resolve(&mut db, &stmts), []);
+
+        let Some(HirStmt::Expr(expr)) = stmts.last() else {
+            unreachable!()
+        };
+        let expr = mem::replace(&mut db.exprs[*expr], HirExpr::Missing);
+
+        (db, stmts, expr)
+    }
+
+    #[test]
+    fn resolve_defined_variable() {
+        check("let a = 1\na", &[]);
+    }
+
+    #[test]
+    fn reference_points_at_its_definition() {
+        let (_, stmts, expr) = resolve_last("let a = 1\na");
+        let HirStmt::VariableDef { local, .. } = stmts[0] else {
+            unreachable!()
+        };
+
+        assert_eq!(expr, HirExpr::LocalRef(local));
+    }
+
+    #[test]
+    fn reference_points_at_latest_shadowing_definition() {
+        let (_, stmts, expr) = resolve_last("let a = 1\nlet a = a\na");
+        let HirStmt::VariableDef { local, .. } = stmts[1] else {
+            unreachable!()
+        };
+
+        assert_eq!(expr, HirExpr::LocalRef(local));
+    }
+
+    #[test]
+    fn shadowed_definition_is_used_in_its_own_initialiser() {
+        let (db, stmts) = {
+            let (mut db, stmts) = lower("let a = 1\nlet a = a");
+            assert_eq!(resolve(&mut db, &stmts), []);
+            (db, stmts)
+        };
+        let HirStmt::VariableDef { local: first, .. } = stmts[0] else {
+            unreachable!()
+        };
+        let HirStmt::VariableDef { value, .. } = stmts[1] else {
+            unreachable!()
+        };
+
+        assert_eq!(db.exprs[value], HirExpr::LocalRef(first));
+    }
+
+    #[test]
+    fn resolve_fn_reference() {
+        let (_, stmts, expr) = resolve_last("fn f() { 1 }\nf");
+        let HirStmt::FnDef(idx) = stmts[0] else {
+            unreachable!()
+        };
+
+        assert_eq!(expr, HirExpr::FnRef(idx));
+    }
+
+    #[test]
+    fn resolve_param() {
+        let (db, stmts) = {
+            let (mut db, stmts) = lower("fn f(x) { x }");
+            assert_eq!(resolve(&mut db, &stmts), []);
+            (db, stmts)
+        };
+        let HirStmt::FnDef(idx) = stmts[0] else {
+            unreachable!()
+        };
+        let HirExpr::Block {
+            tail: Some(tail), ..
+        } = &db.exprs[db.fns[idx].body]
+        else {
+            unreachable!()
+        };
+
+        assert_eq!(db.exprs[*tail], HirExpr::LocalRef(db.fns[idx].params[0]));
+    }
+
+    #[test]
+    fn unresolved_variable() {
+        check("foo", &[(unresolved("foo"), 0..3)]);
+    }
+
+    #[test]
+    fn unresolved_variable_range_excludes_trivia() {
+        check("1 + foo # comment", &[(unresolved("foo"), 4..7)]);
+    }
+
+    #[test]
+    fn unresolved_variables_are_left_in_place() {
+        let (mut db, stmts) = lower("foo");
+        resolve(&mut db, &stmts);
+        let HirStmt::Expr(expr) = stmts[0] else {
+            unreachable!()
+        };
+
+        assert_eq!(db.exprs[expr], HirExpr::VariableRef { var: "foo".into() });
+    }
+
+    #[test]
+    fn use_before_definition_at_top_level() {
+        check("a\nlet a = 1", &[(use_before_definition("a"), 0..1)]);
+    }
+
+    #[test]
+    fn self_referential_definition() {
+        check("let a = a", &[(use_before_definition("a"), 8..9)]);
+    }
+
+    #[test]
+    fn use_before_definition_in_block() {
+        check("{ b let b = 1 }", &[(use_before_definition("b"), 2..3)]);
+    }
+
+    #[test]
+    fn block_bindings_are_scoped() {
+        check("{ let a = 1 }\na", &[(unresolved("a"), 14..15)]);
+    }
+
+    #[test]
+    fn block_sees_enclosing_bindings() {
+        check("let a = 1\n{ { a } }", &[]);
+    }
+
+    #[test]
+    fn fns_are_hoisted() {
+        check("f()\nfn f() { 1 }", &[]);
+    }
+
+    #[test]
+    fn mutually_recursive_fns() {
+        check("fn a() { b() }\nfn b() { a() }", &[]);
+    }
+
+    #[test]
+    fn fn_sees_earlier_top_level_bindings() {
+        check("let a = 1\nfn f() { a }", &[]);
+    }
+
+    #[test]
+    fn fn_referencing_later_top_level_binding() {
+        check(
+            "fn f() { a }\nlet a = 1",
+            &[(use_before_definition("a"), 9..10)],
+        );
+    }
+
+    #[test]
+    fn fn_does_not_see_enclosing_block_bindings() {
+        check("{ let y = 1 fn f() { y } }", &[(unresolved("y"), 21..22)]);
+    }
+
+    #[test]
+    fn fn_does_not_see_enclosing_fn_params() {
+        check(
+            "fn outer(x) { fn inner() { x } inner() }",
+            &[(unresolved("x"), 27..28)],
+        );
+    }
+
+    #[test]
+    fn nested_fn_sees_enclosing_fns() {
+        check("fn outer() { fn inner() { outer() } inner() }", &[]);
+    }
+
+    #[test]
+    fn params_are_not_visible_outside_fn() {
+        check("fn f(x) { x }\nx", &[(unresolved("x"), 14..15)]);
+    }
+
+    #[test]
+    fn assignment_place_is_resolved() {
+        let (db, stmts, expr) = resolve_last("let a = 1\na = 2");
+        let HirStmt::VariableDef { local, .. } = stmts[0] else {
+            unreachable!()
+        };
+        let HirExpr::Assign { place, .. } = expr else {
+            unreachable!()
+        };
+
+        assert_eq!(db.exprs[place], HirExpr::LocalRef(local));
+    }
+
+    #[test]
+    fn assignment_to_undefined_variable() {
+        check("b = 1", &[(unresolved("b"), 0..1)]);
+    }
+
+    #[test]
+    fn loops_are_resolved() {
+        check(
+            "let a = true\nwhile a { b }\nloop { break c }",
+            &[(unresolved("b"), 23..24), (unresolved("c"), 40..41)],
+        );
+    }
+
+    #[test]
+    fn if_branches_are_resolved() {
+        check(
+            "let a = true\nif a { b } else { let c = 1 c }\nc",
+            &[(unresolved("b"), 20..21), (unresolved("c"), 45..46)],
+        );
+    }
+
+    #[test]
+    fn top_level_bindings_persist_across_calls() {
+        let mut resolver = Resolver::default();
+
+        let (mut db, first) = lower("let a = 1");
+        assert_eq!(resolver.resolve(&mut db, &first), []);
+
+        let root = ast::Root::cast(parser::parse("a").syntax()).unwrap();
+        let second: Vec<_> = root
+            .stmts()
+            .filter_map(|stmt| db.lower_stmt(stmt))
+            .collect();
+        assert_eq!(resolver.resolve(&mut db, &second), []);
+    }
+
+    #[test]
+    fn use_before_definition_labels_the_definition() {
+        let (mut db, stmts) = lower("let a = b\nlet b = 1");
+        let errors = resolve(&mut db, &stmts);
+        let diagnostic = Diagnostic::from(errors[0].clone());
+
+        assert_eq!(diagnostic.code, "E0004");
+        assert_eq!(diagnostic.range, TextRange::new(8.into(), 9.into()));
+        assert_eq!(diagnostic.labels.len(), 1);
+        assert_eq!(
+            diagnostic.labels[0].range,
+            TextRange::new(14.into(), 15.into())
+        );
+    }
+
+    #[test]
+    fn unresolved_has_no_labels() {
+        let (mut db, stmts) = lower("a");
+        let errors = resolve(&mut db, &stmts);
+        let diagnostic = Diagnostic::from(errors[0].clone());
+
+        assert_eq!(diagnostic.code, "E0003");
+        assert!(diagnostic.labels.is_empty());
+    }
+}
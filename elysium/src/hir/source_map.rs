@@ -0,0 +1,256 @@
+//! Mapping between HIR nodes and the syntax they were lowered from.
+
+use std::collections::HashMap;
+
+use la_arena::{ArenaMap, Idx};
+use rowan::{TextRange, TextSize};
+
+use super::{ExprIdx, FnIdx, HirStmt, LocalId};
+use crate::syntax::{self, SyntaxKind, SyntaxNode, SyntaxNodePtr, SyntaxToken};
+
+/// Records, for every expression, local and function in a [`Database`](super::Database), the
+/// syntax node it was lowered from, and the reverse.
+///
+/// Pointers are only meaningful against the tree the node was lowered from. Expressions
+/// synthesised for missing syntax have no entry.
+#[derive(Debug, Default, PartialEq)]
+pub struct SourceMap {
+    exprs: NodeMap<super::HirExpr>,
+    locals: NodeMap<super::Local>,
+    fns: NodeMap<super::HirFn>,
+}
+
+impl SourceMap {
+    pub(super) fn insert_expr(&mut self, idx: ExprIdx, node: &SyntaxNode) {
+        self.exprs.insert(idx, node, syntax::trimmed_range(node));
+    }
+
+    /// Makes `node` map back to `idx` without changing where `idx` points, for syntax such as
+    /// parentheses that is transparent in the HIR.
+    pub(super) fn alias_expr(&mut self, node: &SyntaxNode, idx: ExprIdx) {
+        self.exprs.from_syntax.insert(SyntaxNodePtr::new(node), idx);
+    }
+
+    pub(super) fn insert_local(&mut self, local: LocalId, node: &SyntaxNode, name: &SyntaxToken) {
+        self.locals.insert(local, node, name.text_range());
+    }
+
+    pub(super) fn insert_fn(&mut self, function: FnIdx, node: &SyntaxNode, name: &SyntaxToken) {
+        self.fns.insert(function, node, name.text_range());
+    }
+
+    pub fn expr_syntax(&self, idx: ExprIdx) -> Option<SyntaxNodePtr> {
+        self.exprs.syntax(idx)
+    }
+
+    /// The range of the expression's syntax, excluding surrounding trivia.
+    pub fn expr_range(&self, idx: ExprIdx) -> Option<TextRange> {
+        self.exprs.range(idx)
+    }
+
+    pub fn node_expr(&self, ptr: &SyntaxNodePtr) -> Option<ExprIdx> {
+        self.exprs.node(ptr)
+    }
+
+    /// The `VariableDef` or `Param` node that introduced `local`.
+    pub fn local_syntax(&self, local: LocalId) -> Option<SyntaxNodePtr> {
+        self.locals.syntax(local)
+    }
+
+    /// The range of the name `local` was introduced with.
+    pub fn local_range(&self, local: LocalId) -> Option<TextRange> {
+        self.locals.range(local)
+    }
+
+    pub fn node_local(&self, ptr: &SyntaxNodePtr) -> Option<LocalId> {
+        self.locals.node(ptr)
+    }
+
+    pub fn fn_syntax(&self, function: FnIdx) -> Option<SyntaxNodePtr> {
+        self.fns.syntax(function)
+    }
+
+    /// The range of the function's name.
+    pub fn fn_range(&self, function: FnIdx) -> Option<TextRange> {
+        self.fns.range(function)
+    }
+
+    pub fn node_fn(&self, ptr: &SyntaxNodePtr) -> Option<FnIdx> {
+        self.fns.node(ptr)
+    }
+
+    /// The statement node `stmt` was lowered from. For expression statements this is the
+    /// expression itself.
+    pub fn stmt_syntax(&self, stmt: &HirStmt) -> Option<SyntaxNodePtr> {
+        match stmt {
+            HirStmt::VariableDef { local, .. } => self.local_syntax(*local),
+            HirStmt::FnDef(function) => self.fn_syntax(*function),
+            HirStmt::Expr(expr) => self.expr_syntax(*expr),
+        }
+    }
+
+    /// The innermost expression lowered from `root` that covers `offset`.
+    pub fn expr_at_offset(&self, root: &SyntaxNode, offset: TextSize) -> Option<ExprIdx> {
+        let token = root
+            .token_at_offset(offset)
+            .max_by_key(|token| match token.kind() {
+                SyntaxKind::Ident
+                | SyntaxKind::Number
+                | SyntaxKind::Float
+                | SyntaxKind::String
+                | SyntaxKind::TrueKw
+                | SyntaxKind::FalseKw => 2,
+                kind if kind.is_trivia() => 0,
+                _ => 1,
+            })?;
+
+        token
+            .parent_ancestors()
+            .find_map(|node| self.node_expr(&SyntaxNodePtr::new(&node)))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct NodeMap<T> {
+    to_syntax: ArenaMap<Idx<T>, (SyntaxNodePtr, TextRange)>,
+    from_syntax: HashMap<SyntaxNodePtr, Idx<T>>,
+}
+
+// Derived `Default` would needlessly require `T: Default`.
+impl<T> Default for NodeMap<T> {
+    fn default() -> Self {
+        Self {
+            to_syntax: ArenaMap::default(),
+            from_syntax: HashMap::default(),
+        }
+    }
+}
+
+impl<T> NodeMap<T> {
+    fn insert(&mut self, idx: Idx<T>, node: &SyntaxNode, range: TextRange) {
+        let ptr = SyntaxNodePtr::new(node);
+        self.to_syntax.insert(idx, (ptr, range));
+        self.from_syntax.insert(ptr, idx);
+    }
+
+    fn syntax(&self, idx: Idx<T>) -> Option<SyntaxNodePtr> {
+        self.to_syntax.get(idx).map(|(ptr, _)| *ptr)
+    }
+
+    fn range(&self, idx: Idx<T>) -> Option<TextRange> {
+        self.to_syntax.get(idx).map(|(_, range)| *range)
+    }
+
+    fn node(&self, ptr: &SyntaxNodePtr) -> Option<Idx<T>> {
+        self.from_syntax.get(ptr).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ast, hir,
+        hir::{HirExpr, Literal},
+        parser,
+    };
+
+    fn lower(input: &str) -> (SyntaxNode, hir::Database, Vec<HirStmt>) {
+        let syntax = parser::parse(input).syntax();
+        let root = ast::Root::cast(syntax.clone()).unwrap();
+        let (db, stmts) = hir::lower(&root);
+        (syntax, db, stmts)
+    }
+
+    fn range(start: u32, end: u32) -> TextRange {
+        TextRange::new(start.into(), end.into())
+    }
+
+    #[test]
+    fn exprs_map_to_their_syntax_and_back() {
+        let (root, db, stmts) = lower("1 + 2\n");
+        let HirStmt::Expr(idx) = stmts[0] else {
+            unreachable!()
+        };
+
+        let ptr = db.source_map.expr_syntax(idx).unwrap();
+        let node = ptr.to_node(&root);
+
+        assert_eq!(node.kind(), SyntaxKind::InfixExpr);
+        assert_eq!(db.source_map.expr_range(idx), Some(range(0, 5)));
+        assert_eq!(db.source_map.node_expr(&ptr), Some(idx));
+    }
+
+    #[test]
+    fn top_level_stmts_map_to_their_syntax() {
+        let (root, db, stmts) = lower("let a = 1\nfn f(x) { x }\na");
+
+        let kinds: Vec<_> = stmts
+            .iter()
+            .map(|stmt| {
+                db.source_map
+                    .stmt_syntax(stmt)
+                    .unwrap()
+                    .to_node(&root)
+                    .kind()
+            })
+            .collect();
+
+        assert_eq!(
+            kinds,
+            [
+                SyntaxKind::VariableDef,
+                SyntaxKind::FnDef,
+                SyntaxKind::VariableRef
+            ]
+        );
+    }
+
+    #[test]
+    fn definitions_point_at_their_names() {
+        let (_, db, stmts) = lower("let a = 1\nfn f(x) { x }");
+        let HirStmt::VariableDef { local, .. } = stmts[0] else {
+            unreachable!()
+        };
+        let HirStmt::FnDef(function) = stmts[1] else {
+            unreachable!()
+        };
+        let param = db.fns[function].params[0];
+
+        assert_eq!(db.source_map.local_range(local), Some(range(4, 5)));
+        assert_eq!(db.source_map.fn_range(function), Some(range(13, 14)));
+        assert_eq!(db.source_map.local_range(param), Some(range(15, 16)));
+    }
+
+    #[test]
+    fn parens_map_back_to_their_inner_expr() {
+        let (root, db, stmts) = lower("(1)");
+        let HirStmt::Expr(idx) = stmts[0] else {
+            unreachable!()
+        };
+
+        let paren = root.first_child().unwrap();
+
+        assert_eq!(paren.kind(), SyntaxKind::ParenExpr);
+        assert_eq!(
+            db.source_map.node_expr(&SyntaxNodePtr::new(&paren)),
+            Some(idx)
+        );
+        assert_eq!(db.source_map.expr_range(idx), Some(range(1, 2)));
+    }
+
+    #[test]
+    fn innermost_expr_at_offset() {
+        let (root, db, _) = lower("1 + foo(2)");
+
+        let at = |offset: u32| {
+            let idx = db.source_map.expr_at_offset(&root, offset.into()).unwrap();
+            (&db.exprs[idx], db.source_map.expr_range(idx).unwrap())
+        };
+
+        assert_eq!(at(0), (&HirExpr::Literal(Literal::Int(1)), range(0, 1)));
+        assert!(matches!(at(2), (HirExpr::Binary { .. }, r) if r == range(0, 10)));
+        assert!(matches!(at(5), (HirExpr::VariableRef { .. }, r) if r == range(4, 7)));
+        assert!(matches!(at(10), (HirExpr::Call { .. }, r) if r == range(4, 10)));
+    }
+}
@@ -20,9 +20,8 @@ impl<'a> Iterator for Lexer<'a> {
     type Item = Token<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let Ok(kind) = self.inner.next()? else {
-            return None;
-        };
+        // Characters that start no token become error tokens, for the parser to report.
+        let kind = self.inner.next()?.unwrap_or(TokenKind::Error);
         let text = self.inner.slice();
         let range = {
             let Range { start, end } = self.inner.span();
@@ -70,6 +69,46 @@ mod tests {
         check("let", TokenKind::LetKw);
     }
 
+    #[test]
+    fn lex_if_keyword() {
+        check("if", TokenKind::IfKw);
+    }
+
+    #[test]
+    fn lex_else_keyword() {
+        check("else", TokenKind::ElseKw);
+    }
+
+    #[test]
+    fn lex_while_keyword() {
+        check("while", TokenKind::WhileKw);
+    }
+
+    #[test]
+    fn lex_loop_keyword() {
+        check("loop", TokenKind::LoopKw);
+    }
+
+    #[test]
+    fn lex_break_keyword() {
+        check("break", TokenKind::BreakKw);
+    }
+
+    #[test]
+    fn lex_continue_keyword() {
+        check("continue", TokenKind::ContinueKw);
+    }
+
+    #[test]
+    fn lex_true_keyword() {
+        check("true", TokenKind::TrueKw);
+    }
+
+    #[test]
+    fn lex_false_keyword() {
+        check("false", TokenKind::FalseKw);
+    }
+
     #[test]
     fn lex_alphabetic_identifier() {
         check("abcd", TokenKind::Ident);
@@ -90,6 +129,65 @@ mod tests {
         check("123456", TokenKind::Number);
     }
 
+    #[test]
+    fn lex_prefixed_numbers() {
+        check("0xff", TokenKind::Number);
+        check("0o17", TokenKind::Number);
+        check("0b1010", TokenKind::Number);
+        check("1_000", TokenKind::Number);
+    }
+
+    #[test]
+    fn lex_malformed_number_as_one_token() {
+        check("0b102", TokenKind::Number);
+        check("12ab", TokenKind::Number);
+    }
+
+    #[test]
+    fn lex_float() {
+        check("1.5", TokenKind::Float);
+        check("1e-3", TokenKind::Float);
+        check("1e5", TokenKind::Float);
+        check("2.5E+2", TokenKind::Float);
+        check("1_0.0_1", TokenKind::Float);
+        check("1e", TokenKind::Float);
+    }
+
+    #[test]
+    fn hex_digit_e_is_not_an_exponent() {
+        let kinds: Vec<_> = Lexer::new("0xe+1").map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            [TokenKind::Number, TokenKind::Plus, TokenKind::Number]
+        );
+    }
+
+    #[test]
+    fn lex_string() {
+        check(r#""hello, world""#, TokenKind::String);
+    }
+
+    #[test]
+    fn lex_string_with_escapes() {
+        check(r#""a \"quote\" \\ \u{41}""#, TokenKind::String);
+    }
+
+    #[test]
+    fn lex_unterminated_string() {
+        check(r#""abc"#, TokenKind::String);
+        check(r#""abc\"#, TokenKind::String);
+        check(r#""abc\""#, TokenKind::String);
+    }
+
+    #[test]
+    fn unterminated_string_ends_at_newline() {
+        let kinds: Vec<_> = Lexer::new("\"abc\n1").map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            [TokenKind::String, TokenKind::Newline, TokenKind::Number]
+        );
+    }
+
     #[test]
     fn lex_plus() {
         check("+", TokenKind::Plus);
@@ -115,6 +213,36 @@ mod tests {
         check("=", TokenKind::Equals);
     }
 
+    #[test]
+    fn lex_compound_assignment_operators() {
+        check("+=", TokenKind::PlusEq);
+        check("-=", TokenKind::MinusEq);
+        check("*=", TokenKind::StarEq);
+        check("/=", TokenKind::SlashEq);
+    }
+
+    #[test]
+    fn lex_comparison_operators() {
+        check("==", TokenKind::EqEq);
+        check("!=", TokenKind::BangEq);
+        check("<", TokenKind::Less);
+        check("<=", TokenKind::LessEq);
+        check(">", TokenKind::Greater);
+        check(">=", TokenKind::GreaterEq);
+    }
+
+    #[test]
+    fn lex_logical_operators() {
+        check("&&", TokenKind::AmpAmp);
+        check("||", TokenKind::PipePipe);
+        check("!", TokenKind::Bang);
+    }
+
+    #[test]
+    fn lex_comma() {
+        check(",", TokenKind::Comma);
+    }
+
     #[test]
     fn lex_left_brace() {
         check("{", TokenKind::LBrace);
@@ -147,6 +275,55 @@ mod tests {
 
     #[test]
     fn lex_spaces_and_newlines() {
-        check(" \n", TokenKind::Whitespace);
+        check(" \n  \n", TokenKind::Newline);
+    }
+
+    #[test]
+    fn lex_unrecognized_characters() {
+        check("@", TokenKind::Error);
+        check("é", TokenKind::Error);
+
+        let kinds: Vec<_> = Lexer::new("a&\\1").map(|token| token.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Ident,
+                TokenKind::Error,
+                TokenKind::Error,
+                TokenKind::Number
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_tabs() {
+        check("\t \t", TokenKind::Whitespace);
+        check("\t\n\t", TokenKind::Newline);
+    }
+
+    #[test]
+    fn lex_crlf_newlines() {
+        check(" \r\n\t\r\n", TokenKind::Newline);
+    }
+
+    #[test]
+    fn comments_and_strings_end_before_crlf() {
+        let kinds: Vec<_> = Lexer::new("# a\r\n\"b\r\n")
+            .map(|token| token.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                TokenKind::Comment,
+                TokenKind::Newline,
+                TokenKind::String,
+                TokenKind::Newline
+            ]
+        );
+    }
+
+    #[test]
+    fn lex_semicolon() {
+        check(";", TokenKind::Semicolon);
     }
 }
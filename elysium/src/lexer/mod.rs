@@ -1,6 +1,10 @@
 //! Lexing logic and structures
 mod lex;
+mod number;
 mod tokens;
+mod unescape;
 
 pub use lex::{Lexer, Token};
+pub use number::{parse_float, parse_int, NumberError};
 pub use tokens::TokenKind;
+pub use unescape::{unescape, write_escaped, EscapeError};
@@ -0,0 +1,109 @@
+//! Decoding of the text of number literals.
+
+/// A problem with a number literal, found while decoding it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberError {
+    /// A character that isn't a digit of the literal's radix, such as the `2` of `0b12`.
+    InvalidDigit,
+    /// A radix prefix or exponent marker with no digits after it, such as `0x` or `1e`.
+    MissingDigits,
+    /// An integer larger than `u64::MAX`.
+    IntegerTooLarge,
+    /// A float too large to be represented as anything but infinity.
+    FloatTooLarge,
+}
+
+/// Decodes an integer literal: decimal, or hexadecimal, octal or binary with a `0x`, `0o` or
+/// `0b` prefix. Underscores may separate digits anywhere after the first character.
+pub fn parse_int(text: &str) -> Result<u64, NumberError> {
+    let (radix, digits) = match text.get(..2) {
+        Some("0x") => (16, &text[2..]),
+        Some("0o") => (8, &text[2..]),
+        Some("0b") => (2, &text[2..]),
+        _ => (10, text),
+    };
+
+    let digits: String = digits.chars().filter(|c| *c != '_').collect();
+    if digits.is_empty() {
+        return Err(NumberError::MissingDigits);
+    }
+    if !digits.chars().all(|c| c.is_digit(radix)) {
+        return Err(NumberError::InvalidDigit);
+    }
+
+    u64::from_str_radix(&digits, radix).map_err(|_| NumberError::IntegerTooLarge)
+}
+
+/// Decodes a float literal, which has a fractional part, an exponent, or both.
+pub fn parse_float(text: &str) -> Result<f64, NumberError> {
+    let text: String = text.chars().filter(|c| *c != '_').collect();
+    if text.ends_with(['e', 'E', '+', '-']) {
+        return Err(NumberError::MissingDigits);
+    }
+
+    // The lexer only produces digits, a `.`, and a well-placed exponent, all of which Rust
+    // accepts.
+    let value: f64 = text.parse().map_err(|_| NumberError::InvalidDigit)?;
+    if value.is_infinite() {
+        return Err(NumberError::FloatTooLarge);
+    }
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decimal_integers() {
+        assert_eq!(parse_int("0"), Ok(0));
+        assert_eq!(parse_int("1_000_000"), Ok(1_000_000));
+        assert_eq!(parse_int("18446744073709551615"), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn prefixed_integers() {
+        assert_eq!(parse_int("0xff"), Ok(255));
+        assert_eq!(parse_int("0xDEAD_beef"), Ok(0xdead_beef));
+        assert_eq!(parse_int("0o17"), Ok(15));
+        assert_eq!(parse_int("0b1010"), Ok(10));
+        assert_eq!(parse_int("0b_1"), Ok(1));
+    }
+
+    #[test]
+    fn malformed_integers() {
+        assert_eq!(parse_int("0b102"), Err(NumberError::InvalidDigit));
+        assert_eq!(parse_int("0o8"), Err(NumberError::InvalidDigit));
+        assert_eq!(parse_int("12ab"), Err(NumberError::InvalidDigit));
+        assert_eq!(parse_int("0x"), Err(NumberError::MissingDigits));
+        assert_eq!(parse_int("0x__"), Err(NumberError::MissingDigits));
+    }
+
+    #[test]
+    fn integers_too_large() {
+        assert_eq!(
+            parse_int("18446744073709551616"),
+            Err(NumberError::IntegerTooLarge)
+        );
+        assert_eq!(
+            parse_int("0x1_0000_0000_0000_0000"),
+            Err(NumberError::IntegerTooLarge)
+        );
+    }
+
+    #[test]
+    fn floats() {
+        assert_eq!(parse_float("1.5"), Ok(1.5));
+        assert_eq!(parse_float("1e-3"), Ok(0.001));
+        assert_eq!(parse_float("2.5E+2"), Ok(250.0));
+        assert_eq!(parse_float("1_000.000_1"), Ok(1_000.000_1));
+    }
+
+    #[test]
+    fn malformed_floats() {
+        assert_eq!(parse_float("1e"), Err(NumberError::MissingDigits));
+        assert_eq!(parse_float("1.5e-"), Err(NumberError::MissingDigits));
+        assert_eq!(parse_float("1e999"), Err(NumberError::FloatTooLarge));
+    }
+}
@@ -6,21 +6,66 @@ use std::fmt;
     Debug, Copy, Clone, PartialEq, Logos, FromPrimitive, ToPrimitive, Hash, PartialOrd, Ord, Eq,
 )]
 pub enum TokenKind {
-    #[regex("[ \n]+")]
+    /// Spaces and tabs.
+    #[regex("[ \t]+")]
     Whitespace,
 
+    /// Whitespace spanning a line break, which can end a statement. Line breaks are `\n` or
+    /// `\r\n`.
+    #[regex("[ \t]*\r?\n([ \t]|\r?\n)*")]
+    Newline,
+
     #[token("fn")]
     FnKw,
 
     #[token("let")]
     LetKw,
 
+    #[token("if")]
+    IfKw,
+
+    #[token("else")]
+    ElseKw,
+
+    #[token("while")]
+    WhileKw,
+
+    #[token("loop")]
+    LoopKw,
+
+    #[token("break")]
+    BreakKw,
+
+    #[token("continue")]
+    ContinueKw,
+
+    #[token("true")]
+    TrueKw,
+
+    #[token("false")]
+    FalseKw,
+
     #[regex("[A-Za-z][A-Za-z0-9]*")]
     Ident,
 
-    #[regex("[0-9]+")]
+    /// An integer in any radix. Letters are included so that malformed literals such as `0b12`
+    /// stay one token for validation to report.
+    #[regex("[0-9][0-9A-Za-z_]*")]
     Number,
 
+    /// A number with a fractional part, an exponent, or both. Takes precedence over `Number`
+    /// for literals such as `1e5` that would fit either.
+    #[regex(
+        r"[0-9][0-9_]*(\.[0-9][0-9_]*([eE][+-]?[0-9_]*)?|[eE][+-]?[0-9_]*)",
+        priority = 10
+    )]
+    Float,
+
+    /// A double-quoted string on a single line. Unterminated strings and bad escapes are still
+    /// lexed as one token, and reported by validation instead.
+    #[regex(r#""([^"\\\r\n]|\\[^\r\n])*\\?"?"#)]
+    String,
+
     #[token("+")]
     Plus,
 
@@ -36,6 +81,57 @@ pub enum TokenKind {
     #[token("=")]
     Equals,
 
+    #[token("+=")]
+    PlusEq,
+
+    #[token("-=")]
+    MinusEq,
+
+    #[token("*=")]
+    StarEq,
+
+    #[token("/=")]
+    SlashEq,
+
+    #[token("==")]
+    EqEq,
+
+    #[token("!=")]
+    BangEq,
+
+    #[token("<")]
+    Less,
+
+    #[token("<=")]
+    LessEq,
+
+    #[token(">")]
+    Greater,
+
+    #[token(">=")]
+    GreaterEq,
+
+    #[token("&&")]
+    AmpAmp,
+
+    #[token("||")]
+    PipePipe,
+
+    #[token("!")]
+    Bang,
+
+    #[token(",")]
+    Comma,
+
+    #[token(";")]
+    Semicolon,
+
+    #[token(":")]
+    Colon,
+
+    #[token("->")]
+    Arrow,
+
     #[token("(")]
     LParen,
 
@@ -48,7 +144,7 @@ pub enum TokenKind {
     #[token("}")]
     RBrace,
 
-    #[regex("#.*")]
+    #[regex("#[^\r\n]*")]
     Comment,
 
     Error,
@@ -56,7 +152,7 @@ pub enum TokenKind {
 
 impl TokenKind {
     pub const fn is_trivia(self) -> bool {
-        matches!(self, Self::Whitespace | Self::Comment)
+        matches!(self, Self::Whitespace | Self::Newline | Self::Comment)
     }
 }
 
@@ -64,15 +160,43 @@ impl fmt::Display for TokenKind {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str(match self {
             Self::Whitespace => "whitespace",
+            Self::Newline => "newline",
             Self::FnKw => "'fn'",
             Self::LetKw => "'let'",
+            Self::IfKw => "'if'",
+            Self::ElseKw => "'else'",
+            Self::WhileKw => "'while'",
+            Self::LoopKw => "'loop'",
+            Self::BreakKw => "'break'",
+            Self::ContinueKw => "'continue'",
+            Self::TrueKw => "'true'",
+            Self::FalseKw => "'false'",
             Self::Ident => "identifier",
             Self::Number => "number-literal",
+            Self::Float => "float-literal",
+            Self::String => "string-literal",
             Self::Plus => "'+'",
             Self::Minus => "'-'",
             Self::Star => "'*'",
             Self::Slash => "'/'",
             Self::Equals => "'='",
+            Self::PlusEq => "'+='",
+            Self::MinusEq => "'-='",
+            Self::StarEq => "'*='",
+            Self::SlashEq => "'/='",
+            Self::EqEq => "'=='",
+            Self::BangEq => "'!='",
+            Self::Less => "'<'",
+            Self::LessEq => "'<='",
+            Self::Greater => "'>'",
+            Self::GreaterEq => "'>='",
+            Self::AmpAmp => "'&&'",
+            Self::PipePipe => "'||'",
+            Self::Bang => "'!'",
+            Self::Comma => "','",
+            Self::Semicolon => "';'",
+            Self::Colon => "':'",
+            Self::Arrow => "'->'",
             Self::LParen => "'('",
             Self::RParen => "')'",
             Self::LBrace => "'{'",
@@ -0,0 +1,174 @@
+//! Decoding of the escape sequences in string literals.
+
+use std::{fmt, iter::Peekable, ops::Range, str::CharIndices};
+
+/// A problem with a string literal, found while decoding it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EscapeError {
+    /// There is no closing quote before the end of the line.
+    Unterminated,
+    /// A backslash followed by something other than `n`, `t`, `"`, `\` or `u`.
+    UnknownEscape,
+    /// A `\u{...}` escape that is malformed or does not name a Unicode scalar value.
+    InvalidUnicodeEscape,
+}
+
+/// Decodes a string literal token, quotes included, into the string it denotes.
+///
+/// Problems are reported with their range within `text`, and escapes that could not be
+/// decoded are left out of the returned string.
+pub fn unescape(text: &str) -> (String, Vec<(EscapeError, Range<usize>)>) {
+    let mut value = String::new();
+    let mut errors = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    chars.next_if_eq(&(0, '"'));
+
+    loop {
+        match chars.next() {
+            None => {
+                errors.push((EscapeError::Unterminated, 0..text.len()));
+                break;
+            }
+            // The lexer only lets an unescaped quote end the token.
+            Some((_, '"')) => break,
+            Some((start, '\\')) => match unescape_char(&mut chars) {
+                Some(Ok(c)) => value.push(c),
+                Some(Err(error)) => {
+                    let end = chars.peek().map_or(text.len(), |(end, _)| *end);
+                    errors.push((error, start..end));
+                }
+                None => {}
+            },
+            Some((_, c)) => value.push(c),
+        }
+    }
+
+    (value, errors)
+}
+
+/// Decodes the escape after a backslash, or returns `None` at the end of an unterminated
+/// literal.
+fn unescape_char(chars: &mut Peekable<CharIndices>) -> Option<Result<char, EscapeError>> {
+    let c = match chars.next()?.1 {
+        'n' => '\n',
+        't' => '\t',
+        '"' => '"',
+        '\\' => '\\',
+        'u' => return Some(unescape_unicode(chars)),
+        _ => return Some(Err(EscapeError::UnknownEscape)),
+    };
+
+    Some(Ok(c))
+}
+
+/// Decodes the `{...}` of a `\u{...}` escape, which holds one to six hex digits. Stops short
+/// of any character that doesn't fit, so a malformed escape never swallows the closing quote.
+fn unescape_unicode(chars: &mut Peekable<CharIndices>) -> Result<char, EscapeError> {
+    if chars.next_if(|(_, c)| *c == '{').is_none() {
+        return Err(EscapeError::InvalidUnicodeEscape);
+    }
+
+    let mut digits = String::new();
+    while let Some((_, digit)) = chars.next_if(|(_, c)| c.is_ascii_hexdigit()) {
+        digits.push(digit);
+    }
+
+    if chars.next_if(|(_, c)| *c == '}').is_none() || !(1..=6).contains(&digits.len()) {
+        return Err(EscapeError::InvalidUnicodeEscape);
+    }
+
+    u32::from_str_radix(&digits, 16)
+        .ok()
+        .and_then(char::from_u32)
+        .ok_or(EscapeError::InvalidUnicodeEscape)
+}
+
+/// Writes `value` as a string literal, the inverse of [`unescape`].
+pub fn write_escaped(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    f.write_str("\"")?;
+    for c in value.chars() {
+        match c {
+            '\n' => f.write_str("\\n")?,
+            '\t' => f.write_str("\\t")?,
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            c if c.is_control() => write!(f, "\\u{{{:x}}}", u32::from(c))?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    f.write_str("\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(text: &str, expected_value: &str, expected_errors: &[(EscapeError, Range<usize>)]) {
+        assert_eq!(
+            unescape(text),
+            (expected_value.to_string(), expected_errors.to_vec())
+        );
+    }
+
+    struct Escaped<'a>(&'a str);
+
+    impl fmt::Display for Escaped<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write_escaped(f, self.0)
+        }
+    }
+
+    #[test]
+    fn escaping_round_trips() {
+        let value = "tab\tquote\"slash\\bell\u{7}\u{1F600}\n";
+        let escaped = Escaped(value).to_string();
+
+        // Only control characters need escaping; others are written as they are.
+        assert_eq!(
+            escaped,
+            "\"tab\\tquote\\\"slash\\\\bell\\u{7}\u{1F600}\\n\""
+        );
+        assert_eq!(unescape(&escaped), (value.to_string(), Vec::new()));
+    }
+
+    #[test]
+    fn plain_string() {
+        check(r#""hello""#, "hello", &[]);
+    }
+
+    #[test]
+    fn simple_escapes() {
+        check(r#""a\nb\tc\"d\\e""#, "a\nb\tc\"d\\e", &[]);
+    }
+
+    #[test]
+    fn unicode_escapes() {
+        check(r#""\u{41}\u{1F600}""#, "A\u{1F600}", &[]);
+    }
+
+    #[test]
+    fn unterminated() {
+        check(r#""abc"#, "abc", &[(EscapeError::Unterminated, 0..4)]);
+        check(r#""abc\"#, "abc", &[(EscapeError::Unterminated, 0..5)]);
+    }
+
+    #[test]
+    fn unknown_escape() {
+        check(r#""a\qb""#, "ab", &[(EscapeError::UnknownEscape, 2..4)]);
+    }
+
+    #[test]
+    fn invalid_unicode_escapes() {
+        check(
+            r#""\u41\u{}\u{1234567}\u{D800}\u{41""#,
+            "41",
+            &[
+                (EscapeError::InvalidUnicodeEscape, 1..3),
+                (EscapeError::InvalidUnicodeEscape, 5..9),
+                (EscapeError::InvalidUnicodeEscape, 9..20),
+                (EscapeError::InvalidUnicodeEscape, 20..28),
+                (EscapeError::InvalidUnicodeEscape, 28..33),
+            ],
+        );
+    }
+}
@@ -2,16 +2,27 @@
 //! Core functionality for parser.
 
 use rowan::GreenNode;
-use syntax::SyntaxNode;
+use std::fmt::Write;
 pub mod ast;
+pub mod codegen;
+pub mod diagnostic;
+pub mod eval;
+pub mod fmt;
 pub mod hir;
 mod lexer;
+mod line_index;
 mod parser;
+pub mod session;
 mod syntax;
 pub use ast::{Root, Stmt};
-pub use parser::parse;
+pub use diagnostic::{Diagnostic, Severity};
+pub use line_index::{LineCol, LineIndex, WideLineCol};
+pub use parser::{incremental_reparse, parse, ParseError, TextEdit};
+pub use session::Session;
+pub use syntax::{trimmed_range, SyntaxKind, SyntaxNode, SyntaxNodePtr, SyntaxToken};
 
 /// AST like structure.
+#[derive(Debug)]
 pub struct Parse {
     green_node: GreenNode,
     errors: Vec<parser::ParseError>,
@@ -27,21 +38,41 @@ impl Parse {
         s.push_str(&tree[0..tree.len() - 1]);
 
         for error in &self.errors {
-            s.push_str(&format!("\n{error}"));
+            let _ = write!(s, "\n{error}");
         }
         s
     }
 
+    /// The parse errors as [`Diagnostic`]s.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.errors.iter().cloned().map(Diagnostic::from).collect()
+    }
+
+    pub fn errors(&self) -> &[parser::ParseError] {
+        &self.errors
+    }
+
     pub fn syntax(&self) -> SyntaxNode {
         SyntaxNode::new_root(self.green_node.clone())
     }
 }
 
+/// Lists the tokens of `input`, one per line, in the same form as the leaves of
+/// [`Parse::debug_tree`].
+pub fn debug_tokens(input: &str) -> String {
+    let mut s = String::new();
+    for token in lexer::Lexer::new(input) {
+        let kind = SyntaxKind::from(token.kind);
+        let _ = writeln!(s, "{kind:?}@{:?} {:?}", token.range, token.text);
+    }
+    s
+}
+
 #[cfg(test)]
 mod tests {
     use expect_test::{expect, Expect};
 
-    use crate::parser::parse;
+    use crate::{debug_tokens, parser::parse};
 
     #[allow(clippy::needless_pass_by_value)]
     pub fn check(input: &str, expected_tree: Expect) {
@@ -49,6 +80,22 @@ mod tests {
         expected_tree.assert_eq(&parse.debug_tree());
     }
 
+    /// A small xorshift generator for randomized tests, so that failures reproduce.
+    pub struct Rng(pub u64);
+
+    impl Rng {
+        fn next(&mut self) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            usize::try_from(self.0 % 1_000_000).unwrap()
+        }
+
+        pub fn below(&mut self, n: usize) -> usize {
+            self.next() % n
+        }
+    }
+
     #[test]
     fn parse_nothing() {
         check("", expect![[r"Root@0..0"]]);
@@ -64,6 +111,59 @@ Root@0..3
         );
     }
 
+    #[test]
+    fn list_tokens() {
+        expect![[r##"
+            LetKw@0..3 "let"
+            Whitespace@3..4 " "
+            Ident@4..5 "a"
+            Whitespace@5..6 " "
+            Equals@6..7 "="
+            Whitespace@7..8 " "
+            String@8..11 "\"b\""
+            Whitespace@11..12 " "
+            Comment@12..15 "# c"
+            Newline@15..16 "\n"
+            Number@16..17 "1"
+        "##]]
+        .assert_eq(&debug_tokens("let a = \"b\" # c\n1"));
+    }
+
+    #[test]
+    fn parse_crlf_and_tabs() {
+        check(
+            "fn f() {\r\n\tlet a = 1\r\n\ta\r\n}\r\n",
+            expect![[r#"
+                Root@0..29
+                  FnDef@0..29
+                    FnKw@0..2 "fn"
+                    Whitespace@2..3 " "
+                    Ident@3..4 "f"
+                    ParamList@4..7
+                      LParen@4..5 "("
+                      RParen@5..6 ")"
+                      Whitespace@6..7 " "
+                    BlockExpr@7..29
+                      LBrace@7..8 "{"
+                      Newline@8..11 "\r\n\t"
+                      VariableDef@11..23
+                        LetKw@11..14 "let"
+                        Whitespace@14..15 " "
+                        Ident@15..16 "a"
+                        Whitespace@16..17 " "
+                        Equals@17..18 "="
+                        Whitespace@18..19 " "
+                        Literal@19..23
+                          Number@19..20 "1"
+                          Newline@20..23 "\r\n\t"
+                      VariableRef@23..26
+                        Ident@23..24 "a"
+                        Newline@24..26 "\r\n"
+                      RBrace@26..27 "}"
+                      Newline@27..29 "\r\n""#]],
+        );
+    }
+
     #[test]
     fn parse_comment() {
         check(
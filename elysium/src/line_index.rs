@@ -0,0 +1,285 @@
+//! Conversion between byte offsets and line/column positions.
+
+use rowan::{TextRange, TextSize};
+use std::collections::HashMap;
+
+/// A zero-based line and column, with the column counted in UTF-8 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LineCol {
+    pub line: u32,
+    pub col: u32,
+}
+
+/// A zero-based line and column, with the column counted in UTF-16 code units as used by
+/// editors speaking the language server protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct WideLineCol {
+    pub line: u32,
+    pub col: u32,
+}
+
+/// A character taking more than one byte, relative to the start of its line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct WideChar {
+    start: TextSize,
+    end: TextSize,
+    len_utf16: u32,
+}
+
+impl WideChar {
+    fn len_utf8(self) -> u32 {
+        u32::from(self.end - self.start)
+    }
+}
+
+/// Line boundaries of a text, for converting between [`TextSize`] offsets, [`LineCol`]s and
+/// [`WideLineCol`]s.
+///
+/// Lines end at `\n` or `\r\n`; the terminator belongs to the line it ends.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineIndex {
+    line_starts: Vec<TextSize>,
+    /// Where each line's content ends, before its terminator.
+    line_ends: Vec<TextSize>,
+    wide_chars: HashMap<u32, Vec<WideChar>>,
+    len: TextSize,
+}
+
+impl LineIndex {
+    /// # Panics
+    ///
+    /// Panics if `text` is longer than `u32::MAX` bytes, like the rest of the crate.
+    pub fn new(text: &str) -> Self {
+        let mut line_starts = vec![TextSize::from(0)];
+        let mut line_ends = Vec::new();
+        let mut wide_chars: HashMap<u32, Vec<WideChar>> = HashMap::new();
+
+        let mut prev = None;
+        for (idx, c) in text.char_indices() {
+            let offset = TextSize::try_from(idx).unwrap();
+
+            if c == '\n' {
+                let end = if prev == Some('\r') {
+                    offset - TextSize::of('\r')
+                } else {
+                    offset
+                };
+                line_ends.push(end);
+                line_starts.push(offset + TextSize::of('\n'));
+            } else if c.len_utf8() > 1 {
+                let line_start = *line_starts.last().unwrap();
+                let line = u32::try_from(line_starts.len() - 1).unwrap();
+                wide_chars.entry(line).or_default().push(WideChar {
+                    start: offset - line_start,
+                    end: offset - line_start + TextSize::of(c),
+                    len_utf16: u32::try_from(c.len_utf16()).unwrap(),
+                });
+            }
+
+            prev = Some(c);
+        }
+
+        let len = TextSize::of(text);
+        line_ends.push(len);
+
+        Self {
+            line_starts,
+            line_ends,
+            wide_chars,
+            len,
+        }
+    }
+
+    /// Offsets past the end of the text are clamped to it, and offsets inside a `\r\n` to the
+    /// end of the line's content.
+    ///
+    /// # Panics
+    ///
+    /// Never panics for an index built by [`LineIndex::new`], which caps the line count.
+    pub fn line_col(&self, offset: TextSize) -> LineCol {
+        let offset = offset.min(self.len);
+        let line = self.line_starts.partition_point(|&start| start <= offset) - 1;
+        let offset = offset.min(self.line_ends[line]);
+
+        LineCol {
+            line: u32::try_from(line).unwrap(),
+            col: u32::from(offset - self.line_starts[line]),
+        }
+    }
+
+    /// The offset of `line_col`, or `None` if the line does not exist. Columns past the end of
+    /// the line are clamped to it.
+    pub fn offset(&self, line_col: LineCol) -> Option<TextSize> {
+        let line = usize::try_from(line_col.line).ok()?;
+        let start = *self.line_starts.get(line)?;
+
+        Some((start + TextSize::from(line_col.col)).min(self.line_ends[line]))
+    }
+
+    pub fn to_wide(&self, line_col: LineCol) -> WideLineCol {
+        let mut col = line_col.col;
+
+        for c in self.wide_chars(line_col.line) {
+            if u32::from(c.end) > line_col.col {
+                break;
+            }
+            col -= c.len_utf8() - c.len_utf16;
+        }
+
+        WideLineCol {
+            line: line_col.line,
+            col,
+        }
+    }
+
+    pub fn to_utf8(&self, line_col: WideLineCol) -> LineCol {
+        let mut col = line_col.col;
+
+        for c in self.wide_chars(line_col.line) {
+            if u32::from(c.start) >= col {
+                break;
+            }
+            col += c.len_utf8() - c.len_utf16;
+        }
+
+        LineCol {
+            line: line_col.line,
+            col,
+        }
+    }
+
+    /// The range of `line`, including its terminator.
+    pub fn line_range(&self, line: u32) -> Option<TextRange> {
+        let line = usize::try_from(line).ok()?;
+        let start = *self.line_starts.get(line)?;
+        let end = self.line_starts.get(line + 1).copied().unwrap_or(self.len);
+
+        Some(TextRange::new(start, end))
+    }
+
+    /// # Panics
+    ///
+    /// Never panics for an index built by [`LineIndex::new`], which caps the line count.
+    pub fn line_count(&self) -> u32 {
+        u32::try_from(self.line_starts.len()).unwrap()
+    }
+
+    fn wide_chars(&self, line: u32) -> &[WideChar] {
+        self.wide_chars.get(&line).map_or(&[], Vec::as_slice)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line_col(line: u32, col: u32) -> LineCol {
+        LineCol { line, col }
+    }
+
+    fn wide(line: u32, col: u32) -> WideLineCol {
+        WideLineCol { line, col }
+    }
+
+    #[test]
+    fn single_line() {
+        let index = LineIndex::new("let a = 1");
+
+        assert_eq!(index.line_col(0.into()), line_col(0, 0));
+        assert_eq!(index.line_col(4.into()), line_col(0, 4));
+        assert_eq!(index.line_col(9.into()), line_col(0, 9));
+        assert_eq!(index.line_count(), 1);
+    }
+
+    #[test]
+    fn multiple_lines() {
+        let index = LineIndex::new("a\nbc\n\nd");
+
+        assert_eq!(index.line_col(1.into()), line_col(0, 1));
+        assert_eq!(index.line_col(2.into()), line_col(1, 0));
+        assert_eq!(index.line_col(4.into()), line_col(1, 2));
+        assert_eq!(index.line_col(5.into()), line_col(2, 0));
+        assert_eq!(index.line_col(6.into()), line_col(3, 0));
+        assert_eq!(index.line_count(), 4);
+    }
+
+    #[test]
+    fn offsets_past_the_end_are_clamped() {
+        let index = LineIndex::new("a\nb");
+
+        assert_eq!(index.line_col(100.into()), line_col(1, 1));
+    }
+
+    #[test]
+    fn line_ranges_include_terminator() {
+        let index = LineIndex::new("a\nbc");
+
+        assert_eq!(
+            index.line_range(0),
+            Some(TextRange::new(0.into(), 2.into()))
+        );
+        assert_eq!(
+            index.line_range(1),
+            Some(TextRange::new(2.into(), 4.into()))
+        );
+        assert_eq!(index.line_range(2), None);
+    }
+
+    #[test]
+    fn crlf_terminates_a_single_line() {
+        let index = LineIndex::new("ab\r\ncd\r\n");
+
+        assert_eq!(index.line_col(2.into()), line_col(0, 2));
+        assert_eq!(index.line_col(3.into()), line_col(0, 2));
+        assert_eq!(index.line_col(4.into()), line_col(1, 0));
+        assert_eq!(index.line_col(8.into()), line_col(2, 0));
+        assert_eq!(index.line_count(), 3);
+    }
+
+    #[test]
+    fn offset_is_inverse_of_line_col() {
+        let text = "let a = 1\r\nlet é = a\n\nfoo(é)";
+        let index = LineIndex::new(text);
+
+        for (idx, _) in text.char_indices().filter(|(_, c)| *c != '\n') {
+            let offset = TextSize::try_from(idx).unwrap();
+            assert_eq!(index.offset(index.line_col(offset)), Some(offset));
+        }
+    }
+
+    #[test]
+    fn offset_clamps_columns_to_the_line() {
+        let index = LineIndex::new("ab\r\ncd");
+
+        assert_eq!(index.offset(line_col(0, 10)), Some(2.into()));
+        assert_eq!(index.offset(line_col(1, 10)), Some(6.into()));
+        assert_eq!(index.offset(line_col(2, 0)), None);
+    }
+
+    #[test]
+    fn utf16_columns() {
+        // 'é' is two bytes in UTF-8 and one unit in UTF-16, '𝕏' four bytes and two units.
+        let index = LineIndex::new("a\né𝕏b");
+
+        assert_eq!(index.to_wide(line_col(0, 1)), wide(0, 1));
+        assert_eq!(index.to_wide(line_col(1, 0)), wide(1, 0));
+        assert_eq!(index.to_wide(line_col(1, 2)), wide(1, 1));
+        assert_eq!(index.to_wide(line_col(1, 6)), wide(1, 3));
+        assert_eq!(index.to_wide(line_col(1, 7)), wide(1, 4));
+
+        assert_eq!(index.to_utf8(wide(1, 1)), line_col(1, 2));
+        assert_eq!(index.to_utf8(wide(1, 3)), line_col(1, 6));
+        assert_eq!(index.to_utf8(wide(1, 4)), line_col(1, 7));
+    }
+
+    #[test]
+    fn utf16_round_trip() {
+        let text = "fn f(é) { é + 1 }\r\n𝕏𝕏 = 2";
+        let index = LineIndex::new(text);
+
+        for (idx, _) in text.char_indices() {
+            let line_col = index.line_col(TextSize::try_from(idx).unwrap());
+            assert_eq!(index.to_utf8(index.to_wide(line_col)), line_col);
+        }
+    }
+}
@@ -1,41 +1,337 @@
-//! Little CLI to drive our core parsing/language logid.
-use elysium::{ast, hir, parse, Root, Stmt};
-use std::io::{self, Write};
+//! Command-line driver: runs, checks, dumps and formats files, or starts a REPL.
+use elysium::{
+    ast,
+    diagnostic::Renderer,
+    eval::{Value, EVAL_STACK_SIZE},
+    fmt, hir, Diagnostic, Session,
+};
+use std::{
+    env, fs,
+    io::{self, IsTerminal, Read, Write},
+    panic,
+    process::ExitCode,
+    thread,
+};
 
-fn main() -> io::Result<()> {
+const USAGE: &str = "\
+usage: elysium <command> [<args>]
+
+commands:
+    run <file>...                    evaluate files in order, printing the value of each
+                                     top-level expression
+    check <file>...                  report errors in files without running them
+    dump --stage <stage> <file>...   print the tokens, cst, ast or hir of files
+    fmt [--check] <file>...          format files in place, or with --check only list the
+                                     ones that need it
+    repl                             evaluate lines as they are typed
+
+A <file> of `-` is read from standard input.";
+
+const HELP: &str = "\
+:env    show variable bindings
+:reset  forget all definitions
+:tree   toggle printing the syntax tree
+:hir    toggle printing lowered HIR
+:help   show this message
+:quit   exit";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+
+    let result = thread::Builder::new()
+        .stack_size(EVAL_STACK_SIZE)
+        .spawn(move || execute(&args))
+        .map_err(Error::from)
+        .and_then(|thread| {
+            thread
+                .join()
+                .unwrap_or_else(|panic| panic::resume_unwind(panic))
+        });
+
+    result.unwrap_or_else(|error| {
+        match error {
+            Error::Usage(message) => eprintln!("error: {message}\n\n{USAGE}"),
+            Error::Io(error) => eprintln!("error: {error}"),
+        }
+        ExitCode::FAILURE
+    })
+}
+
+/// Runs the command named by the first of `args` with the rest.
+fn execute(args: &[String]) -> Result<ExitCode, Error> {
+    match args.split_first() {
+        Some((command, args)) => match command.as_str() {
+            "run" => run(args),
+            "check" => check(args),
+            "dump" => dump(args),
+            "fmt" => format_files(args),
+            "repl" if args.is_empty() => repl().map(|()| ExitCode::SUCCESS).map_err(Error::from),
+            "repl" => Err(Error::Usage("`repl` takes no arguments".to_owned())),
+            "help" | "--help" | "-h" => {
+                println!("{USAGE}");
+                Ok(ExitCode::SUCCESS)
+            }
+            _ => Err(Error::Usage(format!("unknown command `{command}`"))),
+        },
+        None => Err(Error::Usage("missing command".to_owned())),
+    }
+}
+
+/// Why a command could not run.
+enum Error {
+    Usage(String),
+    Io(io::Error),
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+/// A file given on the command line, or standard input for `-`.
+struct Source<'a> {
+    name: &'a str,
+    text: String,
+}
+
+/// Reads the files named by `args`, of which there must be at least one, with standard input
+/// named at most once. Options should already have been taken out.
+fn read_sources<'a>(args: &[&'a str]) -> Result<Vec<Source<'a>>, Error> {
+    if let Some(option) = args.iter().find(|arg| arg.starts_with("--")) {
+        return Err(Error::Usage(format!("unknown option `{option}`")));
+    }
+    if args.is_empty() {
+        return Err(Error::Usage("no files given".to_owned()));
+    }
+    if args.iter().filter(|&&arg| arg == "-").count() > 1 {
+        return Err(Error::Usage("`-` given more than once".to_owned()));
+    }
+
+    let mut sources = Vec::new();
+    for &path in args {
+        let source = if path == "-" {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            Source {
+                name: "<stdin>",
+                text,
+            }
+        } else {
+            let text = fs::read_to_string(path).map_err(|error| {
+                io::Error::new(error.kind(), format!("cannot read `{path}`: {error}"))
+            })?;
+            Source { name: path, text }
+        };
+        sources.push(source);
+    }
+
+    Ok(sources)
+}
+
+/// Prints `diagnostics` about `source` to standard error, returning whether any is an error.
+fn report(source: &Source, diagnostics: &[Diagnostic]) -> bool {
+    let renderer = Renderer::new(source.name, &source.text).with_colour(io::stderr().is_terminal());
+    for diagnostic in diagnostics {
+        eprintln!("{}", renderer.render(diagnostic));
+    }
+    diagnostics.iter().any(Diagnostic::is_error)
+}
+
+/// Evaluates the files one after another in a single session, so that later files can use
+/// the definitions of earlier ones, printing the values of expression statements other than
+/// `()`. Stops at the first file with an error.
+fn run(args: &[String]) -> Result<ExitCode, Error> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let sources = read_sources(&args)?;
+
+    let mut session = Session::default();
+    for source in &sources {
+        let evaluation = session.eval(&source.text);
+        for value in evaluation
+            .values
+            .iter()
+            .filter(|value| **value != Value::Unit)
+        {
+            println!("{value}");
+        }
+        if report(source, &evaluation.diagnostics) {
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Checks the files in order as [`run`] would run them, without running anything.
+fn check(args: &[String]) -> Result<ExitCode, Error> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let sources = read_sources(&args)?;
+
+    let mut session = Session::default();
+    for source in &sources {
+        let evaluation = session.check(&source.text);
+        if report(source, &evaluation.diagnostics) {
+            return Ok(ExitCode::FAILURE);
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Prints one stage of each file's processing, under a heading when there are several.
+fn dump(args: &[String]) -> Result<ExitCode, Error> {
+    let mut stage = None;
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if arg == "--stage" {
+            stage = args.next().map(String::as_str);
+        } else {
+            paths.push(arg.as_str());
+        }
+    }
+
+    let stage = match stage {
+        Some(stage @ ("tokens" | "cst" | "ast" | "hir")) => stage,
+        Some(stage) => return Err(Error::Usage(format!("unknown stage `{stage}`"))),
+        None => return Err(Error::Usage("missing --stage".to_owned())),
+    };
+    let sources = read_sources(&paths)?;
+
+    for (i, source) in sources.iter().enumerate() {
+        if sources.len() > 1 {
+            if i > 0 {
+                println!();
+            }
+            println!("==> {} <==", source.name);
+        }
+
+        let parse = elysium::parse(&source.text);
+        let root = ast::Root::cast(parse.syntax()).expect("the parser always produces a root");
+        match stage {
+            "tokens" => print!("{}", elysium::debug_tokens(&source.text)),
+            "cst" => println!("{}", parse.debug_tree()),
+            "ast" => print!("{}", root.debug_tree()),
+            _ => {
+                let (mut db, stmts) = hir::lower(&root);
+                hir::resolve(&mut db, &stmts);
+                print!("{}", db.debug_tree(&stmts));
+            }
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Formats each file in place, or with `--check` lists the files that are not formatted
+/// instead. Standard input is formatted to standard output. Fails if any file is not
+/// formatted or does not parse.
+fn format_files(args: &[String]) -> Result<ExitCode, Error> {
+    let check = args.iter().any(|arg| arg == "--check");
+    let paths: Vec<&str> = args
+        .iter()
+        .map(String::as_str)
+        .filter(|arg| *arg != "--check")
+        .collect();
+    let sources = read_sources(&paths)?;
+
+    let mut code = ExitCode::SUCCESS;
+    for (source, path) in sources.iter().zip(paths) {
+        match fmt::format(&source.text) {
+            Ok(formatted) if check => {
+                if formatted != source.text {
+                    println!("{} is not formatted", source.name);
+                    code = ExitCode::FAILURE;
+                }
+            }
+            Ok(formatted) if path == "-" => print!("{formatted}"),
+            Ok(formatted) => {
+                if formatted != source.text {
+                    fs::write(path, formatted)?;
+                }
+            }
+            Err(diagnostics) => {
+                report(source, &diagnostics);
+                code = ExitCode::FAILURE;
+            }
+        }
+    }
+
+    Ok(code)
+}
+
+fn repl() -> io::Result<()> {
     let stdin = io::stdin();
     let mut stdout = io::stdout();
 
+    let mut session = Session::default();
+    let mut show_tree = false;
+    let mut show_hir = false;
+    let colour = stdout.is_terminal();
+
     let mut input = String::new();
 
     loop {
         write!(stdout, "==> ")?;
         stdout.flush()?;
 
-        stdin.read_line(&mut input)?;
+        input.clear();
+        if stdin.read_line(&mut input)? == 0 {
+            return Ok(());
+        }
 
-        let parse = parse(&input);
-        println!("{}", parse.debug_tree());
+        match input.trim() {
+            ":env" => {
+                for (name, value, ty) in session.bindings() {
+                    println!("{name} : {} = {value}", ty.display_named());
+                }
+                continue;
+            }
+            ":reset" => {
+                session.reset();
+                continue;
+            }
+            ":tree" => {
+                show_tree = !show_tree;
+                println!("syntax tree {}", if show_tree { "on" } else { "off" });
+                continue;
+            }
+            ":hir" => {
+                show_hir = !show_hir;
+                println!("hir {}", if show_hir { "on" } else { "off" });
+                continue;
+            }
+            ":help" => {
+                println!("{HELP}");
+                continue;
+            }
+            ":quit" => return Ok(()),
+            command if command.starts_with(':') => {
+                println!("unknown command `{command}`, try :help");
+                continue;
+            }
+            _ => {}
+        }
 
-        let syntax = parse.syntax();
+        let evaluation = session.eval(&input);
 
-        for error in ast::validation::validate(&syntax) {
-            println!("{error}",);
+        if show_tree {
+            println!("{}", evaluation.parse.debug_tree());
         }
 
-        let root = Root::cast(syntax).unwrap();
-
-        dbg!(root
-            .stmts()
-            .filter_map(|stmt| if let Stmt::VariableDef(var_def) = stmt {
-                Some(var_def.value())
-            } else {
-                None
-            })
-            .collect::<Vec<_>>());
+        if show_hir {
+            let stmts = &session.stmts()[evaluation.stmts.clone()];
+            print!("{}", session.database().debug_tree(stmts));
+        }
 
-        dbg!(hir::lower(root));
+        for (value, ty) in evaluation.values.iter().zip(&evaluation.types) {
+            println!("{value} : {}", ty.display_named());
+        }
 
-        input.clear();
+        let renderer = Renderer::new("<input>", &input).with_colour(colour);
+        for diagnostic in &evaluation.diagnostics {
+            println!("{}", renderer.render(diagnostic));
+        }
     }
 }
@@ -1,19 +1,37 @@
-use super::{Parser, SyntaxKind};
+use super::{stmt, Parser, SyntaxKind};
 use crate::{lexer::TokenKind, parser::CompletedMarker};
 
 pub(super) fn expr(p: &mut Parser) -> Option<CompletedMarker> {
     expr_binding_power(p, 0)
 }
 
-fn lhs(p: &mut Parser) -> Option<CompletedMarker> {
-    let cm = if p.at(TokenKind::Number) {
+/// Parses the leftmost operand of an expression, which is a block only if `allow_block` is set.
+fn lhs(p: &mut Parser, allow_block: bool) -> Option<CompletedMarker> {
+    let cm = if p.at(TokenKind::Number)
+        || p.at(TokenKind::Float)
+        || p.at(TokenKind::String)
+        || p.at(TokenKind::TrueKw)
+        || p.at(TokenKind::FalseKw)
+    {
         literal(p)
     } else if p.at(TokenKind::Ident) {
         variable_ref(p)
-    } else if p.at(TokenKind::Minus) {
+    } else if p.at(TokenKind::Minus) || p.at(TokenKind::Bang) {
         prefix_expr(p)
     } else if p.at(TokenKind::LParen) {
         paren_expr(p)
+    } else if allow_block && p.at(TokenKind::LBrace) {
+        block_expr(p)
+    } else if p.at(TokenKind::IfKw) {
+        if_expr(p)
+    } else if p.at(TokenKind::WhileKw) {
+        while_expr(p)
+    } else if p.at(TokenKind::LoopKw) {
+        loop_expr(p)
+    } else if p.at(TokenKind::BreakKw) {
+        break_expr(p)
+    } else if p.at(TokenKind::ContinueKw) {
+        continue_expr(p)
     } else {
         p.error();
         return None;
@@ -27,16 +45,143 @@ fn paren_expr(p: &mut Parser<'_, '_>) -> CompletedMarker {
 
     let m = p.start();
     p.bump();
-    expr_binding_power(p, 0);
-    p.expect(TokenKind::RParen);
+    p.with_newlines(false, |p| {
+        expr_binding_power(p, 0);
+        p.expect(TokenKind::RParen);
+    });
 
     m.complete(p, SyntaxKind::ParenExpr)
 }
 
+pub(super) fn block_expr(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::LBrace));
+
+    let m = p.start();
+    p.bump();
+
+    p.with_newlines(true, |p| {
+        while !p.at(TokenKind::RBrace) && !p.at_end() {
+            stmt::stmt(p);
+            stmt::stmt_end(p, Some(TokenKind::RBrace));
+        }
+
+        p.expect(TokenKind::RBrace);
+    });
+    m.complete(p, SyntaxKind::BlockExpr)
+}
+
+fn if_expr(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::IfKw));
+    let m = p.start();
+    p.bump();
+
+    condition(p);
+
+    if p.at(TokenKind::LBrace) {
+        block_expr(p);
+    } else {
+        p.error();
+    }
+
+    if p.at(TokenKind::ElseKw) {
+        p.bump();
+
+        if p.at(TokenKind::IfKw) {
+            if_expr(p);
+        } else if p.at(TokenKind::LBrace) {
+            block_expr(p);
+        } else {
+            p.error();
+        }
+    }
+
+    m.complete(p, SyntaxKind::IfExpr)
+}
+
+fn while_expr(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::WhileKw));
+    let m = p.start();
+    p.bump();
+
+    condition(p);
+
+    if p.at(TokenKind::LBrace) {
+        block_expr(p);
+    } else {
+        p.error();
+    }
+
+    m.complete(p, SyntaxKind::WhileExpr)
+}
+
+fn loop_expr(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::LoopKw));
+    let m = p.start();
+    p.bump();
+
+    if p.at(TokenKind::LBrace) {
+        block_expr(p);
+    } else {
+        p.error();
+    }
+
+    m.complete(p, SyntaxKind::LoopExpr)
+}
+
+/// Parses a `break`, taking a value only if one starts on the same line.
+fn break_expr(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::BreakKw));
+    let m = p.start();
+    p.bump();
+
+    if !p.newline_before() && p.at_set(&EXPR_START) {
+        expr(p);
+    }
+
+    m.complete(p, SyntaxKind::BreakExpr)
+}
+
+fn continue_expr(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::ContinueKw));
+    let m = p.start();
+    p.bump();
+    m.complete(p, SyntaxKind::ContinueExpr)
+}
+
+/// Tokens that [`lhs`] can start an expression with.
+const EXPR_START: [TokenKind; 15] = [
+    TokenKind::Number,
+    TokenKind::Float,
+    TokenKind::String,
+    TokenKind::TrueKw,
+    TokenKind::FalseKw,
+    TokenKind::Ident,
+    TokenKind::Minus,
+    TokenKind::Bang,
+    TokenKind::LParen,
+    TokenKind::LBrace,
+    TokenKind::IfKw,
+    TokenKind::WhileKw,
+    TokenKind::LoopKw,
+    TokenKind::BreakKw,
+    TokenKind::ContinueKw,
+];
+
+/// Parses the condition of an `if` or `while`. A `{` straight away opens the body rather than a block
+/// expression, leaving the condition missing.
+fn condition(p: &mut Parser) -> Option<CompletedMarker> {
+    let lhs = lhs(p, false)?;
+    Some(postfix_and_infix(p, lhs, 0))
+}
+
 fn prefix_expr(p: &mut Parser<'_, '_>) -> CompletedMarker {
-    assert!(p.at(TokenKind::Minus));
     let m = p.start();
-    let op = UnaryOp::Neg;
+    let op = if p.at(TokenKind::Minus) {
+        UnaryOp::Neg
+    } else {
+        assert!(p.at(TokenKind::Bang));
+        UnaryOp::Not
+    };
     let ((), right_binding_power) = op.binding_power();
     p.bump();
     expr_binding_power(p, right_binding_power);
@@ -51,16 +196,36 @@ fn variable_ref(p: &mut Parser<'_, '_>) -> CompletedMarker {
 }
 
 fn literal(p: &mut Parser<'_, '_>) -> CompletedMarker {
-    assert!(p.at(TokenKind::Number));
+    assert!(
+        p.at(TokenKind::Number)
+            || p.at(TokenKind::Float)
+            || p.at(TokenKind::String)
+            || p.at(TokenKind::TrueKw)
+            || p.at(TokenKind::FalseKw)
+    );
     let m = p.start();
     p.bump();
     m.complete(p, SyntaxKind::Literal)
 }
 
 fn expr_binding_power(p: &mut Parser, minimum_binding_power: u8) -> Option<CompletedMarker> {
-    let mut lhs = lhs(p)?;
+    let lhs = lhs(p, true)?;
+    Some(postfix_and_infix(p, lhs, minimum_binding_power))
+}
 
+/// Parses the operators following `lhs` that bind at least as tightly as
+/// `minimum_binding_power`.
+fn postfix_and_infix(
+    p: &mut Parser,
+    mut lhs: CompletedMarker,
+    minimum_binding_power: u8,
+) -> CompletedMarker {
     loop {
+        // An operator on the next line starts a new statement instead.
+        if p.newline_before() {
+            break;
+        }
+
         let op = if p.at(TokenKind::Plus) {
             BinaryOp::Add
         } else if p.at(TokenKind::Minus) {
@@ -69,6 +234,40 @@ fn expr_binding_power(p: &mut Parser, minimum_binding_power: u8) -> Option<Compl
             BinaryOp::Mul
         } else if p.at(TokenKind::Slash) {
             BinaryOp::Div
+        } else if p.at(TokenKind::EqEq) {
+            BinaryOp::Eq
+        } else if p.at(TokenKind::BangEq) {
+            BinaryOp::Ne
+        } else if p.at(TokenKind::Less) {
+            BinaryOp::Lt
+        } else if p.at(TokenKind::LessEq) {
+            BinaryOp::Le
+        } else if p.at(TokenKind::Greater) {
+            BinaryOp::Gt
+        } else if p.at(TokenKind::GreaterEq) {
+            BinaryOp::Ge
+        } else if p.at(TokenKind::AmpAmp) {
+            BinaryOp::And
+        } else if p.at(TokenKind::PipePipe) {
+            BinaryOp::Or
+        } else if p.at(TokenKind::Equals)
+            || p.at(TokenKind::PlusEq)
+            || p.at(TokenKind::MinusEq)
+            || p.at(TokenKind::StarEq)
+            || p.at(TokenKind::SlashEq)
+        {
+            BinaryOp::Assign
+        } else if p.at(TokenKind::LParen) {
+            let (left_binding_power, ()) = PostfixOp::Call.binding_power();
+
+            if left_binding_power < minimum_binding_power {
+                break;
+            }
+
+            let m = lhs.precede(p);
+            arg_list(p);
+            lhs = m.complete(p, SyntaxKind::CallExpr);
+            continue;
         } else {
             break;
         };
@@ -85,13 +284,39 @@ fn expr_binding_power(p: &mut Parser, minimum_binding_power: u8) -> Option<Compl
 
         let parsed_rhs = expr_binding_power(p, right_binding_power).is_some();
 
-        lhs = m.complete(p, SyntaxKind::InfixExpr);
+        let kind = if matches!(op, BinaryOp::Assign) {
+            SyntaxKind::AssignExpr
+        } else {
+            SyntaxKind::InfixExpr
+        };
+        lhs = m.complete(p, kind);
 
         if !parsed_rhs {
             break;
         }
     }
-    Some(lhs)
+    lhs
+}
+
+fn arg_list(p: &mut Parser) {
+    assert!(p.at(TokenKind::LParen));
+    let m = p.start();
+    p.bump();
+
+    p.with_newlines(false, |p| {
+        while !p.at(TokenKind::RParen) && !p.at_end() {
+            if expr(p).is_none() {
+                break;
+            }
+
+            if !p.at(TokenKind::RParen) {
+                p.expect(TokenKind::Comma);
+            }
+        }
+
+        p.expect(TokenKind::RParen);
+    });
+    m.complete(p, SyntaxKind::ArgList);
 }
 
 enum BinaryOp {
@@ -99,25 +324,53 @@ enum BinaryOp {
     Sub,
     Mul,
     Div,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    And,
+    Or,
+    /// `=` or any of the compound assignment operators.
+    Assign,
 }
 
 impl BinaryOp {
     const fn binding_power(&self) -> (u8, u8) {
         match self {
-            Self::Add | Self::Sub => (1, 2),
-            Self::Mul | Self::Div => (3, 4),
+            // Right-associative, so `a = b = c` assigns `b = c` to `a`.
+            Self::Assign => (2, 1),
+            Self::Or => (3, 4),
+            Self::And => (5, 6),
+            Self::Eq | Self::Ne | Self::Lt | Self::Le | Self::Gt | Self::Ge => (7, 8),
+            Self::Add | Self::Sub => (9, 10),
+            Self::Mul | Self::Div => (11, 12),
         }
     }
 }
 
 enum UnaryOp {
     Neg,
+    Not,
 }
 
 impl UnaryOp {
     const fn binding_power(&self) -> ((), u8) {
         match self {
-            Self::Neg => ((), 5),
+            Self::Neg | Self::Not => ((), 13),
+        }
+    }
+}
+
+enum PostfixOp {
+    Call,
+}
+
+impl PostfixOp {
+    const fn binding_power(&self) -> (u8, ()) {
+        match self {
+            Self::Call => (15, ()),
         }
     }
 }
@@ -138,6 +391,693 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_bool_literals() {
+        check(
+            "true; false",
+            expect![[r#"
+                Root@0..11
+                  Literal@0..4
+                    TrueKw@0..4 "true"
+                  Semicolon@4..5 ";"
+                  Whitespace@5..6 " "
+                  Literal@6..11
+                    FalseKw@6..11 "false""#]],
+        );
+    }
+
+    #[test]
+    fn comparison_binds_looser_than_arithmetic() {
+        check(
+            "1 + 2 < 3 * 4",
+            expect![[r#"
+                Root@0..13
+                  InfixExpr@0..13
+                    InfixExpr@0..6
+                      Literal@0..2
+                        Number@0..1 "1"
+                        Whitespace@1..2 " "
+                      Plus@2..3 "+"
+                      Whitespace@3..4 " "
+                      Literal@4..6
+                        Number@4..5 "2"
+                        Whitespace@5..6 " "
+                    Less@6..7 "<"
+                    Whitespace@7..8 " "
+                    InfixExpr@8..13
+                      Literal@8..10
+                        Number@8..9 "3"
+                        Whitespace@9..10 " "
+                      Star@10..11 "*"
+                      Whitespace@11..12 " "
+                      Literal@12..13
+                        Number@12..13 "4""#]],
+        );
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        check(
+            "a || b && c == d",
+            expect![[r#"
+                Root@0..16
+                  InfixExpr@0..16
+                    VariableRef@0..2
+                      Ident@0..1 "a"
+                      Whitespace@1..2 " "
+                    PipePipe@2..4 "||"
+                    Whitespace@4..5 " "
+                    InfixExpr@5..16
+                      VariableRef@5..7
+                        Ident@5..6 "b"
+                        Whitespace@6..7 " "
+                      AmpAmp@7..9 "&&"
+                      Whitespace@9..10 " "
+                      InfixExpr@10..16
+                        VariableRef@10..12
+                          Ident@10..11 "c"
+                          Whitespace@11..12 " "
+                        EqEq@12..14 "=="
+                        Whitespace@14..15 " "
+                        VariableRef@15..16
+                          Ident@15..16 "d""#]],
+        );
+    }
+
+    #[test]
+    fn parse_not() {
+        check(
+            "!a && b",
+            expect![[r#"
+                Root@0..7
+                  InfixExpr@0..7
+                    PrefixExpr@0..3
+                      Bang@0..1 "!"
+                      VariableRef@1..3
+                        Ident@1..2 "a"
+                        Whitespace@2..3 " "
+                    AmpAmp@3..5 "&&"
+                    Whitespace@5..6 " "
+                    VariableRef@6..7
+                      Ident@6..7 "b""#]],
+        );
+    }
+
+    #[test]
+    fn parse_if() {
+        check(
+            "if a { 1 }",
+            expect![[r#"
+                Root@0..10
+                  IfExpr@0..10
+                    IfKw@0..2 "if"
+                    Whitespace@2..3 " "
+                    VariableRef@3..5
+                      Ident@3..4 "a"
+                      Whitespace@4..5 " "
+                    BlockExpr@5..10
+                      LBrace@5..6 "{"
+                      Whitespace@6..7 " "
+                      Literal@7..9
+                        Number@7..8 "1"
+                        Whitespace@8..9 " "
+                      RBrace@9..10 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_if_else() {
+        check(
+            "if a { 1 } else { 2 }",
+            expect![[r#"
+                Root@0..21
+                  IfExpr@0..21
+                    IfKw@0..2 "if"
+                    Whitespace@2..3 " "
+                    VariableRef@3..5
+                      Ident@3..4 "a"
+                      Whitespace@4..5 " "
+                    BlockExpr@5..11
+                      LBrace@5..6 "{"
+                      Whitespace@6..7 " "
+                      Literal@7..9
+                        Number@7..8 "1"
+                        Whitespace@8..9 " "
+                      RBrace@9..10 "}"
+                      Whitespace@10..11 " "
+                    ElseKw@11..15 "else"
+                    Whitespace@15..16 " "
+                    BlockExpr@16..21
+                      LBrace@16..17 "{"
+                      Whitespace@17..18 " "
+                      Literal@18..20
+                        Number@18..19 "2"
+                        Whitespace@19..20 " "
+                      RBrace@20..21 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_else_if_chain() {
+        check(
+            "if a { 1 } else if b { 2 } else { 3 }",
+            expect![[r#"
+                Root@0..37
+                  IfExpr@0..37
+                    IfKw@0..2 "if"
+                    Whitespace@2..3 " "
+                    VariableRef@3..5
+                      Ident@3..4 "a"
+                      Whitespace@4..5 " "
+                    BlockExpr@5..11
+                      LBrace@5..6 "{"
+                      Whitespace@6..7 " "
+                      Literal@7..9
+                        Number@7..8 "1"
+                        Whitespace@8..9 " "
+                      RBrace@9..10 "}"
+                      Whitespace@10..11 " "
+                    ElseKw@11..15 "else"
+                    Whitespace@15..16 " "
+                    IfExpr@16..37
+                      IfKw@16..18 "if"
+                      Whitespace@18..19 " "
+                      VariableRef@19..21
+                        Ident@19..20 "b"
+                        Whitespace@20..21 " "
+                      BlockExpr@21..27
+                        LBrace@21..22 "{"
+                        Whitespace@22..23 " "
+                        Literal@23..25
+                          Number@23..24 "2"
+                          Whitespace@24..25 " "
+                        RBrace@25..26 "}"
+                        Whitespace@26..27 " "
+                      ElseKw@27..31 "else"
+                      Whitespace@31..32 " "
+                      BlockExpr@32..37
+                        LBrace@32..33 "{"
+                        Whitespace@33..34 " "
+                        Literal@34..36
+                          Number@34..35 "3"
+                          Whitespace@35..36 " "
+                        RBrace@36..37 "}""#]],
+        );
+    }
+
+    #[test]
+    fn else_on_next_line() {
+        check(
+            "if a { 1 }\nelse { 2 }",
+            expect![[r#"
+                Root@0..21
+                  IfExpr@0..21
+                    IfKw@0..2 "if"
+                    Whitespace@2..3 " "
+                    VariableRef@3..5
+                      Ident@3..4 "a"
+                      Whitespace@4..5 " "
+                    BlockExpr@5..11
+                      LBrace@5..6 "{"
+                      Whitespace@6..7 " "
+                      Literal@7..9
+                        Number@7..8 "1"
+                        Whitespace@8..9 " "
+                      RBrace@9..10 "}"
+                      Newline@10..11 "\n"
+                    ElseKw@11..15 "else"
+                    Whitespace@15..16 " "
+                    BlockExpr@16..21
+                      LBrace@16..17 "{"
+                      Whitespace@17..18 " "
+                      Literal@18..20
+                        Number@18..19 "2"
+                        Whitespace@19..20 " "
+                      RBrace@20..21 "}""#]],
+        );
+    }
+
+    #[test]
+    fn if_as_operand() {
+        check(
+            "1 + if a { 2 } else { 3 }",
+            expect![[r#"
+                Root@0..25
+                  InfixExpr@0..25
+                    Literal@0..2
+                      Number@0..1 "1"
+                      Whitespace@1..2 " "
+                    Plus@2..3 "+"
+                    Whitespace@3..4 " "
+                    IfExpr@4..25
+                      IfKw@4..6 "if"
+                      Whitespace@6..7 " "
+                      VariableRef@7..9
+                        Ident@7..8 "a"
+                        Whitespace@8..9 " "
+                      BlockExpr@9..15
+                        LBrace@9..10 "{"
+                        Whitespace@10..11 " "
+                        Literal@11..13
+                          Number@11..12 "2"
+                          Whitespace@12..13 " "
+                        RBrace@13..14 "}"
+                        Whitespace@14..15 " "
+                      ElseKw@15..19 "else"
+                      Whitespace@19..20 " "
+                      BlockExpr@20..25
+                        LBrace@20..21 "{"
+                        Whitespace@21..22 " "
+                        Literal@22..24
+                          Number@22..23 "3"
+                          Whitespace@23..24 " "
+                        RBrace@24..25 "}""#]],
+        );
+    }
+
+    #[test]
+    fn condition_can_contain_blocks() {
+        check(
+            "if a == { b } { 1 }",
+            expect![[r#"
+                Root@0..19
+                  IfExpr@0..19
+                    IfKw@0..2 "if"
+                    Whitespace@2..3 " "
+                    InfixExpr@3..14
+                      VariableRef@3..5
+                        Ident@3..4 "a"
+                        Whitespace@4..5 " "
+                      EqEq@5..7 "=="
+                      Whitespace@7..8 " "
+                      BlockExpr@8..14
+                        LBrace@8..9 "{"
+                        Whitespace@9..10 " "
+                        VariableRef@10..12
+                          Ident@10..11 "b"
+                          Whitespace@11..12 " "
+                        RBrace@12..13 "}"
+                        Whitespace@13..14 " "
+                    BlockExpr@14..19
+                      LBrace@14..15 "{"
+                      Whitespace@15..16 " "
+                      Literal@16..18
+                        Number@16..17 "1"
+                        Whitespace@17..18 " "
+                      RBrace@18..19 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_if_without_condition() {
+        check(
+            "if { 1 }\n2",
+            expect![[r#"
+                Root@0..10
+                  IfExpr@0..9
+                    IfKw@0..2 "if"
+                    Whitespace@2..3 " "
+                    BlockExpr@3..9
+                      LBrace@3..4 "{"
+                      Whitespace@4..5 " "
+                      Literal@5..7
+                        Number@5..6 "1"
+                        Whitespace@6..7 " "
+                      RBrace@7..8 "}"
+                      Newline@8..9 "\n"
+                  Literal@9..10
+                    Number@9..10 "2"
+                error at 3..4: expected number-literal, float-literal, string-literal, 'true', 'false', identifier, '-', '!', '(', 'if', 'while', 'loop', 'break' or 'continue', but found '{'"#]],
+        );
+    }
+
+    #[test]
+    fn parse_if_without_braces() {
+        check(
+            "if a 1\nlet b = 2",
+            expect![[r#"
+                Root@0..16
+                  IfExpr@0..7
+                    IfKw@0..2 "if"
+                    Whitespace@2..3 " "
+                    VariableRef@3..5
+                      Ident@3..4 "a"
+                      Whitespace@4..5 " "
+                    Error@5..7
+                      Number@5..6 "1"
+                      Newline@6..7 "\n"
+                  VariableDef@7..16
+                    LetKw@7..10 "let"
+                    Whitespace@10..11 " "
+                    Ident@11..12 "b"
+                    Whitespace@12..13 " "
+                    Equals@13..14 "="
+                    Whitespace@14..15 " "
+                    Literal@15..16
+                      Number@15..16 "2"
+                error at 5..6: expected '+', '-', '*', '/', '==', '!=', '<', '<=', '>', '>=', '&&', '||', '=', '+=', '-=', '*=', '/=', '(' or '{', but found number-literal"#]],
+        );
+    }
+
+    #[test]
+    fn parse_else_without_braces() {
+        check(
+            "if a { 1 } else 2",
+            expect![[r#"
+                Root@0..17
+                  IfExpr@0..17
+                    IfKw@0..2 "if"
+                    Whitespace@2..3 " "
+                    VariableRef@3..5
+                      Ident@3..4 "a"
+                      Whitespace@4..5 " "
+                    BlockExpr@5..11
+                      LBrace@5..6 "{"
+                      Whitespace@6..7 " "
+                      Literal@7..9
+                        Number@7..8 "1"
+                        Whitespace@8..9 " "
+                      RBrace@9..10 "}"
+                      Whitespace@10..11 " "
+                    ElseKw@11..15 "else"
+                    Whitespace@15..16 " "
+                    Error@16..17
+                      Number@16..17 "2"
+                error at 16..17: expected 'if' or '{', but found number-literal"#]],
+        );
+    }
+
+    #[test]
+    fn parse_while() {
+        check(
+            "while a { b }",
+            expect![[r#"
+                Root@0..13
+                  WhileExpr@0..13
+                    WhileKw@0..5 "while"
+                    Whitespace@5..6 " "
+                    VariableRef@6..8
+                      Ident@6..7 "a"
+                      Whitespace@7..8 " "
+                    BlockExpr@8..13
+                      LBrace@8..9 "{"
+                      Whitespace@9..10 " "
+                      VariableRef@10..12
+                        Ident@10..11 "b"
+                        Whitespace@11..12 " "
+                      RBrace@12..13 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_while_without_body() {
+        check(
+            "while a\nb",
+            expect![[r#"
+                Root@0..9
+                  WhileExpr@0..9
+                    WhileKw@0..5 "while"
+                    Whitespace@5..6 " "
+                    VariableRef@6..8
+                      Ident@6..7 "a"
+                      Newline@7..8 "\n"
+                    Error@8..9
+                      Ident@8..9 "b"
+                error at 8..9: expected '{', but found identifier"#]],
+        );
+    }
+
+    #[test]
+    fn parse_loop_with_break_value() {
+        check(
+            "loop { break 1 + 2 }",
+            expect![[r#"
+                Root@0..20
+                  LoopExpr@0..20
+                    LoopKw@0..4 "loop"
+                    Whitespace@4..5 " "
+                    BlockExpr@5..20
+                      LBrace@5..6 "{"
+                      Whitespace@6..7 " "
+                      BreakExpr@7..19
+                        BreakKw@7..12 "break"
+                        Whitespace@12..13 " "
+                        InfixExpr@13..19
+                          Literal@13..15
+                            Number@13..14 "1"
+                            Whitespace@14..15 " "
+                          Plus@15..16 "+"
+                          Whitespace@16..17 " "
+                          Literal@17..19
+                            Number@17..18 "2"
+                            Whitespace@18..19 " "
+                      RBrace@19..20 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_loop_without_braces() {
+        check(
+            "loop 1",
+            expect![[r#"
+                Root@0..6
+                  LoopExpr@0..6
+                    LoopKw@0..4 "loop"
+                    Whitespace@4..5 " "
+                    Error@5..6
+                      Number@5..6 "1"
+                error at 5..6: expected '{', but found number-literal"#]],
+        );
+    }
+
+    #[test]
+    fn break_value_must_start_on_same_line() {
+        check(
+            "loop { break\n1 }",
+            expect![[r#"
+                Root@0..16
+                  LoopExpr@0..16
+                    LoopKw@0..4 "loop"
+                    Whitespace@4..5 " "
+                    BlockExpr@5..16
+                      LBrace@5..6 "{"
+                      Whitespace@6..7 " "
+                      BreakExpr@7..13
+                        BreakKw@7..12 "break"
+                        Newline@12..13 "\n"
+                      Literal@13..15
+                        Number@13..14 "1"
+                        Whitespace@14..15 " "
+                      RBrace@15..16 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_break_and_continue() {
+        check(
+            "while a { if b { continue }; break }",
+            expect![[r#"
+                Root@0..36
+                  WhileExpr@0..36
+                    WhileKw@0..5 "while"
+                    Whitespace@5..6 " "
+                    VariableRef@6..8
+                      Ident@6..7 "a"
+                      Whitespace@7..8 " "
+                    BlockExpr@8..36
+                      LBrace@8..9 "{"
+                      Whitespace@9..10 " "
+                      IfExpr@10..27
+                        IfKw@10..12 "if"
+                        Whitespace@12..13 " "
+                        VariableRef@13..15
+                          Ident@13..14 "b"
+                          Whitespace@14..15 " "
+                        BlockExpr@15..27
+                          LBrace@15..16 "{"
+                          Whitespace@16..17 " "
+                          ContinueExpr@17..26
+                            ContinueKw@17..25 "continue"
+                            Whitespace@25..26 " "
+                          RBrace@26..27 "}"
+                      Semicolon@27..28 ";"
+                      Whitespace@28..29 " "
+                      BreakExpr@29..35
+                        BreakKw@29..34 "break"
+                        Whitespace@34..35 " "
+                      RBrace@35..36 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_assignment() {
+        check(
+            "a = a + 1",
+            expect![[r#"
+                Root@0..9
+                  AssignExpr@0..9
+                    VariableRef@0..2
+                      Ident@0..1 "a"
+                      Whitespace@1..2 " "
+                    Equals@2..3 "="
+                    Whitespace@3..4 " "
+                    InfixExpr@4..9
+                      VariableRef@4..6
+                        Ident@4..5 "a"
+                        Whitespace@5..6 " "
+                      Plus@6..7 "+"
+                      Whitespace@7..8 " "
+                      Literal@8..9
+                        Number@8..9 "1""#]],
+        );
+    }
+
+    #[test]
+    fn assignment_is_right_associative() {
+        check(
+            "a = b = c",
+            expect![[r#"
+                Root@0..9
+                  AssignExpr@0..9
+                    VariableRef@0..2
+                      Ident@0..1 "a"
+                      Whitespace@1..2 " "
+                    Equals@2..3 "="
+                    Whitespace@3..4 " "
+                    AssignExpr@4..9
+                      VariableRef@4..6
+                        Ident@4..5 "b"
+                        Whitespace@5..6 " "
+                      Equals@6..7 "="
+                      Whitespace@7..8 " "
+                      VariableRef@8..9
+                        Ident@8..9 "c""#]],
+        );
+    }
+
+    #[test]
+    fn parse_compound_assignment() {
+        check(
+            "a *= b || c",
+            expect![[r#"
+                Root@0..11
+                  AssignExpr@0..11
+                    VariableRef@0..2
+                      Ident@0..1 "a"
+                      Whitespace@1..2 " "
+                    StarEq@2..4 "*="
+                    Whitespace@4..5 " "
+                    InfixExpr@5..11
+                      VariableRef@5..7
+                        Ident@5..6 "b"
+                        Whitespace@6..7 " "
+                      PipePipe@7..9 "||"
+                      Whitespace@9..10 " "
+                      VariableRef@10..11
+                        Ident@10..11 "c""#]],
+        );
+    }
+
+    #[test]
+    fn assignment_binds_loosest() {
+        check(
+            "a || b = c",
+            expect![[r#"
+                Root@0..10
+                  AssignExpr@0..10
+                    InfixExpr@0..7
+                      VariableRef@0..2
+                        Ident@0..1 "a"
+                        Whitespace@1..2 " "
+                      PipePipe@2..4 "||"
+                      Whitespace@4..5 " "
+                      VariableRef@5..7
+                        Ident@5..6 "b"
+                        Whitespace@6..7 " "
+                    Equals@7..8 "="
+                    Whitespace@8..9 " "
+                    VariableRef@9..10
+                      Ident@9..10 "c""#]],
+        );
+    }
+
+    #[test]
+    fn parse_assignment_without_value() {
+        check(
+            "a +=",
+            expect![[r#"
+                Root@0..4
+                  AssignExpr@0..4
+                    VariableRef@0..2
+                      Ident@0..1 "a"
+                      Whitespace@1..2 " "
+                    PlusEq@2..4 "+="
+                error at 2..4: expected number-literal, float-literal, string-literal, 'true', 'false', identifier, '-', '!', '(', '{', 'if', 'while', 'loop', 'break' or 'continue'"#]],
+        );
+    }
+
+    #[test]
+    fn parse_string_literal() {
+        check(
+            r#""a\"b" + "c""#,
+            expect![[r#"
+                Root@0..12
+                  InfixExpr@0..12
+                    Literal@0..7
+                      String@0..6 "\"a\\\"b\""
+                      Whitespace@6..7 " "
+                    Plus@7..8 "+"
+                    Whitespace@8..9 " "
+                    Literal@9..12
+                      String@9..12 "\"c\"""#]],
+        );
+    }
+
+    #[test]
+    fn parse_unterminated_string_literal() {
+        check(
+            "let a = \"abc\nlet b = 1",
+            expect![[r#"
+                Root@0..22
+                  VariableDef@0..13
+                    LetKw@0..3 "let"
+                    Whitespace@3..4 " "
+                    Ident@4..5 "a"
+                    Whitespace@5..6 " "
+                    Equals@6..7 "="
+                    Whitespace@7..8 " "
+                    Literal@8..13
+                      String@8..12 "\"abc"
+                      Newline@12..13 "\n"
+                  VariableDef@13..22
+                    LetKw@13..16 "let"
+                    Whitespace@16..17 " "
+                    Ident@17..18 "b"
+                    Whitespace@18..19 " "
+                    Equals@19..20 "="
+                    Whitespace@20..21 " "
+                    Literal@21..22
+                      Number@21..22 "1""#]],
+        );
+    }
+
+    #[test]
+    fn parse_number_literals() {
+        check(
+            "0xff + 1.5e-3",
+            expect![[r#"
+                Root@0..13
+                  InfixExpr@0..13
+                    Literal@0..5
+                      Number@0..4 "0xff"
+                      Whitespace@4..5 " "
+                    Plus@5..6 "+"
+                    Whitespace@6..7 " "
+                    Literal@7..13
+                      Float@7..13 "1.5e-3""#]],
+        );
+    }
+
     #[test]
     fn parse_variable_ref() {
         check(
@@ -356,30 +1296,30 @@ mod tests {
     fn parse_binary_expression_interspersed_with_comments() {
         check(
             "
-1
-  + 1 # Add one
-  + 10 # Add ten",
+1 + # Add one
+  1 + # Add ten
+  10",
             expect![[r##"
                 Root@0..35
-                  Whitespace@0..1 "\n"
+                  Newline@0..1 "\n"
                   InfixExpr@1..35
-                    InfixExpr@1..21
-                      Literal@1..5
+                    InfixExpr@1..19
+                      Literal@1..3
                         Number@1..2 "1"
-                        Whitespace@2..5 "\n  "
-                      Plus@5..6 "+"
-                      Whitespace@6..7 " "
-                      Literal@7..21
-                        Number@7..8 "1"
-                        Whitespace@8..9 " "
-                        Comment@9..18 "# Add one"
-                        Whitespace@18..21 "\n  "
-                    Plus@21..22 "+"
-                    Whitespace@22..23 " "
-                    Literal@23..35
-                      Number@23..25 "10"
-                      Whitespace@25..26 " "
-                      Comment@26..35 "# Add ten""##]],
+                        Whitespace@2..3 " "
+                      Plus@3..4 "+"
+                      Whitespace@4..5 " "
+                      Comment@5..14 "# Add one"
+                      Newline@14..17 "\n  "
+                      Literal@17..19
+                        Number@17..18 "1"
+                        Whitespace@18..19 " "
+                    Plus@19..20 "+"
+                    Whitespace@20..21 " "
+                    Comment@21..30 "# Add ten"
+                    Newline@30..33 "\n  "
+                    Literal@33..35
+                      Number@33..35 "10""##]],
         );
     }
 
@@ -393,7 +1333,7 @@ mod tests {
                     LParen@0..1 "("
                     VariableRef@1..4
                       Ident@1..4 "foo"
-                error at 1..4: expected '+', '-', '*', '/' or ')'"#]],
+                error at 1..4: expected '+', '-', '*', '/', '==', '!=', '<', '<=', '>', '>=', '&&', '||', '=', '+=', '-=', '*=', '/=', '(' or ')'"#]],
         );
     }
 
@@ -402,15 +1342,300 @@ mod tests {
         check(
             "(1+",
             expect![[r#"
-Root@0..3
-  ParenExpr@0..3
-    LParen@0..1 "("
-    InfixExpr@1..3
-      Literal@1..2
-        Number@1..2 "1"
-      Plus@2..3 "+"
-error at 2..3: expected number-literal, identifier, '-' or '('
-error at 2..3: expected ')'"#]],
+                Root@0..3
+                  ParenExpr@0..3
+                    LParen@0..1 "("
+                    InfixExpr@1..3
+                      Literal@1..2
+                        Number@1..2 "1"
+                      Plus@2..3 "+"
+                error at 2..3: expected number-literal, float-literal, string-literal, 'true', 'false', identifier, '-', '!', '(', '{', 'if', 'while', 'loop', 'break' or 'continue'"#]],
+        );
+    }
+
+    #[test]
+    fn parse_call_without_args() {
+        check(
+            "f()",
+            expect![[r#"
+            Root@0..3
+              CallExpr@0..3
+                VariableRef@0..1
+                  Ident@0..1 "f"
+                ArgList@1..3
+                  LParen@1..2 "("
+                  RParen@2..3 ")""#]],
+        );
+    }
+
+    #[test]
+    fn parse_call_with_args() {
+        check(
+            "add(1, 2 * 3)",
+            expect![[r#"
+            Root@0..13
+              CallExpr@0..13
+                VariableRef@0..3
+                  Ident@0..3 "add"
+                ArgList@3..13
+                  LParen@3..4 "("
+                  Literal@4..5
+                    Number@4..5 "1"
+                  Comma@5..6 ","
+                  Whitespace@6..7 " "
+                  InfixExpr@7..12
+                    Literal@7..9
+                      Number@7..8 "2"
+                      Whitespace@8..9 " "
+                    Star@9..10 "*"
+                    Whitespace@10..11 " "
+                    Literal@11..12
+                      Number@11..12 "3"
+                  RParen@12..13 ")""#]],
+        );
+    }
+
+    #[test]
+    fn call_binds_tighter_than_negation() {
+        check(
+            "-f(1)",
+            expect![[r#"
+            Root@0..5
+              PrefixExpr@0..5
+                Minus@0..1 "-"
+                CallExpr@1..5
+                  VariableRef@1..2
+                    Ident@1..2 "f"
+                  ArgList@2..5
+                    LParen@2..3 "("
+                    Literal@3..4
+                      Number@3..4 "1"
+                    RParen@4..5 ")""#]],
+        );
+    }
+
+    #[test]
+    fn parse_chained_calls() {
+        check(
+            "f(1)(2)",
+            expect![[r#"
+            Root@0..7
+              CallExpr@0..7
+                CallExpr@0..4
+                  VariableRef@0..1
+                    Ident@0..1 "f"
+                  ArgList@1..4
+                    LParen@1..2 "("
+                    Literal@2..3
+                      Number@2..3 "1"
+                    RParen@3..4 ")"
+                ArgList@4..7
+                  LParen@4..5 "("
+                  Literal@5..6
+                    Number@5..6 "2"
+                  RParen@6..7 ")""#]],
+        );
+    }
+
+    #[test]
+    fn parse_call_with_missing_comma() {
+        check(
+            "f(1 2)",
+            expect![[r#"
+                Root@0..6
+                  CallExpr@0..6
+                    VariableRef@0..1
+                      Ident@0..1 "f"
+                    ArgList@1..6
+                      LParen@1..2 "("
+                      Literal@2..4
+                        Number@2..3 "1"
+                        Whitespace@3..4 " "
+                      Error@4..5
+                        Number@4..5 "2"
+                      RParen@5..6 ")"
+                error at 4..5: expected '+', '-', '*', '/', '==', '!=', '<', '<=', '>', '>=', '&&', '||', '=', '+=', '-=', '*=', '/=', '(', ')' or ',', but found number-literal"#]],
+        );
+    }
+
+    #[test]
+    fn parse_unclosed_call() {
+        check(
+            "f(1,",
+            expect![[r#"
+                Root@0..4
+                  CallExpr@0..4
+                    VariableRef@0..1
+                      Ident@0..1 "f"
+                    ArgList@1..4
+                      LParen@1..2 "("
+                      Literal@2..3
+                        Number@2..3 "1"
+                      Comma@3..4 ","
+                error at 3..4: expected ')'"#]],
+        );
+    }
+
+    #[test]
+    fn newlines_inside_parentheses_do_not_end_expressions() {
+        check(
+            "(1\n+ 2)",
+            expect![[r#"
+            Root@0..7
+              ParenExpr@0..7
+                LParen@0..1 "("
+                InfixExpr@1..6
+                  Literal@1..3
+                    Number@1..2 "1"
+                    Newline@2..3 "\n"
+                  Plus@3..4 "+"
+                  Whitespace@4..5 " "
+                  Literal@5..6
+                    Number@5..6 "2"
+                RParen@6..7 ")""#]],
+        );
+    }
+
+    #[test]
+    fn newlines_inside_args_do_not_end_expressions() {
+        check(
+            "f(\n  a\n  * 2,\n  { b\n  -c },\n)",
+            expect![[r#"
+            Root@0..29
+              CallExpr@0..29
+                VariableRef@0..1
+                  Ident@0..1 "f"
+                ArgList@1..29
+                  LParen@1..2 "("
+                  Newline@2..5 "\n  "
+                  InfixExpr@5..12
+                    VariableRef@5..9
+                      Ident@5..6 "a"
+                      Newline@6..9 "\n  "
+                    Star@9..10 "*"
+                    Whitespace@10..11 " "
+                    Literal@11..12
+                      Number@11..12 "2"
+                  Comma@12..13 ","
+                  Newline@13..16 "\n  "
+                  BlockExpr@16..26
+                    LBrace@16..17 "{"
+                    Whitespace@17..18 " "
+                    VariableRef@18..22
+                      Ident@18..19 "b"
+                      Newline@19..22 "\n  "
+                    PrefixExpr@22..25
+                      Minus@22..23 "-"
+                      VariableRef@23..25
+                        Ident@23..24 "c"
+                        Whitespace@24..25 " "
+                    RBrace@25..26 "}"
+                  Comma@26..27 ","
+                  Newline@27..28 "\n"
+                  RParen@28..29 ")""#]],
+        );
+    }
+
+    #[test]
+    fn parse_empty_block() {
+        check(
+            "{}",
+            expect![[r#"
+            Root@0..2
+              BlockExpr@0..2
+                LBrace@0..1 "{"
+                RBrace@1..2 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_block_with_stmts_and_tail() {
+        check(
+            "{ let a = 1; a + 1 }",
+            expect![[r#"
+                Root@0..20
+                  BlockExpr@0..20
+                    LBrace@0..1 "{"
+                    Whitespace@1..2 " "
+                    VariableDef@2..11
+                      LetKw@2..5 "let"
+                      Whitespace@5..6 " "
+                      Ident@6..7 "a"
+                      Whitespace@7..8 " "
+                      Equals@8..9 "="
+                      Whitespace@9..10 " "
+                      Literal@10..11
+                        Number@10..11 "1"
+                    Semicolon@11..12 ";"
+                    Whitespace@12..13 " "
+                    InfixExpr@13..19
+                      VariableRef@13..15
+                        Ident@13..14 "a"
+                        Whitespace@14..15 " "
+                      Plus@15..16 "+"
+                      Whitespace@16..17 " "
+                      Literal@17..19
+                        Number@17..18 "1"
+                        Whitespace@18..19 " "
+                    RBrace@19..20 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_nested_blocks() {
+        check(
+            "{ { 1 } }",
+            expect![[r#"
+            Root@0..9
+              BlockExpr@0..9
+                LBrace@0..1 "{"
+                Whitespace@1..2 " "
+                BlockExpr@2..8
+                  LBrace@2..3 "{"
+                  Whitespace@3..4 " "
+                  Literal@4..6
+                    Number@4..5 "1"
+                    Whitespace@5..6 " "
+                  RBrace@6..7 "}"
+                  Whitespace@7..8 " "
+                RBrace@8..9 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_block_as_operand() {
+        check(
+            "1 + { 2 }",
+            expect![[r#"
+            Root@0..9
+              InfixExpr@0..9
+                Literal@0..2
+                  Number@0..1 "1"
+                  Whitespace@1..2 " "
+                Plus@2..3 "+"
+                Whitespace@3..4 " "
+                BlockExpr@4..9
+                  LBrace@4..5 "{"
+                  Whitespace@5..6 " "
+                  Literal@6..8
+                    Number@6..7 "2"
+                    Whitespace@7..8 " "
+                  RBrace@8..9 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_unclosed_block() {
+        check(
+            "{ 1",
+            expect![[r#"
+                Root@0..3
+                  BlockExpr@0..3
+                    LBrace@0..1 "{"
+                    Whitespace@1..2 " "
+                    Literal@2..3
+                      Number@2..3 "1"
+                error at 2..3: expected '+', '-', '*', '/', '==', '!=', '<', '<=', '>', '>=', '&&', '||', '=', '+=', '-=', '*=', '/=', '(', ';' or '}'"#]],
         );
     }
 }
@@ -1,5 +1,6 @@
 mod expr;
 mod stmt;
+mod ty;
 use super::CompletedMarker;
 use crate::parser::Parser;
 use crate::syntax::SyntaxKind;
@@ -8,7 +9,12 @@ pub fn root(p: &mut Parser) -> CompletedMarker {
     let m = p.start();
     while !p.at_end() {
         stmt::stmt(p);
+        stmt::stmt_end(p, None);
     }
 
     m.complete(p, SyntaxKind::Root)
 }
+
+pub fn block(p: &mut Parser) -> CompletedMarker {
+    expr::block_expr(p)
+}
@@ -1,23 +1,38 @@
 use super::CompletedMarker;
 use crate::lexer::TokenKind;
-use crate::parser::grammar::expr;
+use crate::parser::grammar::{expr, ty};
 use crate::parser::Parser;
 use crate::syntax::SyntaxKind;
 
 pub(super) fn stmt(p: &mut Parser) -> Option<CompletedMarker> {
     if p.at(TokenKind::LetKw) {
         Some(variable_def(p))
+    } else if p.at(TokenKind::FnKw) {
+        Some(fn_def(p))
     } else {
         expr::expr(p)
     }
 }
 
+/// Ends a statement with `;`s, a newline, or `closing` (the end of input if `None`), reporting
+/// an error if another statement follows on the same line.
+pub(super) fn stmt_end(p: &mut Parser, closing: Option<TokenKind>) {
+    if p.at(TokenKind::Semicolon) {
+        while p.at(TokenKind::Semicolon) {
+            p.bump();
+        }
+    } else if !(p.at_end() || closing.is_some_and(|kind| p.at(kind)) || p.at_newline()) {
+        p.error_without_recovery();
+    }
+}
+
 fn variable_def(p: &mut Parser) -> CompletedMarker {
     assert!(p.at(TokenKind::LetKw));
     let m = p.start();
     p.bump();
 
     p.expect(TokenKind::Ident);
+    ty::annotation(p);
     p.expect(TokenKind::Equals);
 
     expr::expr(p);
@@ -25,6 +40,55 @@ fn variable_def(p: &mut Parser) -> CompletedMarker {
     m.complete(p, SyntaxKind::VariableDef)
 }
 
+fn fn_def(p: &mut Parser) -> CompletedMarker {
+    assert!(p.at(TokenKind::FnKw));
+    let m = p.start();
+    p.bump();
+
+    p.expect(TokenKind::Ident);
+
+    // With none of the rest of a function to follow, the `fn` is more likely a typo than the
+    // start of one, so the rest is left alone rather than reported piece by piece.
+    if !p.at(TokenKind::LParen) && !p.at_set(&[TokenKind::Arrow, TokenKind::LBrace]) {
+        p.error();
+        return m.complete(p, SyntaxKind::FnDef);
+    }
+
+    param_list(p);
+
+    if p.at(TokenKind::Arrow) {
+        p.bump();
+        ty::type_ref(p);
+    }
+
+    if p.at(TokenKind::LBrace) {
+        expr::block_expr(p);
+    } else {
+        p.error();
+    }
+
+    m.complete(p, SyntaxKind::FnDef)
+}
+
+fn param_list(p: &mut Parser) {
+    let m = p.start();
+    p.expect(TokenKind::LParen);
+
+    while p.at(TokenKind::Ident) {
+        let param = p.start();
+        p.bump();
+        ty::annotation(p);
+        param.complete(p, SyntaxKind::Param);
+
+        if !p.at(TokenKind::RParen) {
+            p.expect(TokenKind::Comma);
+        }
+    }
+
+    p.expect(TokenKind::RParen);
+    m.complete(p, SyntaxKind::ParamList);
+}
+
 #[cfg(test)]
 mod tests {
     // snip
@@ -63,7 +127,7 @@ Root@0..13
                     Ident@4..5 "a"
                     Whitespace@5..6 " "
                     Equals@6..7 "="
-                    Whitespace@7..8 "\n"
+                    Newline@7..8 "\n"
                   VariableDef@8..17
                     LetKw@8..11 "let"
                     Whitespace@11..12 " "
@@ -73,7 +137,7 @@ Root@0..13
                     Whitespace@15..16 " "
                     VariableRef@16..17
                       Ident@16..17 "a"
-                error at 8..11: expected number-literal, identifier, '-' or '(', but found 'let'"#]],
+                error at 8..11: expected number-literal, float-literal, string-literal, 'true', 'false', identifier, '-', '!', '(', '{', 'if', 'while', 'loop', 'break' or 'continue', but found 'let'"#]],
         );
     }
 
@@ -83,19 +147,371 @@ Root@0..13
             "let a = 1
 a",
             expect![[r#"
-    Root@0..11
-      VariableDef@0..10
-        LetKw@0..3 "let"
-        Whitespace@3..4 " "
-        Ident@4..5 "a"
-        Whitespace@5..6 " "
-        Equals@6..7 "="
-        Whitespace@7..8 " "
-        Literal@8..10
-          Number@8..9 "1"
-          Whitespace@9..10 "\n"
-      VariableRef@10..11
-        Ident@10..11 "a""#]],
+                Root@0..11
+                  VariableDef@0..10
+                    LetKw@0..3 "let"
+                    Whitespace@3..4 " "
+                    Ident@4..5 "a"
+                    Whitespace@5..6 " "
+                    Equals@6..7 "="
+                    Whitespace@7..8 " "
+                    Literal@8..10
+                      Number@8..9 "1"
+                      Newline@9..10 "\n"
+                  VariableRef@10..11
+                    Ident@10..11 "a""#]],
+        );
+    }
+
+    #[test]
+    fn newline_ends_statement() {
+        check(
+            "1 + 2\n-3",
+            expect![[r#"
+                Root@0..8
+                  InfixExpr@0..6
+                    Literal@0..2
+                      Number@0..1 "1"
+                      Whitespace@1..2 " "
+                    Plus@2..3 "+"
+                    Whitespace@3..4 " "
+                    Literal@4..6
+                      Number@4..5 "2"
+                      Newline@5..6 "\n"
+                  PrefixExpr@6..8
+                    Minus@6..7 "-"
+                    Literal@7..8
+                      Number@7..8 "3""#]],
+        );
+    }
+
+    #[test]
+    fn operator_at_end_of_line_continues_statement() {
+        check(
+            "1 +\n  2",
+            expect![[r#"
+                Root@0..7
+                  InfixExpr@0..7
+                    Literal@0..2
+                      Number@0..1 "1"
+                      Whitespace@1..2 " "
+                    Plus@2..3 "+"
+                    Newline@3..6 "\n  "
+                    Literal@6..7
+                      Number@6..7 "2""#]],
+        );
+    }
+
+    #[test]
+    fn newline_before_call_ends_statement() {
+        check(
+            "f\n(1)",
+            expect![[r#"
+                Root@0..5
+                  VariableRef@0..2
+                    Ident@0..1 "f"
+                    Newline@1..2 "\n"
+                  ParenExpr@2..5
+                    LParen@2..3 "("
+                    Literal@3..4
+                      Number@3..4 "1"
+                    RParen@4..5 ")""#]],
+        );
+    }
+
+    #[test]
+    fn semicolons_separate_statements() {
+        check(
+            "let a = 1; a;; a",
+            expect![[r#"
+                Root@0..16
+                  VariableDef@0..9
+                    LetKw@0..3 "let"
+                    Whitespace@3..4 " "
+                    Ident@4..5 "a"
+                    Whitespace@5..6 " "
+                    Equals@6..7 "="
+                    Whitespace@7..8 " "
+                    Literal@8..9
+                      Number@8..9 "1"
+                  Semicolon@9..10 ";"
+                  Whitespace@10..11 " "
+                  VariableRef@11..12
+                    Ident@11..12 "a"
+                  Semicolon@12..13 ";"
+                  Semicolon@13..14 ";"
+                  Whitespace@14..15 " "
+                  VariableRef@15..16
+                    Ident@15..16 "a""#]],
+        );
+    }
+
+    #[test]
+    fn statements_running_together() {
+        check(
+            "let a = 1 let b = a a",
+            expect![[r#"
+                Root@0..21
+                  VariableDef@0..10
+                    LetKw@0..3 "let"
+                    Whitespace@3..4 " "
+                    Ident@4..5 "a"
+                    Whitespace@5..6 " "
+                    Equals@6..7 "="
+                    Whitespace@7..8 " "
+                    Literal@8..10
+                      Number@8..9 "1"
+                      Whitespace@9..10 " "
+                  VariableDef@10..20
+                    LetKw@10..13 "let"
+                    Whitespace@13..14 " "
+                    Ident@14..15 "b"
+                    Whitespace@15..16 " "
+                    Equals@16..17 "="
+                    Whitespace@17..18 " "
+                    VariableRef@18..20
+                      Ident@18..19 "a"
+                      Whitespace@19..20 " "
+                  VariableRef@20..21
+                    Ident@20..21 "a"
+                error at 10..13: expected '+', '-', '*', '/', '==', '!=', '<', '<=', '>', '>=', '&&', '||', '=', '+=', '-=', '*=', '/=', '(', ';' or newline, but found 'let'
+                error at 20..21: expected '+', '-', '*', '/', '==', '!=', '<', '<=', '>', '>=', '&&', '||', '=', '+=', '-=', '*=', '/=', '(', ';' or newline, but found identifier"#]],
+        );
+    }
+
+    #[test]
+    fn parse_fn_def() {
+        check(
+            "fn add(a, b) { a + b }",
+            expect![[r#"
+                Root@0..22
+                  FnDef@0..22
+                    FnKw@0..2 "fn"
+                    Whitespace@2..3 " "
+                    Ident@3..6 "add"
+                    ParamList@6..13
+                      LParen@6..7 "("
+                      Param@7..8
+                        Ident@7..8 "a"
+                      Comma@8..9 ","
+                      Whitespace@9..10 " "
+                      Param@10..11
+                        Ident@10..11 "b"
+                      RParen@11..12 ")"
+                      Whitespace@12..13 " "
+                    BlockExpr@13..22
+                      LBrace@13..14 "{"
+                      Whitespace@14..15 " "
+                      InfixExpr@15..21
+                        VariableRef@15..17
+                          Ident@15..16 "a"
+                          Whitespace@16..17 " "
+                        Plus@17..18 "+"
+                        Whitespace@18..19 " "
+                        VariableRef@19..21
+                          Ident@19..20 "b"
+                          Whitespace@20..21 " "
+                      RBrace@21..22 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_fn_def_without_params() {
+        check(
+            "fn one() { 1 }",
+            expect![[r#"
+                Root@0..14
+                  FnDef@0..14
+                    FnKw@0..2 "fn"
+                    Whitespace@2..3 " "
+                    Ident@3..6 "one"
+                    ParamList@6..9
+                      LParen@6..7 "("
+                      RParen@7..8 ")"
+                      Whitespace@8..9 " "
+                    BlockExpr@9..14
+                      LBrace@9..10 "{"
+                      Whitespace@10..11 " "
+                      Literal@11..13
+                        Number@11..12 "1"
+                        Whitespace@12..13 " "
+                      RBrace@13..14 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_fn_def_with_trailing_comma() {
+        check(
+            "fn id(a,) { a }",
+            expect![[r#"
+                Root@0..15
+                  FnDef@0..15
+                    FnKw@0..2 "fn"
+                    Whitespace@2..3 " "
+                    Ident@3..5 "id"
+                    ParamList@5..10
+                      LParen@5..6 "("
+                      Param@6..7
+                        Ident@6..7 "a"
+                      Comma@7..8 ","
+                      RParen@8..9 ")"
+                      Whitespace@9..10 " "
+                    BlockExpr@10..15
+                      LBrace@10..11 "{"
+                      Whitespace@11..12 " "
+                      VariableRef@12..14
+                        Ident@12..13 "a"
+                        Whitespace@13..14 " "
+                      RBrace@14..15 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_fn_def_with_multiple_stmts() {
+        check(
+            "fn f(x) {\n  let y = x\n  y * y\n}",
+            expect![[r#"
+                Root@0..31
+                  FnDef@0..31
+                    FnKw@0..2 "fn"
+                    Whitespace@2..3 " "
+                    Ident@3..4 "f"
+                    ParamList@4..8
+                      LParen@4..5 "("
+                      Param@5..6
+                        Ident@5..6 "x"
+                      RParen@6..7 ")"
+                      Whitespace@7..8 " "
+                    BlockExpr@8..31
+                      LBrace@8..9 "{"
+                      Newline@9..12 "\n  "
+                      VariableDef@12..24
+                        LetKw@12..15 "let"
+                        Whitespace@15..16 " "
+                        Ident@16..17 "y"
+                        Whitespace@17..18 " "
+                        Equals@18..19 "="
+                        Whitespace@19..20 " "
+                        VariableRef@20..24
+                          Ident@20..21 "x"
+                          Newline@21..24 "\n  "
+                      InfixExpr@24..30
+                        VariableRef@24..26
+                          Ident@24..25 "y"
+                          Whitespace@25..26 " "
+                        Star@26..27 "*"
+                        Whitespace@27..28 " "
+                        VariableRef@28..30
+                          Ident@28..29 "y"
+                          Newline@29..30 "\n"
+                      RBrace@30..31 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_fn_def_without_body() {
+        check(
+            "fn f()",
+            expect![[r#"
+                Root@0..6
+                  FnDef@0..6
+                    FnKw@0..2 "fn"
+                    Whitespace@2..3 " "
+                    Ident@3..4 "f"
+                    ParamList@4..6
+                      LParen@4..5 "("
+                      RParen@5..6 ")"
+                error at 5..6: expected '->' or '{'"#]],
+        );
+    }
+
+    #[test]
+    fn parse_unclosed_fn_body() {
+        check(
+            "fn f() { 1",
+            expect![[r#"
+                Root@0..10
+                  FnDef@0..10
+                    FnKw@0..2 "fn"
+                    Whitespace@2..3 " "
+                    Ident@3..4 "f"
+                    ParamList@4..7
+                      LParen@4..5 "("
+                      RParen@5..6 ")"
+                      Whitespace@6..7 " "
+                    BlockExpr@7..10
+                      LBrace@7..8 "{"
+                      Whitespace@8..9 " "
+                      Literal@9..10
+                        Number@9..10 "1"
+                error at 9..10: expected '+', '-', '*', '/', '==', '!=', '<', '<=', '>', '>=', '&&', '||', '=', '+=', '-=', '*=', '/=', '(', ';' or '}'"#]],
+        );
+    }
+
+    #[test]
+    fn stray_fn_keyword_reports_few_errors() {
+        check(
+            "let g = fn_missing\nlet h = 1",
+            expect![[r#"
+            Root@0..28
+              VariableDef@0..8
+                LetKw@0..3 "let"
+                Whitespace@3..4 " "
+                Ident@4..5 "g"
+                Whitespace@5..6 " "
+                Equals@6..7 "="
+                Whitespace@7..8 " "
+              FnDef@8..19
+                FnKw@8..10 "fn"
+                Error@10..11
+                  Error@10..11 "_"
+                Error@11..19
+                  Ident@11..18 "missing"
+                  Newline@18..19 "\n"
+              VariableDef@19..28
+                LetKw@19..22 "let"
+                Whitespace@22..23 " "
+                Ident@23..24 "h"
+                Whitespace@24..25 " "
+                Equals@25..26 "="
+                Whitespace@26..27 " "
+                Literal@27..28
+                  Number@27..28 "1"
+            error at 8..10: expected number-literal, float-literal, string-literal, 'true', 'false', identifier, '-', '!', '(', '{', 'if', 'while', 'loop', 'break' or 'continue', but found 'fn'
+            error at 10..11: expected identifier, but found an unrecognized token
+            error at 11..18: expected '(', but found identifier"#]],
+        );
+    }
+
+    #[test]
+    fn recover_on_fn_token() {
+        check(
+            "fn f(\nfn g() { 2 }",
+            expect![[r#"
+                Root@0..18
+                  FnDef@0..6
+                    FnKw@0..2 "fn"
+                    Whitespace@2..3 " "
+                    Ident@3..4 "f"
+                    ParamList@4..6
+                      LParen@4..5 "("
+                      Newline@5..6 "\n"
+                  FnDef@6..18
+                    FnKw@6..8 "fn"
+                    Whitespace@8..9 " "
+                    Ident@9..10 "g"
+                    ParamList@10..13
+                      LParen@10..11 "("
+                      RParen@11..12 ")"
+                      Whitespace@12..13 " "
+                    BlockExpr@13..18
+                      LBrace@13..14 "{"
+                      Whitespace@14..15 " "
+                      Literal@15..17
+                        Number@15..16 "2"
+                        Whitespace@16..17 " "
+                      RBrace@17..18 "}"
+                error at 6..8: expected identifier or ')', but found 'fn'"#]],
         );
     }
 }
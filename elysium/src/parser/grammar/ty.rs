@@ -0,0 +1,252 @@
+use super::{Parser, SyntaxKind};
+use crate::{lexer::TokenKind, parser::CompletedMarker};
+
+/// Tokens that can follow a type, left for the caller to parse when a type is missing.
+const TYPE_RECOVERY_SET: [TokenKind; 4] = [
+    TokenKind::Equals,
+    TokenKind::Comma,
+    TokenKind::RParen,
+    TokenKind::LBrace,
+];
+
+/// Parses the `: Type` of a `let` or parameter, if there is one.
+pub(super) fn annotation(p: &mut Parser) {
+    if p.at(TokenKind::Colon) {
+        p.bump();
+        type_ref(p);
+    }
+}
+
+/// Parses a type: a name such as `Int`, the unit type `()`, or a function type such as
+/// `fn(Int, Bool) -> Int`.
+pub(super) fn type_ref(p: &mut Parser) -> Option<CompletedMarker> {
+    let m = if p.at(TokenKind::Ident) {
+        let m = p.start();
+        p.bump();
+        m
+    } else if p.at(TokenKind::LParen) {
+        let m = p.start();
+        p.bump();
+        p.expect(TokenKind::RParen);
+        m
+    } else if p.at(TokenKind::FnKw) {
+        let m = p.start();
+        p.bump();
+        fn_type_params(p);
+        if p.at(TokenKind::Arrow) {
+            p.bump();
+            type_ref(p);
+        } else {
+            // The return type is required, but without it the type is still clearly over.
+            p.error_without_recovery();
+        }
+        m
+    } else {
+        if p.at_set(&TYPE_RECOVERY_SET) {
+            p.error_without_recovery();
+        } else {
+            p.error();
+        }
+        return None;
+    };
+
+    Some(m.complete(p, SyntaxKind::TypeRef))
+}
+
+fn fn_type_params(p: &mut Parser) {
+    p.expect(TokenKind::LParen);
+
+    while p.at(TokenKind::Ident) || p.at(TokenKind::LParen) || p.at(TokenKind::FnKw) {
+        type_ref(p);
+
+        if !p.at(TokenKind::RParen) {
+            p.expect(TokenKind::Comma);
+        }
+    }
+
+    p.expect(TokenKind::RParen);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::check;
+    use expect_test::expect;
+
+    #[test]
+    fn parse_annotated_variable_definition() {
+        check(
+            "let x: Int = 3",
+            expect![[r#"
+            Root@0..14
+              VariableDef@0..14
+                LetKw@0..3 "let"
+                Whitespace@3..4 " "
+                Ident@4..5 "x"
+                Colon@5..6 ":"
+                Whitespace@6..7 " "
+                TypeRef@7..11
+                  Ident@7..10 "Int"
+                  Whitespace@10..11 " "
+                Equals@11..12 "="
+                Whitespace@12..13 " "
+                Literal@13..14
+                  Number@13..14 "3""#]],
+        );
+    }
+
+    #[test]
+    fn parse_annotated_fn() {
+        check(
+            "fn f(a: Int, b) -> Bool { a }",
+            expect![[r#"
+            Root@0..29
+              FnDef@0..29
+                FnKw@0..2 "fn"
+                Whitespace@2..3 " "
+                Ident@3..4 "f"
+                ParamList@4..16
+                  LParen@4..5 "("
+                  Param@5..11
+                    Ident@5..6 "a"
+                    Colon@6..7 ":"
+                    Whitespace@7..8 " "
+                    TypeRef@8..11
+                      Ident@8..11 "Int"
+                  Comma@11..12 ","
+                  Whitespace@12..13 " "
+                  Param@13..14
+                    Ident@13..14 "b"
+                  RParen@14..15 ")"
+                  Whitespace@15..16 " "
+                Arrow@16..18 "->"
+                Whitespace@18..19 " "
+                TypeRef@19..24
+                  Ident@19..23 "Bool"
+                  Whitespace@23..24 " "
+                BlockExpr@24..29
+                  LBrace@24..25 "{"
+                  Whitespace@25..26 " "
+                  VariableRef@26..28
+                    Ident@26..27 "a"
+                    Whitespace@27..28 " "
+                  RBrace@28..29 "}""#]],
+        );
+    }
+
+    #[test]
+    fn parse_unit_and_fn_types() {
+        check(
+            "let f: fn((), fn(Int) -> Int) -> () = g",
+            expect![[r#"
+            Root@0..39
+              VariableDef@0..39
+                LetKw@0..3 "let"
+                Whitespace@3..4 " "
+                Ident@4..5 "f"
+                Colon@5..6 ":"
+                Whitespace@6..7 " "
+                TypeRef@7..36
+                  FnKw@7..9 "fn"
+                  LParen@9..10 "("
+                  TypeRef@10..12
+                    LParen@10..11 "("
+                    RParen@11..12 ")"
+                  Comma@12..13 ","
+                  Whitespace@13..14 " "
+                  TypeRef@14..28
+                    FnKw@14..16 "fn"
+                    LParen@16..17 "("
+                    TypeRef@17..20
+                      Ident@17..20 "Int"
+                    RParen@20..21 ")"
+                    Whitespace@21..22 " "
+                    Arrow@22..24 "->"
+                    Whitespace@24..25 " "
+                    TypeRef@25..28
+                      Ident@25..28 "Int"
+                  RParen@28..29 ")"
+                  Whitespace@29..30 " "
+                  Arrow@30..32 "->"
+                  Whitespace@32..33 " "
+                  TypeRef@33..36
+                    LParen@33..34 "("
+                    RParen@34..35 ")"
+                    Whitespace@35..36 " "
+                Equals@36..37 "="
+                Whitespace@37..38 " "
+                VariableRef@38..39
+                  Ident@38..39 "g""#]],
+        );
+    }
+
+    #[test]
+    fn parse_missing_type() {
+        check(
+            "let x: = 3",
+            expect![[r#"
+            Root@0..10
+              VariableDef@0..10
+                LetKw@0..3 "let"
+                Whitespace@3..4 " "
+                Ident@4..5 "x"
+                Colon@5..6 ":"
+                Whitespace@6..7 " "
+                Equals@7..8 "="
+                Whitespace@8..9 " "
+                Literal@9..10
+                  Number@9..10 "3"
+            error at 7..8: expected identifier, '(' or 'fn', but found '='"#]],
+        );
+    }
+
+    #[test]
+    fn parse_missing_param_type() {
+        check(
+            "fn f(a:) {}",
+            expect![[r#"
+            Root@0..11
+              FnDef@0..11
+                FnKw@0..2 "fn"
+                Whitespace@2..3 " "
+                Ident@3..4 "f"
+                ParamList@4..9
+                  LParen@4..5 "("
+                  Param@5..7
+                    Ident@5..6 "a"
+                    Colon@6..7 ":"
+                  RParen@7..8 ")"
+                  Whitespace@8..9 " "
+                BlockExpr@9..11
+                  LBrace@9..10 "{"
+                  RBrace@10..11 "}"
+            error at 7..8: expected identifier, '(' or 'fn', but found ')'"#]],
+        );
+    }
+
+    #[test]
+    fn parse_fn_type_without_return_type() {
+        check(
+            "let f: fn(Int) = g",
+            expect![[r#"
+                Root@0..18
+                  VariableDef@0..18
+                    LetKw@0..3 "let"
+                    Whitespace@3..4 " "
+                    Ident@4..5 "f"
+                    Colon@5..6 ":"
+                    Whitespace@6..7 " "
+                    TypeRef@7..15
+                      FnKw@7..9 "fn"
+                      LParen@9..10 "("
+                      TypeRef@10..13
+                        Ident@10..13 "Int"
+                      RParen@13..14 ")"
+                      Whitespace@14..15 " "
+                    Equals@15..16 "="
+                    Whitespace@16..17 " "
+                    VariableRef@17..18
+                      Ident@17..18 "g"
+                error at 15..16: expected '->', but found '='"#]],
+        );
+    }
+}
@@ -3,9 +3,12 @@
 mod event;
 mod grammar;
 mod marker;
+#[allow(clippy::module_inception)]
 mod parser;
+mod reparse;
 mod sink;
 mod source;
 
 pub use marker::CompletedMarker;
 pub use parser::{parse, ParseError, Parser};
+pub use reparse::{incremental_reparse, TextEdit};
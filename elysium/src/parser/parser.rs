@@ -7,7 +7,8 @@ use crate::{
     Parse,
 };
 use event::Event;
-use marker::Marker;
+use marker::{CompletedMarker, Marker};
+use rowan::TextRange;
 use std::mem;
 
 use sink::Sink;
@@ -16,7 +17,9 @@ use source::Source;
 mod parse_error;
 pub use parse_error::ParseError;
 
-const RECOVERY_SET: [TokenKind; 1] = [TokenKind::LetKw];
+/// Tokens that errors leave in place rather than wrapping in an error node, as they are likely
+/// to start whatever the parser should move on to.
+const RECOVERY_SET: [TokenKind; 3] = [TokenKind::LetKw, TokenKind::FnKw, TokenKind::LBrace];
 
 /// Parses an input∏ string into a full AST representation.
 pub fn parse(input: &str) -> Parse {
@@ -37,6 +40,10 @@ pub struct Parser<'t, 'input> {
     source: Source<'t, 'input>,
     events: Vec<Event>,
     expected_kinds: Vec<TokenKind>,
+    /// Whether newlines end statements here, as they do everywhere but inside parentheses.
+    newlines_significant: bool,
+    /// Where the last error was reported, so that recovering from it does not report more there.
+    last_error: Option<TextRange>,
 }
 
 impl<'t, 'input> Parser<'t, 'input> {
@@ -45,6 +52,8 @@ impl<'t, 'input> Parser<'t, 'input> {
             source,
             events: Vec::new(),
             expected_kinds: Vec::new(),
+            newlines_significant: true,
+            last_error: None,
         }
     }
 
@@ -61,8 +70,17 @@ impl<'t, 'input> Parser<'t, 'input> {
         Marker::new(pos)
     }
 
-    pub(crate) fn parse(mut self) -> Vec<Event> {
-        grammar::root(&mut self);
+    pub(crate) fn parse(self) -> Vec<Event> {
+        self.parse_with(grammar::root)
+    }
+
+    /// Parses a lone block, for reparsing one in isolation.
+    pub(crate) fn parse_block(self) -> Vec<Event> {
+        self.parse_with(grammar::block)
+    }
+
+    fn parse_with(mut self, entry: fn(&mut Self) -> CompletedMarker) -> Vec<Event> {
+        entry(&mut self);
         self.events
     }
 
@@ -77,10 +95,37 @@ impl<'t, 'input> Parser<'t, 'input> {
     }
 
     pub(crate) fn at(&mut self, kind: TokenKind) -> bool {
-        self.expected_kinds.push(kind);
+        if !self.expected_kinds.contains(&kind) {
+            self.expected_kinds.push(kind);
+        }
         self.peek() == Some(kind)
     }
 
+    /// Like [`Parser::at`], for a newline before the next token.
+    pub(crate) fn at_newline(&mut self) -> bool {
+        if !self.expected_kinds.contains(&TokenKind::Newline) {
+            self.expected_kinds.push(TokenKind::Newline);
+        }
+        self.newline_before()
+    }
+
+    /// Whether a newline comes before the next token, where newlines are significant.
+    pub(crate) fn newline_before(&mut self) -> bool {
+        self.newlines_significant && self.source.newline_before()
+    }
+
+    /// Runs `f` with newlines significant or not, restoring the previous setting afterwards.
+    pub(crate) fn with_newlines<T>(
+        &mut self,
+        significant: bool,
+        f: impl FnOnce(&mut Self) -> T,
+    ) -> T {
+        let outer = mem::replace(&mut self.newlines_significant, significant);
+        let result = f(self);
+        self.newlines_significant = outer;
+        result
+    }
+
     pub(crate) fn expect(&mut self, kind: TokenKind) {
         if self.at(kind) {
             self.bump();
@@ -90,6 +135,18 @@ impl<'t, 'input> Parser<'t, 'input> {
     }
 
     pub(crate) fn error(&mut self) {
+        self.error_without_recovery();
+
+        if !self.at_set(&RECOVERY_SET) && !self.at_end() {
+            let m = self.start();
+            self.bump();
+            m.complete(self, SyntaxKind::Error);
+        }
+    }
+
+    /// Reports an error at the current token but leaves it for the caller to parse. Only the
+    /// first error at a token is reported.
+    pub(crate) fn error_without_recovery(&mut self) {
         let current_token = self.source.peek_token();
 
         let (found, range) = if let Some(Token { kind, range, .. }) = current_token {
@@ -103,20 +160,20 @@ impl<'t, 'input> Parser<'t, 'input> {
             )
         };
 
+        let expected = mem::take(&mut self.expected_kinds);
+        if self.last_error.replace(range) == Some(range) {
+            return;
+        }
+
         self.events.push(Event::Error(ParseError {
-            expected: mem::take(&mut self.expected_kinds),
+            expected,
             found,
             range,
         }));
-
-        if !self.at_set(&RECOVERY_SET) && !self.at_end() {
-            let m = self.start();
-            self.bump();
-            m.complete(self, SyntaxKind::Error);
-        }
     }
 
-    fn at_set(&mut self, set: &[TokenKind]) -> bool {
+    /// Whether the next token is in `set`, without recording any as expected.
+    pub(crate) fn at_set(&mut self, set: &[TokenKind]) -> bool {
         self.peek().is_some_and(|k| set.contains(&k))
     }
 
@@ -1,44 +1,57 @@
 //! Parser errors.
 
-use crate::lexer::TokenKind;
+use crate::{lexer::TokenKind, Diagnostic};
 use rowan::TextRange;
-use std::fmt;
+use std::fmt::{self, Write};
 
 #[derive(Debug, PartialEq, Clone, Eq)]
 pub struct ParseError {
-    pub(super) expected: Vec<TokenKind>,
-    pub(super) found: Option<TokenKind>,
-    pub(super) range: TextRange,
+    pub(in crate::parser) expected: Vec<TokenKind>,
+    pub(in crate::parser) found: Option<TokenKind>,
+    pub(in crate::parser) range: TextRange,
 }
 
-impl fmt::Display for ParseError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "error at {}..{}: expected ",
-            u32::from(self.range.start()),
-            u32::from(self.range.end()),
-        )?;
+impl ParseError {
+    fn message(&self) -> String {
+        let mut message = String::from("expected ");
 
         let num_expected = self.expected.len();
         let first = |idx| idx == 0;
         let last = |idx| idx == num_expected - 1;
 
         for (idx, expected_kind) in self.expected.iter().enumerate() {
-            if first(idx) {
-                write!(f, "{expected_kind}")?;
+            let _ = if first(idx) {
+                write!(message, "{expected_kind}")
             } else if last(idx) {
-                write!(f, " or {expected_kind}")?;
+                write!(message, " or {expected_kind}")
             } else {
-                write!(f, ", {expected_kind}")?;
-            }
+                write!(message, ", {expected_kind}")
+            };
         }
 
         if let Some(found) = self.found {
-            write!(f, ", but found {found}")?;
+            let _ = write!(message, ", but found {found}");
         }
 
-        Ok(())
+        message
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "error at {}..{}: {}",
+            u32::from(self.range.start()),
+            u32::from(self.range.end()),
+            self.message()
+        )
+    }
+}
+
+impl From<ParseError> for Diagnostic {
+    fn from(error: ParseError) -> Self {
+        Self::error("E0001", error.message(), error.range)
     }
 }
 
@@ -59,7 +72,25 @@ mod tests {
             },
         };
 
-        assert_eq!(format!("{error}"), output)
+        assert_eq!(format!("{error}"), output);
+    }
+
+    #[test]
+    fn into_diagnostic() {
+        let error = ParseError {
+            expected: vec![TokenKind::RParen],
+            found: Some(TokenKind::LetKw),
+            range: TextRange::new(3.into(), 6.into()),
+        };
+
+        assert_eq!(
+            Diagnostic::from(error),
+            Diagnostic::error(
+                "E0001",
+                "expected ')', but found 'let'",
+                TextRange::new(3.into(), 6.into())
+            )
+        );
     }
 
     #[test]
@@ -79,7 +110,7 @@ mod tests {
             None,
             5..6,
             "error at 5..6: expected ')'",
-        )
+        );
     }
 
     #[test]
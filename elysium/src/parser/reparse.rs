@@ -0,0 +1,450 @@
+//! Incremental reparsing: after an edit only the token or block containing it is relexed and
+//! reparsed, and the rest of the old tree is reused as-is.
+
+use super::{
+    parser::{parse, ParseError, Parser},
+    sink::Sink,
+    source::Source,
+};
+use crate::{
+    lexer::Lexer,
+    syntax::{ElysiumLanguage, SyntaxKind, SyntaxNode, SyntaxToken},
+    Parse,
+};
+use rowan::{GreenToken, Language, NodeOrToken, TextRange, TextSize};
+use std::{iter, ops::Range};
+
+/// A replacement of the `delete` range of a text by `insert`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub delete: TextRange,
+    pub insert: String,
+}
+
+impl TextEdit {
+    pub fn replace(delete: TextRange, insert: impl Into<String>) -> Self {
+        Self {
+            delete,
+            insert: insert.into(),
+        }
+    }
+
+    pub fn insert(offset: TextSize, insert: impl Into<String>) -> Self {
+        Self::replace(TextRange::empty(offset), insert)
+    }
+
+    pub fn delete(delete: TextRange) -> Self {
+        Self::replace(delete, String::new())
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `delete` is out of bounds of `text` or not on character boundaries.
+    pub fn apply(&self, text: &mut String) {
+        text.replace_range(Range::<usize>::from(self.delete), &self.insert);
+    }
+}
+
+/// Parses the text of `old` with `edit` applied, giving the same result as a full [`parse`] of
+/// the edited text.
+///
+/// An edit within an identifier, number, whitespace or comment that stays a single token of the
+/// same kind only replaces that token. Otherwise the innermost block around the edit whose braces
+/// still balance is relexed and reparsed on its own, and failing that everything is parsed again.
+///
+/// # Panics
+///
+/// Panics if `edit.delete` is out of bounds of the text of `old` or not on character boundaries.
+#[allow(clippy::needless_pass_by_value)] // Edits are consumed as they come in from the editor.
+pub fn incremental_reparse(old: &Parse, edit: TextEdit) -> Parse {
+    let root = old.syntax();
+
+    // A tree that ends before the edit does not hold all of the text, so cannot be reused.
+    if edit.delete.end() > root.text_range().end() {
+        let mut text = root.to_string();
+        edit.apply(&mut text);
+        return parse(&text);
+    }
+
+    reparse_token(old, &root, &edit)
+        .or_else(|| reparse_block(old, &root, &edit))
+        .unwrap_or_else(|| {
+            let mut text = root.to_string();
+            edit.apply(&mut text);
+            parse(&text)
+        })
+}
+
+fn reparse_token(old: &Parse, root: &SyntaxNode, edit: &TextEdit) -> Option<Parse> {
+    let token = root.covering_element(edit.delete).into_token()?;
+    if !matches!(
+        token.kind(),
+        SyntaxKind::Ident
+            | SyntaxKind::Number
+            | SyntaxKind::Whitespace
+            | SyntaxKind::Newline
+            | SyntaxKind::Comment
+    ) {
+        return None;
+    }
+
+    let old_range = token.text_range();
+    let mut text = token.text().to_owned();
+    TextEdit::replace(edit.delete - old_range.start(), edit.insert.as_str()).apply(&mut text);
+
+    // The previous token is relexed too, in case the new text would run into it, and so is the
+    // rest of the line, as a float such as `1.5e+3` can take in several of the tokens after.
+    // Lexing after a line break starts afresh whatever came before.
+    let prev = token.prev_token();
+    let mut next = Vec::new();
+    let mut following = token.next_token();
+    while let Some(t) = following {
+        following = (t.kind() != SyntaxKind::Newline)
+            .then(|| t.next_token())
+            .flatten();
+        next.push(t);
+    }
+    let window: String = prev
+        .iter()
+        .map(SyntaxToken::text)
+        .chain(iter::once(text.as_str()))
+        .chain(next.iter().map(SyntaxToken::text))
+        .collect();
+    let expected: Vec<_> = prev
+        .iter()
+        .map(|t| (t.kind(), t.text_range().len()))
+        .chain(iter::once((token.kind(), TextSize::of(&text))))
+        .chain(next.iter().map(|t| (t.kind(), t.text_range().len())))
+        .collect();
+    let relexed: Vec<_> = Lexer::new(&window)
+        .map(|t| (SyntaxKind::from(t.kind), t.range.len()))
+        .collect();
+    if relexed != expected {
+        return None;
+    }
+
+    let new_range = TextRange::at(old_range.start(), TextSize::of(&text));
+    let green_node = token.replace_with(GreenToken::new(
+        ElysiumLanguage::kind_to_raw(token.kind()),
+        &text,
+    ));
+
+    // The tree keeps its shape, so errors found at the token stay, only at its new range.
+    let inner = old
+        .errors
+        .iter()
+        .filter(|error| error.found.is_some() && error.range == old_range)
+        .map(|error| ParseError {
+            range: new_range,
+            ..error.clone()
+        })
+        .collect();
+
+    Some(splice(old, green_node, old_range, new_range, inner))
+}
+
+fn reparse_block(old: &Parse, root: &SyntaxNode, edit: &TextEdit) -> Option<Parse> {
+    let covering = match root.covering_element(edit.delete) {
+        NodeOrToken::Node(node) => node,
+        NodeOrToken::Token(token) => token.parent()?,
+    };
+
+    covering
+        .ancestors()
+        .filter(|node| node.kind() == SyntaxKind::BlockExpr)
+        .find_map(|block| {
+            let braces = |kind| {
+                block
+                    .children_with_tokens()
+                    .filter_map(NodeOrToken::into_token)
+                    .find(|token| token.kind() == kind)
+            };
+            let l_brace = braces(SyntaxKind::LBrace)?;
+            let r_brace = braces(SyntaxKind::RBrace)?;
+            if edit.delete.start() < l_brace.text_range().end()
+                || edit.delete.end() > r_brace.text_range().start()
+            {
+                return None;
+            }
+
+            let old_range = block.text_range();
+            let mut text = block.to_string();
+            TextEdit::replace(edit.delete - old_range.start(), edit.insert.as_str())
+                .apply(&mut text);
+
+            let Parse { green_node, errors } = parse_block(&text)?;
+
+            // Errors at the opening brace come from the statement before, so only those after
+            // it are replaced.
+            let l_brace_end = l_brace.text_range().end();
+            let new_end = old_range.start() + TextSize::of(&text);
+            let inner = errors
+                .into_iter()
+                .map(|error| ParseError {
+                    range: error.range + old_range.start(),
+                    ..error
+                })
+                .collect();
+
+            Some(splice(
+                old,
+                block.replace_with(green_node),
+                TextRange::new(l_brace_end, old_range.end()),
+                TextRange::new(l_brace_end, new_end),
+                inner,
+            ))
+        })
+}
+
+/// Parses `text` as a block on its own, provided that gives the same block as parsing it in
+/// context would: the braces balance, and the block ends at its last closing brace. Not even
+/// trivia may follow that brace, as a comment or whitespace there could run on into the text
+/// after the block.
+fn parse_block(text: &str) -> Option<Parse> {
+    let tokens: Vec<_> = Lexer::new(text).collect();
+
+    let last = tokens.last()?;
+    if last.range.end() != TextSize::of(text) || SyntaxKind::from(last.kind) != SyntaxKind::RBrace {
+        return None;
+    }
+
+    let mut depth = 0_u32;
+    let mut non_trivia = tokens.iter().filter(|token| !token.kind.is_trivia());
+    let first = non_trivia.next()?;
+    if first.range.start() != 0.into() || SyntaxKind::from(first.kind) != SyntaxKind::LBrace {
+        return None;
+    }
+    for token in iter::once(first).chain(non_trivia) {
+        if depth == 0 && token.range.start() != 0.into() {
+            // The first brace was closed before the end.
+            return None;
+        }
+        match SyntaxKind::from(token.kind) {
+            SyntaxKind::LBrace => depth += 1,
+            SyntaxKind::RBrace => depth -= 1,
+            _ => {}
+        }
+    }
+    if depth != 0 {
+        return None;
+    }
+
+    let events = Parser::new(Source::new(&tokens)).parse_block();
+    let parse = Sink::new(&tokens, events).finish();
+
+    let block = parse.syntax();
+    let closed = block
+        .children_with_tokens()
+        .filter(|element| !element.kind().is_trivia())
+        .last()
+        .is_some_and(|element| element.kind() == SyntaxKind::RBrace);
+
+    (closed && block.text_range().len() == TextSize::of(text)).then_some(parse)
+}
+
+/// Builds the new parse from the spliced tree, replacing the errors `old` reported within
+/// `old_range` by `inner` and shifting those after it. Errors overlapping `old_range` are dropped,
+/// as reparsing it finds them again if they still apply.
+fn splice(
+    old: &Parse,
+    green_node: rowan::GreenNode,
+    old_range: TextRange,
+    new_range: TextRange,
+    inner: Vec<ParseError>,
+) -> Parse {
+    let root = SyntaxNode::new_root(green_node.clone());
+    // `last_token` gives up at an empty node at the end, so every token is looked at.
+    let last_token_range = root
+        .descendants_with_tokens()
+        .filter_map(NodeOrToken::into_token)
+        .last()
+        .map(|token| token.text_range());
+
+    let before = old
+        .errors
+        .iter()
+        .filter(|error| error.found.is_some() && error.range.end() <= old_range.start())
+        .cloned();
+    let after = old.errors.iter().filter_map(|error| {
+        let range = if error.found.is_none() {
+            // Errors at the end of input point at the last token, wherever that now is.
+            last_token_range?
+        } else if error.range.start() >= old_range.end() {
+            error.range - old_range.end() + new_range.end()
+        } else {
+            return None;
+        };
+        Some(ParseError {
+            range,
+            ..error.clone()
+        })
+    });
+
+    Parse {
+        green_node,
+        errors: before.chain(inner).chain(after).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::Rng;
+
+    fn range(start: u32, end: u32) -> TextRange {
+        TextRange::new(start.into(), end.into())
+    }
+
+    /// Checks reparsing `text` after `edit` against parsing the edited text from scratch, giving
+    /// back the old and the reparsed trees.
+    #[track_caller]
+    fn check(text: &str, edit: TextEdit) -> (Parse, Parse) {
+        let old = parse(text);
+        let mut edited = text.to_owned();
+        edit.apply(&mut edited);
+
+        let reparsed = incremental_reparse(&old, edit);
+        assert_eq!(reparsed.debug_tree(), parse(&edited).debug_tree());
+        (old, reparsed)
+    }
+
+    /// Whether the `index`th top-level child is the same green node in both trees, rather than
+    /// an equal copy.
+    fn shares_child(old: &Parse, new: &Parse, index: usize) -> bool {
+        let green = |parse: &Parse| {
+            let child = parse.syntax().children().nth(index).unwrap();
+            std::ptr::from_ref::<rowan::GreenNodeData>(&child.green())
+        };
+        green(old) == green(new)
+    }
+
+    #[test]
+    fn edit_within_identifier() {
+        let (old, new) = check(
+            "let a = 1\nlet b = abc",
+            TextEdit::replace(range(19, 20), "xyz"),
+        );
+
+        assert!(shares_child(&old, &new, 0));
+    }
+
+    #[test]
+    fn identifier_becoming_keyword() {
+        check("le a", TextEdit::insert(2.into(), "t"));
+    }
+
+    #[test]
+    fn tokens_merging() {
+        check("a b", TextEdit::delete(range(1, 2)));
+        check("a 1", TextEdit::replace(range(1, 2), "b"));
+    }
+
+    #[test]
+    fn edit_within_block() {
+        let (old, new) = check(
+            "fn f() { 1 }\nfn g(x) { x + 1 }",
+            TextEdit::replace(range(25, 28), "* (2 - x)"),
+        );
+
+        assert!(shares_child(&old, &new, 0));
+    }
+
+    #[test]
+    fn edit_within_nested_block() {
+        check(
+            "fn f() {\n  let a = { 1 }\n  a\n}",
+            TextEdit::insert(20.into(), " + 2"),
+        );
+    }
+
+    #[test]
+    fn unbalancing_a_block() {
+        check("fn f() { 1 }\n2", TextEdit::insert(9.into(), "{"));
+        check("fn f() { { 1 } }\n2", TextEdit::delete(range(13, 14)));
+    }
+
+    #[test]
+    fn errors_are_moved() {
+        let (_, reparsed) = check(
+            "let a = { let 1 = 2 }\nlet b =",
+            TextEdit::insert(9.into(), "0;"),
+        );
+
+        assert_eq!(
+            reparsed
+                .errors()
+                .iter()
+                .map(|error| error.range)
+                .collect::<Vec<_>>(),
+            [range(16, 17), range(30, 31)]
+        );
+    }
+
+    #[test]
+    fn errors_within_block_are_replaced() {
+        check(
+            "let a = { let 1 = 2 }\n3",
+            TextEdit::replace(range(14, 15), "b"),
+        );
+        check(
+            "let a = { let b = 2 }\n3",
+            TextEdit::replace(range(14, 15), "1"),
+        );
+    }
+
+    #[test]
+    fn edit_after_unrecognized_characters() {
+        check(
+            "== {--fn al#p lse{ \"s\"u1b&a& \\wh.<2.55rue {<con#fn ",
+            TextEdit::replace(range(43, 45), "else "),
+        );
+    }
+
+    #[test]
+    fn number_becoming_float() {
+        check("1x.5e+3 + 1", TextEdit::delete(range(1, 2)));
+    }
+
+    #[test]
+    fn comment_running_out_of_block() {
+        check("{ f \"}# a\" } b", TextEdit::delete(range(4, 5)));
+    }
+
+    #[test]
+    fn randomized_edits_match_full_reparse() {
+        const TEXTS: [&str; 4] = [
+            "let a = 1\nfn f(x, y) {\n  let z = { x * y }\n  z + a # sum\n}\nf(a, { 2 })",
+            "fn g() { { { 1 } } }\n-g() / (3 + 4)",
+            "let x = {\n  let y = 10\n  { y - 1 }\n}\n",
+            "a = 1.5 @ \"s\\\"\" {\n  f(2e+1, b)\\ # c\n}",
+        ];
+        const INSERTS: [&str; 23] = [
+            "", "", "1", "23", "a", "bc", " ", "\n", "{", "}", "{ 1 }", "+", "-", "(", ")", ",",
+            "let ", "fn ", "#", "\"", ".", "\\", ";",
+        ];
+
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        for text in TEXTS {
+            let mut text = text.to_owned();
+            let mut parse = parse(&text);
+
+            for _ in 0..300 {
+                let start = rng.below(text.len() + 1);
+                let end = (start + rng.below(4)).min(text.len());
+                let edit = TextEdit::replace(
+                    range(u32::try_from(start).unwrap(), u32::try_from(end).unwrap()),
+                    INSERTS[rng.below(INSERTS.len())],
+                );
+
+                edit.apply(&mut text);
+                parse = incremental_reparse(&parse, edit.clone());
+
+                assert_eq!(
+                    parse.debug_tree(),
+                    super::parse(&text).debug_tree(),
+                    "after {edit:?}, giving {text:?}"
+                );
+            }
+        }
+    }
+}
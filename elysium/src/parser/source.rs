@@ -21,11 +21,11 @@ impl<'t, 'input> Source<'t, 'input> {
         Some(token)
     }
 
-    pub fn peek_token(&mut self) -> Option<&Token> {
+    pub fn peek_token(&mut self) -> Option<&Token<'input>> {
         self.eat_trivia();
         self.peek_token_raw()
     }
-    fn peek_token_raw(&self) -> Option<&Token> {
+    fn peek_token_raw(&self) -> Option<&Token<'input>> {
         self.tokens.get(self.cursor)
     }
 
@@ -35,7 +35,17 @@ impl<'t, 'input> Source<'t, 'input> {
     }
 
     fn peek_kind_raw(&self) -> Option<TokenKind> {
-        self.peek_token_raw().map(|Token { kind, .. }| (*kind))
+        self.peek_token_raw().map(|Token { kind, .. }| *kind)
+    }
+
+    /// Whether a newline separates the next token from the one before it.
+    pub fn newline_before(&mut self) -> bool {
+        self.eat_trivia();
+        self.tokens[..self.cursor]
+            .iter()
+            .rev()
+            .take_while(|token| token.kind.is_trivia())
+            .any(|token| token.kind == TokenKind::Newline)
     }
 
     fn eat_trivia(&mut self) {
@@ -0,0 +1,475 @@
+//! Long-lived evaluation state shared across several inputs, e.g. lines of a REPL or the files
+//! given to `elysium run`.
+
+use crate::{
+    ast,
+    eval::{Evaluator, Overflow, Value},
+    hir::{self, Binding, Database, HirStmt, InferenceResult, Resolver, Ty, TypeChecker},
+    parser::parse,
+    Diagnostic, Parse,
+};
+use rowan::TextRange;
+use std::{mem, ops::Range};
+
+/// Result of feeding one input to a [`Session`].
+#[derive(Debug)]
+pub struct Evaluation {
+    pub parse: Parse,
+    /// Indices into [`Session::stmts`] of the statements lowered from this input.
+    pub stmts: Range<usize>,
+    /// Values of the expression statements that were evaluated, in order.
+    pub values: Vec<Value>,
+    /// The static type of each of [`Evaluation::values`].
+    pub types: Vec<Ty>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Accumulates HIR, variable bindings and diagnostics across inputs.
+#[derive(Debug, Default)]
+pub struct Session {
+    db: Database,
+    stmts: Vec<HirStmt>,
+    resolver: Resolver,
+    checker: TypeChecker,
+    evaluator: Evaluator,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Session {
+    /// Sets how integer overflow is handled, [`Overflow::Checked`] by default.
+    #[must_use]
+    pub fn with_overflow(mut self, overflow: Overflow) -> Self {
+        self.evaluator = mem::take(&mut self.evaluator).with_overflow(overflow);
+        self
+    }
+
+    /// Parses, validates, lowers, type checks, folds and evaluates `input` on top of the
+    /// existing state.
+    ///
+    /// Inputs with parse or validation errors are not lowered, and inputs with name resolution
+    /// or type errors are not evaluated or remembered. Evaluation stops at the first runtime error,
+    /// keeping any bindings made by earlier statements but forgetting the failing statement and
+    /// those after it.
+    pub fn eval(&mut self, input: &str) -> Evaluation {
+        self.process(input, true)
+    }
+
+    /// Does everything [`Session::eval`] does but evaluate, so that the statements of `input`
+    /// are remembered for later inputs to refer to, but leave variables without values.
+    pub fn check(&mut self, input: &str) -> Evaluation {
+        self.process(input, false)
+    }
+
+    fn process(&mut self, input: &str, evaluate: bool) -> Evaluation {
+        let parse = parse(input);
+        let syntax = parse.syntax();
+
+        let mut diagnostics = parse.diagnostics();
+        diagnostics.extend(
+            ast::validation::validate(&syntax)
+                .into_iter()
+                .map(Diagnostic::from),
+        );
+
+        let start = self.stmts.len();
+        let mut values = Vec::new();
+        let mut types = Vec::new();
+
+        if let Some(root) =
+            ast::Root::cast(syntax).filter(|_| !diagnostics.iter().any(Diagnostic::is_error))
+        {
+            let stmts: Vec<_> = root
+                .stmts()
+                .filter_map(|stmt| self.db.lower_stmt(stmt))
+                .collect();
+
+            let snapshot = (self.resolver.clone(), self.checker.clone());
+            let resolve_errors = self.resolver.resolve(&mut self.db, &stmts);
+
+            if resolve_errors.is_empty() {
+                let inference = self.checker.infer(&self.db, &stmts);
+
+                if inference.errors().is_empty() {
+                    types = expr_types(&stmts, &inference);
+                    let warnings = hir::fold(&mut self.db, &stmts, self.evaluator.overflow());
+                    diagnostics.extend(warnings.into_iter().map(Diagnostic::from));
+                    self.stmts.extend(stmts);
+                    if evaluate {
+                        if let Some(failed) = self.run(start, &mut values, &mut diagnostics) {
+                            let dropped = self.stmts.split_off(failed);
+                            self.resolver = snapshot.0;
+                            self.resolver.declare(&self.db, &self.stmts[start..]);
+                            self.checker.forget(&dropped);
+                        }
+                    }
+                    types.truncate(values.len());
+                } else {
+                    (self.resolver, self.checker) = snapshot;
+                    diagnostics.extend(inference.errors().iter().cloned().map(Diagnostic::from));
+                }
+            } else {
+                (self.resolver, self.checker) = snapshot;
+                diagnostics.extend(resolve_errors.into_iter().map(Diagnostic::from));
+            }
+        }
+
+        self.diagnostics.extend(diagnostics.iter().cloned());
+
+        Evaluation {
+            parse,
+            stmts: start..self.stmts.len(),
+            values,
+            types,
+            diagnostics,
+        }
+    }
+
+    /// Evaluates the statements from `start` on, returning the index of the one that failed.
+    fn run(
+        &mut self,
+        start: usize,
+        values: &mut Vec<Value>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> Option<usize> {
+        for (idx, stmt) in self.stmts.iter().enumerate().skip(start) {
+            match self.evaluator.eval_stmt(&self.db, stmt) {
+                Ok(Some(value)) => values.push(value),
+                Ok(None) => {}
+                Err(error) => {
+                    diagnostics.push(error.to_diagnostic(self.stmt_range(stmt)));
+                    return Some(idx);
+                }
+            }
+        }
+        None
+    }
+
+    /// Where to report a runtime error raised by `stmt`: the expression it evaluates.
+    fn stmt_range(&self, stmt: &HirStmt) -> TextRange {
+        let source_map = &self.db.source_map;
+        let range = match stmt {
+            HirStmt::VariableDef { value, .. } => source_map.expr_range(*value),
+            HirStmt::FnDef(function) => source_map.fn_range(*function),
+            HirStmt::Expr(expr) => source_map.expr_range(*expr),
+        };
+        range.unwrap_or_default()
+    }
+
+    /// Top-level bindings that currently hold a value, with their types, sorted by name.
+    pub fn bindings(&self) -> Vec<(&str, Value, Ty)> {
+        let mut bindings: Vec<_> = self
+            .resolver
+            .top_level()
+            .filter_map(|(name, binding)| match binding {
+                Binding::Local(local) => Some((
+                    name,
+                    self.evaluator.global(local)?,
+                    self.checker.global(local)?,
+                )),
+                Binding::Fn(function) => {
+                    Some((name, Value::Fn(function), self.checker.fn_ty(function)?))
+                }
+            })
+            .collect();
+        bindings.sort_unstable_by_key(|(name, ..)| *name);
+        bindings
+    }
+
+    /// Every statement lowered so far, across all inputs.
+    pub fn stmts(&self) -> &[HirStmt] {
+        &self.stmts
+    }
+
+    pub const fn database(&self) -> &Database {
+        &self.db
+    }
+
+    /// Every diagnostic reported so far, across all inputs.
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
+
+    /// Forgets all accumulated state, but not how overflow is handled.
+    pub fn reset(&mut self) {
+        *self = Self::default().with_overflow(self.evaluator.overflow());
+    }
+}
+
+/// Types of the expression statements in `stmts`, in order.
+fn expr_types(stmts: &[HirStmt], inference: &InferenceResult) -> Vec<Ty> {
+    stmts
+        .iter()
+        .filter_map(|stmt| match stmt {
+            HirStmt::Expr(expr) => Some(inference[*expr].clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::RuntimeError;
+
+    fn range(start: u32, end: u32) -> TextRange {
+        TextRange::new(start.into(), end.into())
+    }
+
+    #[test]
+    fn crlf_source_renders_diagnostics_at_their_lines() {
+        let input = "let a = 1\r\nlet b = a +\r\n  c\r\nlet d = e\r\n";
+        let evaluation = Session::default().check(input);
+        let renderer = crate::diagnostic::Renderer::new("<input>", input);
+        let output: Vec<_> = evaluation
+            .diagnostics
+            .iter()
+            .map(|diagnostic| renderer.render(diagnostic))
+            .collect();
+
+        expect_test::expect![[r"
+            error[E0003]: cannot find `c` in this scope
+             --> <input>:3:3
+              |
+            3 |   c
+              |   ^
+            error[E0003]: cannot find `e` in this scope
+             --> <input>:4:9
+              |
+            4 | let d = e
+              |         ^"]]
+        .assert_eq(&output.join("\n"));
+    }
+
+    #[test]
+    fn bindings_persist_across_inputs() {
+        let mut session = Session::default();
+
+        assert!(session.eval("let a = 1").values.is_empty());
+        assert_eq!(session.eval("a + 1").values, [Value::Int(2)]);
+    }
+
+    #[test]
+    fn stmts_accumulate_across_inputs() {
+        let mut session = Session::default();
+
+        assert_eq!(session.eval("let a = 1\nlet b = 2").stmts, 0..2);
+        assert_eq!(session.eval("a + b").stmts, 2..3);
+        assert_eq!(session.stmts().len(), 3);
+    }
+
+    #[test]
+    fn bindings_are_sorted_by_name() {
+        let mut session = Session::default();
+        session.eval("let b = 2\nlet a = 1");
+
+        assert_eq!(
+            session.bindings(),
+            [("a", Value::Int(1), Ty::Int), ("b", Value::Int(2), Ty::Int)]
+        );
+    }
+
+    #[test]
+    fn input_with_errors_is_not_evaluated() {
+        let mut session = Session::default();
+        let evaluation = session.eval("let a = (1");
+
+        assert_eq!(evaluation.stmts, 0..0);
+        assert_eq!(evaluation.diagnostics.len(), 1);
+        assert_eq!(evaluation.diagnostics[0].code, "E0001");
+        assert!(session.bindings().is_empty());
+    }
+
+    #[test]
+    fn runtime_error_keeps_earlier_bindings() {
+        let mut session = Session::default();
+        let evaluation = session.eval("let a = 1\nlet b = a / 0\nlet c = 3");
+
+        assert_eq!(
+            evaluation.diagnostics,
+            [RuntimeError::DivisionByZero.to_diagnostic(range(18, 23))]
+        );
+        assert_eq!(session.bindings(), [("a", Value::Int(1), Ty::Int)]);
+    }
+
+    #[test]
+    fn runtime_error_forgets_later_stmts() {
+        let mut session = Session::default();
+        assert_eq!(
+            session.eval("let a = 1\nlet b = a / 0\nlet c = 3").stmts,
+            0..1
+        );
+
+        let evaluation = session.eval("c");
+        assert_eq!(evaluation.diagnostics.len(), 1);
+        assert_eq!(evaluation.diagnostics[0].code, "E0003");
+        assert_eq!(
+            session.eval("let b = a\nlet c = b\nc").values,
+            [Value::Int(1)]
+        );
+    }
+
+    #[test]
+    fn runtime_error_restores_shadowed_bindings() {
+        let mut session = Session::default();
+        session.eval("let a = 1");
+        session.eval("let a = true\nlet b = f()\nlet c = b / 0\nlet a = 2\nfn f() { 3 }");
+
+        assert_eq!(
+            session.bindings(),
+            [
+                ("a", Value::Bool(true), Ty::Bool),
+                ("b", Value::Int(3), Ty::Int)
+            ]
+        );
+        assert_eq!(session.eval("f").diagnostics[0].code, "E0003");
+    }
+
+    #[test]
+    fn diagnostics_accumulate_across_inputs() {
+        let mut session = Session::default();
+        session.eval("1 / 0");
+        session.eval("2 / 0");
+
+        let warning = Diagnostic::warning(
+            "W0001",
+            "this will divide by zero when evaluated",
+            range(0, 5),
+        );
+        let error = RuntimeError::DivisionByZero.to_diagnostic(range(0, 5));
+        assert_eq!(
+            session.diagnostics(),
+            [warning.clone(), error.clone(), warning, error]
+        );
+    }
+
+    #[test]
+    fn fns_resolve_across_inputs() {
+        let mut session = Session::default();
+        session.eval("let a = 1\nfn add(x) { x + a }");
+
+        assert_eq!(session.eval("add(2)").values, [Value::Int(3)]);
+    }
+
+    #[test]
+    fn checked_input_is_remembered_but_not_evaluated() {
+        let mut session = Session::default();
+        let evaluation = session.check("let a = 1 / 0\nfn f(x) { x + a }\nf(1)");
+
+        assert_eq!(evaluation.stmts, 0..3);
+        assert!(evaluation.values.is_empty());
+        let codes = |evaluation: Evaluation| {
+            evaluation
+                .diagnostics
+                .iter()
+                .map(|diagnostic| diagnostic.code)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(codes(evaluation), ["W0001"]);
+        assert_eq!(codes(session.check("f(true)")), ["E0006"]);
+        // `a` was never given a value.
+        let names: Vec<_> = session.bindings().iter().map(|(name, ..)| *name).collect();
+        assert_eq!(names, ["f"]);
+    }
+
+    #[test]
+    fn input_with_resolve_errors_is_not_evaluated_or_remembered() {
+        let mut session = Session::default();
+        let evaluation = session.eval("let a = 1\nlet b = c");
+
+        assert_eq!(evaluation.stmts, 0..0);
+        assert_eq!(
+            evaluation.diagnostics,
+            [Diagnostic::error(
+                "E0003",
+                "cannot find `c` in this scope",
+                range(18, 19)
+            )]
+        );
+        assert!(session.bindings().is_empty());
+        assert_eq!(session.eval("a").diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn values_come_with_their_types() {
+        let mut session = Session::default();
+        let evaluation = session.eval("1 + 2\nlet a = true\n1.5");
+
+        assert_eq!(evaluation.values, [Value::Int(3), Value::Float(1.5)]);
+        assert_eq!(evaluation.types, [Ty::Int, Ty::Float]);
+    }
+
+    #[test]
+    fn types_persist_across_inputs() {
+        let mut session = Session::default();
+        session.eval("fn double(x) { x * 2 }");
+
+        let evaluation = session.eval("let a = double(4)\na");
+        assert_eq!(evaluation.types, [Ty::Int]);
+        assert_eq!(session.eval("double(true)").diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn input_with_type_errors_is_not_evaluated_or_remembered() {
+        let mut session = Session::default();
+        let evaluation = session.eval("let a = 1\nlet b = a + true");
+
+        assert_eq!(evaluation.stmts, 0..0);
+        assert!(evaluation.values.is_empty());
+        assert_eq!(
+            evaluation.diagnostics,
+            [Diagnostic::error(
+                "E0006",
+                "expected `Int`, found `Bool`",
+                range(22, 26)
+            )]
+        );
+        assert!(session.bindings().is_empty());
+
+        // `a` was forgotten along with its type, so it can be defined again as anything.
+        session.eval("let a = true");
+        assert_eq!(session.bindings(), [("a", Value::Bool(true), Ty::Bool)]);
+    }
+
+    #[test]
+    fn overflow_handling_is_configurable() {
+        let mut session = Session::default().with_overflow(Overflow::Wrapping);
+        let input = "let a = 9223372036854775807\na + 1";
+        assert_eq!(session.eval(input).values, [Value::Int(i64::MIN)]);
+
+        // Resetting keeps the configuration.
+        session.reset();
+        assert_eq!(session.eval(input).values, [Value::Int(i64::MIN)]);
+
+        let mut session = Session::default();
+        let evaluation = session.eval(input);
+        assert!(evaluation.values.is_empty());
+        assert_eq!(evaluation.diagnostics[0].code, "E0005");
+    }
+
+    #[test]
+    fn folding_only_warns_about_overflow_that_fails() {
+        let input = "9223372036854775807 + 1";
+
+        let evaluation = Session::default()
+            .with_overflow(Overflow::Wrapping)
+            .eval(input);
+        assert_eq!(evaluation.values, [Value::Int(i64::MIN)]);
+        assert!(evaluation.diagnostics.is_empty());
+
+        let evaluation = Session::default().eval(input);
+        let codes: Vec<_> = evaluation.diagnostics.iter().map(|d| d.code).collect();
+        assert_eq!(codes, ["W0001", "E0005"]);
+    }
+
+    #[test]
+    fn reset_forgets_everything() {
+        let mut session = Session::default();
+        session.eval("let a = 1");
+        session.eval("b");
+        session.reset();
+
+        assert!(session.bindings().is_empty());
+        assert!(session.stmts().is_empty());
+        assert!(session.diagnostics().is_empty());
+    }
+}
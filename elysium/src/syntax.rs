@@ -3,10 +3,12 @@
 use crate::lexer::TokenKind;
 use num_derive::{FromPrimitive, ToPrimitive};
 use num_traits::{FromPrimitive, ToPrimitive};
+use rowan::TextRange;
 
 pub type SyntaxNode = rowan::SyntaxNode<ElysiumLanguage>;
 pub type SyntaxToken = rowan::SyntaxToken<ElysiumLanguage>;
 pub type SyntaxElement = rowan::SyntaxElement<ElysiumLanguage>;
+pub type SyntaxNodePtr = rowan::ast::SyntaxNodePtr<ElysiumLanguage>;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum ElysiumLanguage {}
@@ -26,15 +28,43 @@ impl rowan::Language for ElysiumLanguage {
 #[derive(Debug, Copy, Clone, PartialEq, FromPrimitive, ToPrimitive, Eq, Hash, PartialOrd, Ord)]
 pub enum SyntaxKind {
     Whitespace,
+    Newline,
     FnKw,
     LetKw,
+    IfKw,
+    ElseKw,
+    WhileKw,
+    LoopKw,
+    BreakKw,
+    ContinueKw,
+    TrueKw,
+    FalseKw,
     Ident,
     Number,
+    Float,
+    String,
     Plus,
     Minus,
     Star,
     Slash,
     Equals,
+    PlusEq,
+    MinusEq,
+    StarEq,
+    SlashEq,
+    EqEq,
+    BangEq,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    AmpAmp,
+    PipePipe,
+    Bang,
+    Comma,
+    Semicolon,
+    Colon,
+    Arrow,
     LParen,
     RParen,
     LBrace,
@@ -48,21 +78,90 @@ pub enum SyntaxKind {
     PrefixExpr,
     VariableRef,
     VariableDef,
+    FnDef,
+    ParamList,
+    Param,
+    BlockExpr,
+    CallExpr,
+    ArgList,
+    IfExpr,
+    WhileExpr,
+    LoopExpr,
+    BreakExpr,
+    ContinueExpr,
+    AssignExpr,
+    TypeRef,
+}
+
+impl SyntaxKind {
+    pub const fn is_trivia(self) -> bool {
+        matches!(self, Self::Whitespace | Self::Newline | Self::Comment)
+    }
+}
+
+/// Range of `node` from its first to its last non-trivia token.
+///
+/// The sink attaches trailing whitespace and comments to the preceding node, so
+/// [`SyntaxNode::text_range`] is usually wider than the text a user would point at.
+pub fn trimmed_range(node: &SyntaxNode) -> TextRange {
+    let mut tokens = node
+        .descendants_with_tokens()
+        .filter_map(SyntaxElement::into_token)
+        .filter(|token| !token.kind().is_trivia());
+
+    let Some(first) = tokens.next() else {
+        return node.text_range();
+    };
+    let end = tokens
+        .last()
+        .unwrap_or_else(|| first.clone())
+        .text_range()
+        .end();
+
+    TextRange::new(first.text_range().start(), end)
 }
 
 impl From<TokenKind> for SyntaxKind {
     fn from(token: TokenKind) -> Self {
         match token {
             TokenKind::Whitespace => Self::Whitespace,
+            TokenKind::Newline => Self::Newline,
             TokenKind::FnKw => Self::FnKw,
             TokenKind::LetKw => Self::LetKw,
+            TokenKind::IfKw => Self::IfKw,
+            TokenKind::ElseKw => Self::ElseKw,
+            TokenKind::WhileKw => Self::WhileKw,
+            TokenKind::LoopKw => Self::LoopKw,
+            TokenKind::BreakKw => Self::BreakKw,
+            TokenKind::ContinueKw => Self::ContinueKw,
+            TokenKind::TrueKw => Self::TrueKw,
+            TokenKind::FalseKw => Self::FalseKw,
             TokenKind::Ident => Self::Ident,
             TokenKind::Number => Self::Number,
+            TokenKind::Float => Self::Float,
+            TokenKind::String => Self::String,
             TokenKind::Plus => Self::Plus,
             TokenKind::Minus => Self::Minus,
             TokenKind::Star => Self::Star,
             TokenKind::Slash => Self::Slash,
             TokenKind::Equals => Self::Equals,
+            TokenKind::PlusEq => Self::PlusEq,
+            TokenKind::MinusEq => Self::MinusEq,
+            TokenKind::StarEq => Self::StarEq,
+            TokenKind::SlashEq => Self::SlashEq,
+            TokenKind::EqEq => Self::EqEq,
+            TokenKind::BangEq => Self::BangEq,
+            TokenKind::Less => Self::Less,
+            TokenKind::LessEq => Self::LessEq,
+            TokenKind::Greater => Self::Greater,
+            TokenKind::GreaterEq => Self::GreaterEq,
+            TokenKind::AmpAmp => Self::AmpAmp,
+            TokenKind::PipePipe => Self::PipePipe,
+            TokenKind::Bang => Self::Bang,
+            TokenKind::Comma => Self::Comma,
+            TokenKind::Semicolon => Self::Semicolon,
+            TokenKind::Colon => Self::Colon,
+            TokenKind::Arrow => Self::Arrow,
             TokenKind::LParen => Self::LParen,
             TokenKind::RParen => Self::RParen,
             TokenKind::LBrace => Self::LBrace,
@@ -0,0 +1,97 @@
+//! Runs the `elysium` binary on programs given through standard input.
+
+use elysium::eval::MAX_CALL_DEPTH;
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+fn elysium(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_elysium"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn unrecognized_characters_fail() {
+    let input = "let a = 1\nlet b = a @ 2\nundefined_thing + true\n";
+    for command in ["check", "run"] {
+        let output = elysium(&[command, "-"], input);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(!output.status.success(), "{command} succeeded");
+        assert!(stderr.contains("unrecognized token"), "{stderr}");
+        assert!(output.stdout.is_empty());
+    }
+}
+
+#[test]
+fn stdin_given_twice_is_rejected() {
+    let output = elysium(&["run", "-", "-"], "1\n");
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(!output.status.success());
+    assert!(
+        stderr.starts_with("error: `-` given more than once\n"),
+        "{stderr}"
+    );
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn run_crlf_and_tabs() {
+    let output = elysium(&["run", "-"], "fn f(x) {\r\n\tx * 2\r\n}\r\nf(21)\r\n");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "42\n");
+}
+
+#[test]
+fn run_recursion_up_to_the_call_depth_limit() {
+    let depth = MAX_CALL_DEPTH - 1;
+    let input = format!("fn f(n) {{ if n == 0 {{ 0 }} else {{ 1 + f(n - 1) }} }}\nf({depth})\n");
+    let output = elysium(&["run", "-"], &input);
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("{depth}\n")
+    );
+}
+
+#[test]
+fn run_skips_unit_values() {
+    let output = elysium(&["run", "-"], "let i = 0\nwhile i < 3 { i += 1 }\n{}\ni\n");
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "3\n");
+}
+
+#[test]
+fn repl_names_type_variables() {
+    let output = elysium(&["repl"], "fn id(x) { x }\nid\n:env\n");
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success());
+    assert!(stdout.contains("<fn> : fn('a) -> 'a\n"), "{stdout}");
+    assert!(stdout.contains("id : fn('a) -> 'a = <fn>\n"), "{stdout}");
+    assert!(!stdout.contains('?'), "{stdout}");
+}
+
+#[test]
+fn repl_prints_hir_as_dump_does() {
+    let input = "let a = 1\nfn f(x) { x + a }\n";
+    let dump = elysium(&["dump", "--stage", "hir", "-"], input);
+    let repl = elysium(&["repl"], &format!(":hir\n{input}"));
+    let stdout = String::from_utf8(repl.stdout).unwrap().replace("==> ", "");
+    assert!(repl.status.success());
+    assert_eq!(
+        stdout,
+        format!("hir on\n{}", String::from_utf8(dump.stdout).unwrap())
+    );
+}
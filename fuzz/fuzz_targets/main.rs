@@ -9,6 +9,7 @@ fuzz_target!(|data: &[u8]| {
         let syntax = parse.syntax();
         let _validation_errs = ast::validation::validate(&syntax);
         let root = ast::Root::cast(syntax).unwrap();
-        let (_database, _stmts) = hir::lower(root);
+        let (mut database, stmts) = hir::lower(&root);
+        let _resolve_errs = hir::resolve(&mut database, &stmts);
     }
 });